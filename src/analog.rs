@@ -0,0 +1,420 @@
+//! Debounce inputs read through an analog-to-digital converter.
+//!
+//! A popular way to read many buttons with a single pin is a resistor
+//! ladder: each button pulls the shared line to a different voltage, so
+//! one ADC channel can tell which (if any) is pressed.  The raw readings
+//! are just as noisy as any other input, and in addition the line passes
+//! through intermediate voltages as contacts make and break.
+//!
+//! The [`Ladder`](Ladder) decoder maps each reading to a button by the
+//! voltage [`Band`](Band) it falls in, then debounces the decoded button
+//! identity (including "no button") by requiring the same result for a
+//! number of consecutive polls.
+//!
+//! Other inputs have more than two meaningful levels without being a
+//! keypad, like a rotary position sender or a multi-level sense line.
+//! The [`MultiLevel`](MultiLevel) classifier splits the whole range of
+//! readings into contiguous bands and debounces the band index.
+
+use embedded_hal::adc::{Channel, OneShot};
+
+/// A range of readings that decodes to a particular button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Band<Id> {
+    /// The lowest reading in the band.
+    pub min: u16,
+
+    /// The highest reading in the band.
+    pub max: u16,
+
+    /// The button identified by readings in this band.
+    pub id: Id,
+}
+
+impl<Id> Band<Id> {
+    /// Create a band covering the inclusive range of readings
+    /// `min..=max`.
+    #[inline]
+    pub const fn new(min: u16, max: u16, id: Id) -> Self {
+        Band { min, max, id }
+    }
+
+    #[inline(always)]
+    fn contains(&self, reading: u16) -> bool {
+        self.min <= reading && reading <= self.max
+    }
+}
+
+/// An event from a [`Ladder`](Ladder) decoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event<Id> {
+    /// The given button was pressed.
+    Pressed(Id),
+
+    /// The given button was released.
+    Released(Id),
+}
+
+/// A resistor-ladder keypad decoder.
+///
+/// Readings that don't fall in any of the configured bands are decoded
+/// as "no button", so the idle voltage (and the gaps between bands)
+/// need not be listed.  A decoded identity must be seen for `max_count`
+/// consecutive polls before it is accepted, which should be configured
+/// just like [`MAX_COUNT`](crate::Debounce#associatedconstant.MAX_COUNT).
+///
+/// If the decoded button changes directly from one to another, the
+/// `Released` event for the first is reported on one poll and the
+/// `Pressed` event for the second on the next.
+pub struct Ladder<Id, const N: usize> {
+    bands: [Band<Id>; N],
+    settle: Settle<Option<Id>>,
+    stable: Option<Id>,
+}
+
+impl<Id: Copy + Eq, const N: usize> Ladder<Id, N> {
+    /// Create a new decoder for the given bands, with no button
+    /// pressed.
+    ///
+    /// If bands overlap, the first matching band wins.
+    #[inline]
+    pub const fn new(bands: [Band<Id>; N], max_count: u8) -> Self {
+        Ladder {
+            bands,
+            settle: Settle::new(max_count, None),
+            stable: None,
+        }
+    }
+
+    /// Decode a single reading without debouncing it.
+    pub fn classify(&self, reading: u16) -> Option<Id> {
+        self.bands
+            .iter()
+            .find(|band| band.contains(reading))
+            .map(|band| band.id)
+    }
+
+    /// Update the decoder with a new reading.
+    ///
+    /// This should be done on a regular basis at roughly the frequency
+    /// used in the calculation of `max_count`.
+    pub fn update(&mut self, reading: u16) -> Option<Event<Id>> {
+        let id = self.settle.update(self.classify(reading))?;
+
+        if id == self.stable {
+            return None;
+        }
+
+        match self.stable {
+            Some(released) => {
+                self.stable = None;
+                Some(Event::Released(released))
+            }
+            None => {
+                self.stable = id;
+                self.stable.map(Event::Pressed)
+            }
+        }
+    }
+
+    /// Read the ADC channel and update the decoder with the result.
+    ///
+    /// This blocks until the conversion is complete.
+    pub fn poll<Adc, ADC, Pin>(
+        &mut self,
+        adc: &mut Adc,
+        pin: &mut Pin,
+    ) -> Result<Option<Event<Id>>, Adc::Error>
+    where
+        Adc: OneShot<ADC, u16, Pin>,
+        Pin: Channel<ADC>,
+    {
+        let reading = nb::block!(adc.read(pin))?;
+        Ok(self.update(reading))
+    }
+
+    /// The currently pressed button, if any.
+    #[inline]
+    pub fn pressed(&self) -> Option<Id> {
+        self.stable
+    }
+}
+
+/// A multi-level input classifier.
+///
+/// The range of readings is split into `N` contiguous bands, each
+/// given by the lowest reading in it.  Band `i` covers the readings
+/// from its own start up to (but not including) the start of band
+/// `i + 1`, the last band covers everything above its start, and the
+/// first band also covers anything below its start.  So every reading
+/// falls in exactly one band, identified by its index.
+///
+/// The band index must be seen for `max_count` consecutive polls before
+/// it is accepted, which should be configured just like
+/// [`MAX_COUNT`](crate::Debounce#associatedconstant.MAX_COUNT).  Unlike
+/// the [`Ladder`](Ladder), a change from one band to another is
+/// reported directly, without passing through "no band".
+///
+/// A reading that sits right on a boundary between bands may decode to
+/// one band and then the other on alternate polls, never settling.  To
+/// avoid this, create the classifier
+/// [`with_hysteresis()`](#method.with_hysteresis), giving a margin for
+/// each boundary.
+pub struct MultiLevel<const N: usize> {
+    starts: [u16; N],
+    margins: [u16; N],
+    last: Option<usize>,
+    settle: Settle<usize>,
+    stable: Option<usize>,
+}
+
+impl<const N: usize> MultiLevel<N> {
+    /// Create a new classifier for bands starting at the given
+    /// readings, which must be in ascending order.
+    ///
+    /// The band is unknown until the first one is accepted.
+    #[inline]
+    pub const fn new(starts: [u16; N], max_count: u8) -> Self {
+        MultiLevel::with_hysteresis(starts, [0; N], max_count)
+    }
+
+    /// Create a new classifier with hysteresis at each boundary.
+    ///
+    /// The margin for each band applies to the boundary at its start,
+    /// so the margin for the first band is unused.  Once a reading
+    /// falls in one band, the next reading must go past the boundary by
+    /// more than the margin before it falls in the neighboring band.
+    #[inline]
+    pub const fn with_hysteresis(starts: [u16; N], margins: [u16; N], max_count: u8) -> Self {
+        MultiLevel {
+            starts,
+            margins,
+            last: None,
+            settle: Settle::new(max_count, 0),
+            stable: None,
+        }
+    }
+
+    /// Classify a single reading without debouncing it.
+    ///
+    /// This ignores any hysteresis margins.
+    pub fn classify(&self, reading: u16) -> usize {
+        self.starts
+            .iter()
+            .skip(1)
+            .take_while(|&&start| start <= reading)
+            .count()
+    }
+
+    /// Classify a reading, applying hysteresis against the last band.
+    fn classify_near(&mut self, reading: u16) -> usize {
+        let mut band = self.classify(reading);
+
+        if let Some(last) = self.last {
+            if band > last && reading < self.starts[band].saturating_add(self.margins[band]) {
+                band -= 1;
+            } else if band < last
+                && reading.saturating_add(self.margins[band + 1]) >= self.starts[band + 1]
+            {
+                band += 1;
+            }
+        }
+
+        self.last = Some(band);
+        band
+    }
+
+    /// Update the classifier with a new reading.
+    ///
+    /// Returns the index of the new band when it changes.  This should
+    /// be done on a regular basis at roughly the frequency used in the
+    /// calculation of `max_count`.
+    pub fn update(&mut self, reading: u16) -> Option<usize> {
+        let band = self.classify_near(reading);
+        let band = self.settle.update(band)?;
+
+        if Some(band) == self.stable {
+            return None;
+        }
+
+        self.stable = Some(band);
+        self.stable
+    }
+
+    /// Read the ADC channel and update the classifier with the result.
+    ///
+    /// This blocks until the conversion is complete.
+    pub fn poll<Adc, ADC, Pin>(
+        &mut self,
+        adc: &mut Adc,
+        pin: &mut Pin,
+    ) -> Result<Option<usize>, Adc::Error>
+    where
+        Adc: OneShot<ADC, u16, Pin>,
+        Pin: Channel<ADC>,
+    {
+        let reading = nb::block!(adc.read(pin))?;
+        Ok(self.update(reading))
+    }
+
+    /// The index of the current band, if one has been accepted.
+    #[inline]
+    pub fn band(&self) -> Option<usize> {
+        self.stable
+    }
+}
+
+/// Counts consecutive identical classifications.
+struct Settle<T> {
+    max_count: u8,
+    count: u8,
+    candidate: T,
+}
+
+impl<T: Copy + Eq> Settle<T> {
+    #[inline]
+    const fn new(max_count: u8, candidate: T) -> Self {
+        Settle {
+            max_count,
+            count: 0,
+            candidate,
+        }
+    }
+
+    /// Note a classification, returning it if it has been seen for
+    /// `max_count` consecutive updates.
+    fn update(&mut self, value: T) -> Option<T> {
+        if value == self.candidate {
+            if self.count < self.max_count {
+                self.count += 1;
+            }
+        } else {
+            self.candidate = value;
+            self.count = 1;
+        }
+
+        if self.count < self.max_count {
+            None
+        } else {
+            Some(self.candidate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Key {
+        Up,
+        Down,
+    }
+
+    const BANDS: [Band<Key>; 2] = [Band::new(0, 100, Key::Up), Band::new(400, 600, Key::Down)];
+
+    #[test]
+    fn press_and_release() {
+        let mut ladder = Ladder::new(BANDS, 3);
+
+        assert_eq!(None, ladder.update(1023));
+        assert_eq!(None, ladder.update(50));
+        assert_eq!(None, ladder.update(1023));
+        assert_eq!(None, ladder.update(50));
+        assert_eq!(None, ladder.update(50));
+        assert_eq!(Some(Event::Pressed(Key::Up)), ladder.update(50));
+        assert_eq!(Some(Key::Up), ladder.pressed());
+        assert_eq!(None, ladder.update(50));
+
+        assert_eq!(None, ladder.update(1023));
+        assert_eq!(None, ladder.update(1023));
+        assert_eq!(Some(Event::Released(Key::Up)), ladder.update(1023));
+        assert_eq!(None, ladder.pressed());
+        assert_eq!(None, ladder.update(1023));
+    }
+
+    #[test]
+    fn direct_change() {
+        let mut ladder = Ladder::new(BANDS, 2);
+
+        assert_eq!(None, ladder.update(500));
+        assert_eq!(Some(Event::Pressed(Key::Down)), ladder.update(500));
+
+        // Passing through the gap between bands is too quick to count.
+        assert_eq!(None, ladder.update(300));
+        assert_eq!(None, ladder.update(20));
+        assert_eq!(Some(Event::Released(Key::Down)), ladder.update(20));
+        assert_eq!(Some(Event::Pressed(Key::Up)), ladder.update(20));
+        assert_eq!(None, ladder.update(20));
+    }
+
+    const STARTS: [u16; 4] = [0, 250, 500, 750];
+
+    #[test]
+    fn classify_levels() {
+        let levels = MultiLevel::new(STARTS, 1);
+
+        assert_eq!(0, levels.classify(0));
+        assert_eq!(0, levels.classify(249));
+        assert_eq!(1, levels.classify(250));
+        assert_eq!(2, levels.classify(749));
+        assert_eq!(3, levels.classify(750));
+        assert_eq!(3, levels.classify(u16::MAX));
+
+        // Below the first start is still the first band.
+        let levels = MultiLevel::new([100, 200], 1);
+        assert_eq!(0, levels.classify(50));
+    }
+
+    #[test]
+    fn debounce_band_index() {
+        let mut levels = MultiLevel::new(STARTS, 3);
+
+        assert_eq!(None, levels.band());
+        assert_eq!(None, levels.update(300));
+        assert_eq!(None, levels.update(300));
+        assert_eq!(Some(1), levels.update(300));
+        assert_eq!(Some(1), levels.band());
+        assert_eq!(None, levels.update(300));
+
+        // Sweeping through a band on the way to another doesn't count.
+        assert_eq!(None, levels.update(600));
+        assert_eq!(None, levels.update(900));
+        assert_eq!(None, levels.update(900));
+        assert_eq!(Some(3), levels.update(900));
+
+        // Nor does a glitch.
+        assert_eq!(None, levels.update(10));
+        assert_eq!(None, levels.update(900));
+        assert_eq!(Some(3), levels.band());
+    }
+
+    #[test]
+    fn hysteresis() {
+        let margins = [0, 10, 20, 10];
+        let mut levels = MultiLevel::with_hysteresis(STARTS, margins, 2);
+
+        // Sitting on a boundary without hysteresis never settles.
+        let mut plain = MultiLevel::new(STARTS, 2);
+        for &reading in [499, 500, 499, 500, 499, 500].iter() {
+            assert_eq!(None, plain.update(reading));
+        }
+
+        assert_eq!(None, levels.update(499));
+        assert_eq!(Some(1), levels.update(500));
+        for &reading in [499, 500, 510, 519, 481].iter() {
+            assert_eq!(None, levels.update(reading));
+        }
+
+        // Going well past the boundary still changes band.
+        assert_eq!(None, levels.update(520));
+        assert_eq!(Some(2), levels.update(520));
+        assert_eq!(None, levels.update(481));
+        assert_eq!(None, levels.update(480));
+        assert_eq!(None, levels.update(479));
+        assert_eq!(Some(1), levels.update(479));
+
+        // The raw classification ignores the margins.
+        assert_eq!(2, levels.classify(500));
+    }
+}
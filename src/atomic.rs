@@ -0,0 +1,435 @@
+//! A debouncer with its state in an atomic.
+//!
+//! The [`Debouncer`](crate::Debouncer) keeps its state in a plain
+//! `UnsafeCell`, and its `Sync` implementation rests on you keeping the
+//! promises made by calling its `unsafe` methods.  When the state fits
+//! in a `u8`, as it almost always does, it can live in an `AtomicU8`
+//! instead.  Then reading the [`AtomicDebounced`](AtomicDebounced) pin
+//! and even polling the [`AtomicDebouncer`](AtomicDebouncer) are safe,
+//! and the `Sync` implementation no longer relies on good behavior.
+//! Only `init()` and `deinit()`, which move the pin in and out, remain
+//! `unsafe`.
+//!
+//! # Multiple cores
+//!
+//! The `AtomicDebouncer` is also the one to use when `poll()` runs on
+//! one core while the debounced pin is read on another, as is common on
+//! the RP2040.  `init()` publishes the pin with a release store of the
+//! state, which `poll()` reads with acquire ordering before touching
+//! the pin, and the debounced level is a single byte that is always
+//! read and written atomically.  `init()` and `deinit()` should be
+//! called while the other core is not yet (or no longer) polling.
+//!
+//! On targets with atomic compare-and-swap on bytes, each poll updates
+//! the state with a compare-and-swap, so concurrent polls each count
+//! toward the integrator.  Targets with only atomic loads and stores,
+//! such as the Cortex-M0+ cores of the RP2040, can't do that, so there
+//! each poll updates the state inside a
+//! [`critical_section`](https://docs.rs/critical-section), and the
+//! `critical-section` feature is required.  The `rp2040` feature turns
+//! on both it and `atomic`.  The RP2040 HAL's critical section takes a
+//! hardware spinlock, so polls on both cores still each count.
+
+use core::cell::UnsafeCell;
+use core::convert::Infallible;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use embedded_hal::digital::v2::InputPin;
+
+use crate::{bad_config, Debounce, DebounceExt, Edge, InitError, Level, PollError};
+
+#[cfg(all(not(target_has_atomic = "8"), not(feature = "critical-section")))]
+compile_error!(
+    "the `atomic` feature needs the `critical-section` feature on targets without compare-and-swap"
+);
+
+/// A pin debouncer with atomic state.
+///
+/// ```
+/// # struct PinType;
+/// # impl embedded_hal::digital::v2::InputPin for PinType {
+/// #     type Error = core::convert::Infallible;
+/// #     fn is_high(&self) -> Result<bool, Self::Error> {
+/// #         Ok(true)
+/// #     }
+/// #     fn is_low(&self) -> Result<bool, Self::Error> {
+/// #         Ok(false)
+/// #     }
+/// # }
+/// use unflappable::atomic::AtomicDebouncer;
+/// use unflappable::default::ActiveLow;
+/// static DEBOUNCER: AtomicDebouncer<PinType, ActiveLow> = AtomicDebouncer::new();
+///
+/// # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+/// # let input_pin = PinType;
+/// let debounced_pin = unsafe { DEBOUNCER.init(input_pin) }.unwrap();
+///
+/// // Later, perhaps in an interrupt service routine:
+/// DEBOUNCER.poll()?;
+/// #     Ok(())
+/// # }
+/// ```
+pub struct AtomicDebouncer<Pin, Cfg: Debounce<Storage = u8>> {
+    cfg: PhantomData<Cfg>,
+    pin: UnsafeCell<MaybeUninit<Pin>>,
+    state: AtomicU8,
+}
+
+// The pin is only written by `init()` and `deinit()`, whose callers
+// promise not to overlap them with anything else, and is otherwise
+// shared by reference between callers of `poll()`.
+unsafe impl<Pin: Send + Sync, Cfg: Debounce<Storage = u8>> Sync for AtomicDebouncer<Pin, Cfg> {}
+
+/// An error that arose during deinit.
+pub enum DeinitError<'a, Cfg: Debounce<Storage = u8>> {
+    /// The `AtomicDebouncer` was not initialized.
+    Init,
+
+    /// The provided pin does not match this `AtomicDebouncer`.
+    Pin(AtomicDebounced<'a, Cfg>),
+}
+
+impl<'a, Cfg: Debounce<Storage = u8>> core::fmt::Debug for DeinitError<'a, Cfg> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DeinitError::Init => f.write_str("Init"),
+            DeinitError::Pin(_) => f.write_str("Pin(_)"),
+        }
+    }
+}
+
+impl<Pin: InputPin, Cfg: Debounce<Storage = u8>> AtomicDebouncer<Pin, Cfg> {
+    /// Create a new, uninitialized pin debouncer.
+    #[inline]
+    pub const fn new() -> Self {
+        AtomicDebouncer {
+            cfg: PhantomData,
+            pin: UnsafeCell::new(MaybeUninit::uninit()),
+            state: AtomicU8::new(0),
+        }
+    }
+
+    /// Initialize the pin debouncer for a given input pin.
+    ///
+    /// Returns an error if the `AtomicDebouncer` has already be
+    /// initialized, or if the [`Debounce`](crate::Debounce)
+    /// configuration is invalid.
+    ///
+    /// # Safety
+    ///
+    /// For this call to be safe, you must ensure that it is not run
+    /// concurrently with a call to `init()` or `deinit()`.  It may
+    /// safely race with `poll()`, which reports an error until the
+    /// initialization is complete.
+    #[inline]
+    pub unsafe fn init(&self, pin: Pin) -> Result<AtomicDebounced<'_, Cfg>, InitError<Pin>> {
+        if let Some(message) = Cfg::config_error() {
+            return Err(bad_config(pin, message));
+        }
+
+        if self.state.load(Ordering::Acquire) & Cfg::init_mask() != 0 {
+            return Err(InitError::AlreadyInit(pin));
+        }
+
+        (*self.pin.get()).as_mut_ptr().write(pin);
+
+        let mut new_state = Cfg::init_level();
+        if Cfg::INIT_HIGH {
+            new_state |= Cfg::state_mask();
+        }
+        new_state |= Cfg::init_mask();
+
+        // Publish the pin along with the init flag.
+        self.state.store(new_state, Ordering::Release);
+
+        Ok(AtomicDebounced {
+            cfg: PhantomData,
+            state: &self.state,
+        })
+    }
+
+    /// Poll the pin debouncer.
+    ///
+    /// This should be done on a regular basis at roughly the frequency
+    /// used in the calculation of [`MAX_COUNT`](crate::Debounce#associatedconstant.MAX_COUNT).
+    ///
+    /// Returns the debounced [`Edge`](crate::Edge), if any, that
+    /// resulted from this poll.  Unlike
+    /// [`Debouncer::poll()`](crate::Debouncer#method.poll) this is safe
+    /// to call from anywhere, and concurrent polls each count toward the
+    /// integrator.  On targets without compare-and-swap, that takes a
+    /// critical section (see the [module docs](self)).
+    pub fn poll(&self) -> Result<Edge, PollError<Pin::Error>> {
+        if self.state.load(Ordering::Acquire) & Cfg::init_mask() == 0 {
+            return Err(PollError::Init);
+        }
+
+        let pin_ptr = self.pin.get();
+        // This is safe because we've seen the init flag published after
+        // the pin was written, and the pin is only moved out by
+        // `deinit()`, whose caller promises not to race with us.
+        let pin = unsafe { &*(*pin_ptr).as_ptr() };
+        let low = pin.is_low().map_err(PollError::Pin)?;
+
+        #[cfg(target_has_atomic = "8")]
+        let edge = self.update(!low);
+
+        #[cfg(not(target_has_atomic = "8"))]
+        let edge = self.update_locked(!low);
+
+        Ok(edge)
+    }
+
+    // Step the state with a compare-and-swap, retrying if another poll
+    // got there first.
+    #[cfg(target_has_atomic = "8")]
+    #[inline(always)]
+    fn update(&self, high: bool) -> Edge {
+        let mut edge = Edge::NoChange;
+        let _ = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |state| {
+                let (new_state, new_edge) = Cfg::step(state, high);
+                edge = new_edge;
+                Some(new_state)
+            });
+        edge
+    }
+
+    // Step the state inside a critical section, for targets that can
+    // only load and store atomically.
+    #[cfg(any(test, not(target_has_atomic = "8")))]
+    #[inline(always)]
+    fn update_locked(&self, high: bool) -> Edge {
+        critical_section::with(|_| {
+            let (new_state, edge) = Cfg::step(self.state.load(Ordering::Acquire), high);
+            self.state.store(new_state, Ordering::Release);
+            edge
+        })
+    }
+
+    /// Destroy the debounced pin, returning the original input pin.
+    ///
+    /// You must pass in the debounced pin produced from the call to
+    /// [`init()`](#method.init).  Returns an error if called with an
+    /// `AtomicDebounced` pin not associated with this debouncer.
+    ///
+    /// # Safety
+    ///
+    /// For this method to be safe, you must ensure that it is not run
+    /// concurrently with a call to any other method of this type,
+    /// including `poll()` and `deinit()` itself.
+    #[inline]
+    pub unsafe fn deinit<'a>(
+        &self,
+        pin: AtomicDebounced<'a, Cfg>,
+    ) -> Result<Pin, DeinitError<'a, Cfg>> {
+        if self.state.load(Ordering::Acquire) & Cfg::init_mask() == 0 {
+            return Err(DeinitError::Init);
+        }
+
+        if !core::ptr::eq(&self.state, pin.state) {
+            return Err(DeinitError::Pin(pin));
+        }
+
+        self.state.store(0, Ordering::Release);
+
+        Ok((*self.pin.get()).as_ptr().read())
+    }
+
+    /// Has this debouncer been initialized?
+    ///
+    /// See [`Debouncer::is_initialized()`](crate::Debouncer#method.is_initialized).
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.state.load(Ordering::Acquire) & Cfg::init_mask() != 0
+    }
+}
+
+impl<Pin: InputPin, Cfg: Debounce<Storage = u8>> Default for AtomicDebouncer<Pin, Cfg> {
+    fn default() -> Self {
+        AtomicDebouncer::new()
+    }
+}
+
+/// A debounced pin backed by atomic state.
+pub struct AtomicDebounced<'state, Cfg: Debounce<Storage = u8>> {
+    cfg: PhantomData<Cfg>,
+    state: &'state AtomicU8,
+}
+
+impl<'state, Cfg: Debounce<Storage = u8>> AtomicDebounced<'state, Cfg> {
+    /// The current debounced level.
+    #[inline(always)]
+    pub fn state(&self) -> Level {
+        Level::from(self.state.load(Ordering::Relaxed) & Cfg::state_mask() != 0)
+    }
+}
+
+impl<'state, Cfg: Debounce<Storage = u8>> InputPin for AtomicDebounced<'state, Cfg> {
+    type Error = Infallible;
+
+    #[inline(always)]
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.state().is_high())
+    }
+
+    #[inline(always)]
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.state().is_low())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    extern crate std;
+
+    use super::*;
+
+    use core::sync::atomic::AtomicBool;
+
+    use embedded_hal_mock::pin;
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 3;
+        const INIT_HIGH: bool = false;
+    }
+
+    #[test]
+    fn atomic_simple() {
+        let expectations = [
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::Low),
+            pin::Transaction::get(pin::State::Low),
+            pin::Transaction::get(pin::State::Low),
+        ];
+
+        let debouncer: AtomicDebouncer<_, Cfg> = AtomicDebouncer::new();
+        assert!(matches!(debouncer.poll(), Err(PollError::Init)));
+
+        // It is always safe to init a stack-scoped AtomicDebouncer.
+        let debounced = unsafe { debouncer.init(pin::Mock::new(&expectations)) }.unwrap();
+        assert_eq!(false, debounced.is_high().unwrap());
+
+        assert_eq!(Edge::NoChange, debouncer.poll().unwrap());
+        assert_eq!(Edge::NoChange, debouncer.poll().unwrap());
+        assert_eq!(Edge::Rising, debouncer.poll().unwrap());
+        assert_eq!(true, debounced.is_high().unwrap());
+
+        assert_eq!(Edge::NoChange, debouncer.poll().unwrap());
+        assert_eq!(Edge::NoChange, debouncer.poll().unwrap());
+        assert_eq!(Edge::Falling, debouncer.poll().unwrap());
+        assert_eq!(Level::Low, debounced.state());
+
+        // It is always safe to deinit a stack-scoped AtomicDebouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
+
+    #[test]
+    fn shared_across_threads() {
+        struct Pin(&'static AtomicBool);
+
+        impl InputPin for Pin {
+            type Error = Infallible;
+
+            fn is_high(&self) -> Result<bool, Self::Error> {
+                Ok(self.0.load(Ordering::Relaxed))
+            }
+
+            fn is_low(&self) -> Result<bool, Self::Error> {
+                Ok(!self.0.load(Ordering::Relaxed))
+            }
+        }
+
+        static LEVEL: AtomicBool = AtomicBool::new(true);
+        static DEBOUNCER: AtomicDebouncer<Pin, Cfg> = AtomicDebouncer::new();
+
+        // This is safe since nothing else can see DEBOUNCER yet.
+        let debounced = unsafe { DEBOUNCER.init(Pin(&LEVEL)) }.unwrap();
+
+        std::thread::spawn(|| {
+            for _ in 0..10 {
+                DEBOUNCER.poll().unwrap();
+            }
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(Level::High, debounced.state());
+    }
+
+    struct High;
+
+    impl InputPin for High {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+
+        fn is_low(&self) -> Result<bool, Self::Error> {
+            Ok(false)
+        }
+    }
+
+    struct Slow;
+    impl Debounce for Slow {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 60;
+        const INIT_HIGH: bool = false;
+    }
+
+    // Poll from four threads at once, sixty polls in all, returning the
+    // number of rising edges seen.  Were any update lost, the
+    // integrator would fall short and never rise.
+    fn poll_concurrently(
+        debouncer: &'static AtomicDebouncer<High, Slow>,
+        update: fn(&AtomicDebouncer<High, Slow>, bool) -> Edge,
+    ) -> usize {
+        let threads: std::vec::Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    (0..15)
+                        .filter(|_| update(debouncer, true) == Edge::Rising)
+                        .count()
+                })
+            })
+            .collect();
+
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .sum()
+    }
+
+    #[test]
+    fn concurrent_polls_all_count() {
+        static DEBOUNCER: AtomicDebouncer<High, Slow> = AtomicDebouncer::new();
+
+        // This is safe since nothing else can see DEBOUNCER yet.
+        let debounced = unsafe { DEBOUNCER.init(High) }.unwrap();
+
+        let rises = poll_concurrently(&DEBOUNCER, |debouncer, _| debouncer.poll().unwrap());
+        assert_eq!(1, rises);
+        assert_eq!(Level::High, debounced.state());
+    }
+
+    #[test]
+    fn concurrent_locked_polls_all_count() {
+        static DEBOUNCER: AtomicDebouncer<High, Slow> = AtomicDebouncer::new();
+
+        // This is safe since nothing else can see DEBOUNCER yet.
+        let debounced = unsafe { DEBOUNCER.init(High) }.unwrap();
+
+        let rises = poll_concurrently(&DEBOUNCER, AtomicDebouncer::update_locked);
+        assert_eq!(1, rises);
+        assert_eq!(Level::High, debounced.state());
+    }
+}
@@ -0,0 +1,193 @@
+//! Keep a log of recent events for diagnostics.
+//!
+//! When a device faults in the field, the first question from support
+//! is often what the user actually pressed beforehand.  An
+//! [`AuditLog`](AuditLog) is a fixed-size ring buffer of the last `N`
+//! debounced edges, each with a timestamp and the channel it came from,
+//! shared by as many debouncers as you like.  Once full, each new entry
+//! overwrites the oldest.
+//!
+//! The log can be read back entry by entry, or exported in a compact
+//! binary form of [`ENTRY_LEN`](ENTRY_LEN) bytes per entry, oldest
+//! first:
+//!
+//! | byte | contents                              |
+//! |------|---------------------------------------|
+//! | 0-3  | timestamp, little-endian              |
+//! | 4    | channel                               |
+//! | 5    | edge: `1` for rising, `2` for falling |
+//!
+//! ```
+//! use unflappable::{audit::AuditLog, Edge};
+//!
+//! let mut log: AuditLog<32> = AuditLog::new();
+//! # let (now, edge) = (1234, Edge::Falling);
+//!
+//! // In the poll routine, for each debouncer:
+//! log.record(now, 0, edge);
+//!
+//! // Later, when a fault is reported:
+//! let mut buf = [0; 32 * unflappable::audit::ENTRY_LEN];
+//! let len = log.export(&mut buf);
+//! // send buf[..len] to the support tool
+//! ```
+
+use crate::Edge;
+
+/// The length in bytes of each exported entry.
+pub const ENTRY_LEN: usize = 6;
+
+/// A single entry of an [`AuditLog`](AuditLog).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// The time of the edge, in ticks of your clock.
+    pub timestamp: u32,
+
+    /// The channel the edge came from.
+    pub channel: u8,
+
+    /// The debounced edge.
+    pub edge: Edge,
+}
+
+impl AuditEntry {
+    fn to_bytes(self) -> [u8; ENTRY_LEN] {
+        let timestamp = self.timestamp.to_le_bytes();
+        let edge = match self.edge {
+            Edge::NoChange => 0,
+            Edge::Rising => 1,
+            Edge::Falling => 2,
+        };
+        [
+            timestamp[0],
+            timestamp[1],
+            timestamp[2],
+            timestamp[3],
+            self.channel,
+            edge,
+        ]
+    }
+}
+
+/// A ring buffer of the last `N` debounced edges.
+#[derive(Debug, Clone)]
+pub struct AuditLog<const N: usize> {
+    entries: [Option<AuditEntry>; N],
+    next: usize,
+}
+
+impl<const N: usize> AuditLog<N> {
+    /// Create a new, empty log.
+    #[inline]
+    pub const fn new() -> Self {
+        AuditLog {
+            entries: [None; N],
+            next: 0,
+        }
+    }
+
+    /// Record an edge from a channel at time `timestamp`.
+    ///
+    /// [`Edge::NoChange`](Edge::NoChange) isn't recorded, so the result
+    /// of every poll can be passed straight in.
+    pub fn record(&mut self, timestamp: u32, channel: u8, edge: Edge) {
+        if N == 0 || edge == Edge::NoChange {
+            return;
+        }
+
+        self.entries[self.next] = Some(AuditEntry {
+            timestamp,
+            channel,
+            edge,
+        });
+        self.next = (self.next + 1) % N;
+    }
+
+    /// The number of entries in the log.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.is_some()).count()
+    }
+
+    /// Is the log empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget every entry.
+    #[inline]
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// The entries in the log, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = AuditEntry> + '_ {
+        let (newer, older) = self.entries.split_at(self.next);
+        older.iter().chain(newer.iter()).filter_map(|entry| *entry)
+    }
+
+    /// Export the log into `buf`, returning the number of bytes written.
+    ///
+    /// Entries are written oldest first.  If `buf` can't hold them all,
+    /// only the most recent that fit are written.
+    pub fn export(&self, buf: &mut [u8]) -> usize {
+        let skip = self.len().saturating_sub(buf.len() / ENTRY_LEN);
+
+        let mut len = 0;
+        for entry in self.iter().skip(skip) {
+            buf[len..len + ENTRY_LEN].copy_from_slice(&entry.to_bytes());
+            len += ENTRY_LEN;
+        }
+        len
+    }
+}
+
+impl<const N: usize> Default for AuditLog<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wraps_around() {
+        let mut log: AuditLog<3> = AuditLog::new();
+        assert!(log.is_empty());
+
+        log.record(10, 0, Edge::Falling);
+        log.record(11, 0, Edge::NoChange);
+        log.record(12, 1, Edge::Falling);
+        log.record(20, 0, Edge::Rising);
+        log.record(21, 1, Edge::Rising);
+        assert_eq!(3, log.len());
+
+        let mut timestamps = [0; 3];
+        for (slot, entry) in timestamps.iter_mut().zip(log.iter()) {
+            *slot = entry.timestamp;
+        }
+        assert_eq!([12, 20, 21], timestamps);
+
+        log.clear();
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn export_most_recent() {
+        let mut log: AuditLog<4> = AuditLog::new();
+        log.record(0x0102_0304, 5, Edge::Falling);
+        log.record(0x0a0b_0c0d, 6, Edge::Rising);
+
+        let mut buf = [0; 2 * ENTRY_LEN];
+        assert_eq!(2 * ENTRY_LEN, log.export(&mut buf));
+        assert_eq!([4, 3, 2, 1, 5, 2, 0x0d, 0x0c, 0x0b, 0x0a, 6, 1], buf);
+
+        let mut short = [0; ENTRY_LEN + 1];
+        assert_eq!(ENTRY_LEN, log.export(&mut short));
+        assert_eq!([0x0d, 0x0c, 0x0b, 0x0a, 6, 1, 0], short);
+    }
+}
@@ -0,0 +1,597 @@
+//! Debounce a whole port at once.
+//!
+//! A panel of a dozen buttons doesn't need a dozen
+//! [`Debouncer`](crate::Debouncer)s each reading its own pin.  When the
+//! buttons share a port, the [`DebouncerBank`](DebouncerBank) takes the
+//! whole port word in one read and debounces every bit of it at the
+//! same time, using vertical counters: the bits of each channel's
+//! counter are spread across a few words, so that a handful of bitwise
+//! operations advances every counter in parallel.
+//!
+//! Each channel changes state once it has read the opposite level on
+//! four polls in a row.  Any poll that agrees with the current state
+//! restarts its count.  For a longer window, give the bank more counter
+//! bits: with `BITS` bits per channel, it takes `2^BITS` polls in a
+//! row, at the cost of another word of RAM for each extra bit.
+//!
+//! ```
+//! use unflappable::bank::DebouncerBank;
+//!
+//! // Eight polls in a row, for a noisier set of switches.
+//! static BANK: DebouncerBank<16, 3> = DebouncerBank::new(0xffff);
+//! # assert_eq!(8, DebouncerBank::<16, 3>::MAX_COUNT);
+//! ```
+//!
+//! Channels that don't bounce, such as signals from another
+//! microcontroller, can be marked
+//! [clean](DebouncerBank#method.with_clean_channels), to follow their
+//! samples straight away rather than waiting out the count.
+//!
+//! The port word can be passed in directly, or read from anything
+//! implementing [`PortRead`](PortRead), such as an I/O expander that
+//! returns all of its inputs in a single bus transaction.  Buttons
+//! spread over separate pins can still share a bank: pass them to
+//! [`poll_pins()`](DebouncerBank#method.poll_pins), which samples each
+//! into its bit of the word, so that one set of word operations
+//! debounces them all, rather than a `Debouncer` per pin.
+//!
+//! Individual channels can be read through a [`BankPin`](BankPin),
+//! which implements `InputPin` just like a [`Debounced`](crate::Debounced)
+//! pin.  Logic that looks at combinations of channels, such as chords,
+//! should read them all at once from [`state()`](DebouncerBank#method.state),
+//! or with the `critical-section` feature,
+//! [`snapshot()`](DebouncerBank#method.snapshot), rather than one pin
+//! at a time, which may straddle a poll.
+
+use core::cell::UnsafeCell;
+use core::convert::Infallible;
+
+use embedded_hal::digital::v2::InputPin;
+
+use crate::{Level, Sampler};
+
+/// A source of port words, read a whole port at a time.
+///
+/// Implement this for a GPIO port or an I/O expander driver to let a
+/// [`DebouncerBank`](DebouncerBank) read all of its channels with one
+/// access.  It is also implemented for closures, which is handy for
+/// wrapping a driver method:
+///
+/// ```
+/// # struct Mcp23017;
+/// # impl Mcp23017 {
+/// #     fn read_gpioab(&mut self) -> Result<u16, ()> { Ok(0xffff) }
+/// # }
+/// # let mut expander = Mcp23017;
+/// use unflappable::bank::DebouncerBank;
+///
+/// static BANK: DebouncerBank<16> = DebouncerBank::new(0xffff);
+///
+/// let mut port = || expander.read_gpioab().map(u32::from);
+/// let edges = unsafe { BANK.poll(&mut port) }?;
+/// # Ok::<(), ()>(())
+/// ```
+pub trait PortRead {
+    /// The type of error reading the port.
+    type Error;
+
+    /// Read the raw levels of the port.  Bit `n` of the result is the
+    /// level of channel `n`.
+    fn read_port(&mut self) -> Result<u32, Self::Error>;
+}
+
+impl<F, E> PortRead for F
+where
+    F: FnMut() -> Result<u32, E>,
+{
+    type Error = E;
+
+    #[inline]
+    fn read_port(&mut self) -> Result<u32, Self::Error> {
+        self()
+    }
+}
+
+/// A bank of up to 32 debounced channels.
+///
+/// Each channel has a counter of `BITS` bits, two by default, and
+/// changes state after [`MAX_COUNT`](#associatedconstant.MAX_COUNT)
+/// polls in a row at the opposite level.
+///
+/// Like a [`Debouncer`](crate::Debouncer), this is generally put into a
+/// static and shared between the application and the interrupt service
+/// routine doing the polling.
+///
+/// ```
+/// use unflappable::bank::DebouncerBank;
+///
+/// // Twelve buttons, all initially released (high).
+/// static BANK: DebouncerBank<12> = DebouncerBank::new(0xfff);
+/// # fn read_port() -> u32 { 0xffe }
+///
+/// let start = BANK.pin(0);
+///
+/// // Later, in an interrupt service routine:
+/// let edges = unsafe { BANK.update(read_port()) };
+/// if edges.falling & 1 != 0 {
+///     // The start button was pressed.
+/// }
+/// ```
+pub struct DebouncerBank<const N: usize, const BITS: usize = 2> {
+    state: UnsafeCell<u32>,
+    count: UnsafeCell<[u32; BITS]>,
+    clean: u32,
+}
+
+// We demand particular mutex requirements as documented on the methods
+// marked as unsafe.  They are expected to be enforced statically by
+// the user, outside of the type system.
+unsafe impl<const N: usize, const BITS: usize> Sync for DebouncerBank<N, BITS> {}
+
+/// The transitions resulting from a single poll of a bank.
+///
+/// Each is a mask, with a bit set for each channel that made the
+/// transition.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BankEdges {
+    /// The channels which went from low to high.
+    pub rising: u32,
+
+    /// The channels which went from high to low.
+    pub falling: u32,
+}
+
+impl BankEdges {
+    /// Did any channel change?
+    #[inline]
+    pub const fn any(&self) -> bool {
+        self.rising | self.falling != 0
+    }
+}
+
+impl<const N: usize, const BITS: usize> DebouncerBank<N, BITS> {
+    const MASK: u32 = if N >= 32 { !0 } else { (1 << N) - 1 };
+
+    /// The number of polls in a row at the opposite level it takes a
+    /// channel to change state.
+    pub const MAX_COUNT: u32 = 1 << BITS;
+
+    /// Create a new bank, with the channels starting at the levels given
+    /// by the bits of `initial`.
+    ///
+    /// In a `static`, these checks are made at compile time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 32 channels, or if the counters
+    /// are not between 2 and 4 bits.
+    #[inline]
+    pub const fn new(initial: u32) -> Self {
+        assert!(N <= 32, "a DebouncerBank has at most 32 channels");
+        assert!(
+            BITS >= 2 && BITS <= 4,
+            "a DebouncerBank has 2 to 4 counter bits"
+        );
+
+        DebouncerBank {
+            state: UnsafeCell::new(initial & Self::MASK),
+            count: UnsafeCell::new([0; BITS]),
+            clean: 0,
+        }
+    }
+
+    /// Mark the channels set in `clean` as not needing debouncing.
+    ///
+    /// A clean channel changes state on the first poll that reads the
+    /// opposite level, with no delay, while the rest of the bank is
+    /// debounced as usual.
+    ///
+    /// ```
+    /// use unflappable::bank::DebouncerBank;
+    ///
+    /// // Seven buttons, and a ready line from a coprocessor on channel 7.
+    /// static BANK: DebouncerBank<8> = DebouncerBank::new(0xff).with_clean_channels(1 << 7);
+    /// # assert_eq!(0x80, unsafe { BANK.update(0x7f) }.falling);
+    /// ```
+    #[inline]
+    pub const fn with_clean_channels(mut self, clean: u32) -> Self {
+        self.clean = clean & Self::MASK;
+        self
+    }
+
+    /// Update the bank with a fresh reading of the port.
+    ///
+    /// Bit `n` of `sample` is the raw level of channel `n`; bits beyond
+    /// the channels in the bank are ignored.  Returns the channels that
+    /// changed state on this poll.
+    ///
+    /// # Safety
+    ///
+    /// For this method to be safe, you must ensure that it is not run
+    /// concurrently with itself.  The usual way to do this is to call
+    /// `update()` from a single interrupt service routine.
+    #[inline]
+    pub unsafe fn update(&self, sample: u32) -> BankEdges {
+        self.update_linted(sample)
+    }
+
+    /// Read the port and update the bank with the reading.
+    ///
+    /// Returns the channels that changed state on this poll, or the
+    /// error reading the port, in which case the bank is left as it
+    /// was.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`update()`](#method.update) apply.
+    #[inline]
+    pub unsafe fn poll<Port: PortRead>(&self, port: &mut Port) -> Result<BankEdges, Port::Error> {
+        let sample = port.read_port()?;
+        Ok(self.update_linted(sample))
+    }
+
+    /// Sample each of `pins` and update the bank with the samples.
+    ///
+    /// Pin `n` is sampled into channel `n`.  Returns the channels that
+    /// changed state on this poll, or the first error sampling a pin,
+    /// in which case the bank is left as it was.
+    ///
+    /// ```
+    /// # use embedded_hal::digital::v2::InputPin;
+    /// # struct PinType;
+    /// # impl InputPin for PinType {
+    /// #     type Error = core::convert::Infallible;
+    /// #     fn is_high(&self) -> Result<bool, Self::Error> { Ok(true) }
+    /// #     fn is_low(&self) -> Result<bool, Self::Error> { Ok(false) }
+    /// # }
+    /// use unflappable::bank::DebouncerBank;
+    ///
+    /// static BANK: DebouncerBank<3> = DebouncerBank::new(0b111);
+    /// let mut buttons = [PinType, PinType, PinType];
+    ///
+    /// // In the interrupt service routine:
+    /// let edges = unsafe { BANK.poll_pins(&mut buttons) }?;
+    /// # Ok::<(), core::convert::Infallible>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more pins than channels in the bank.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`update()`](#method.update) apply.
+    #[inline]
+    pub unsafe fn poll_pins<P: Sampler>(&self, pins: &mut [P]) -> Result<BankEdges, P::Error> {
+        assert!(
+            pins.len() <= N,
+            "more pins than channels in the DebouncerBank"
+        );
+
+        let mut sample = 0;
+        for (channel, pin) in pins.iter_mut().enumerate() {
+            if pin.sample()? {
+                sample |= 1 << channel;
+            }
+        }
+        Ok(self.update_linted(sample))
+    }
+
+    // n.b. defined seperately to ensure that we think about unsafety.
+    #[inline(always)]
+    fn update_linted(&self, sample: u32) -> BankEdges {
+        // This is safe because we demand from the caller that this is
+        // an exclusive call.
+        let (state, count) = unsafe { (&mut *self.state.get(), &mut *self.count.get()) };
+
+        // Channels that disagree with their state count up, the rest
+        // are reset to zero.  The carry ripples up through the bits of
+        // the counters.
+        let differs = (sample ^ *state) & Self::MASK & !self.clean;
+        let mut carry = differs;
+        for bit in count.iter_mut() {
+            let next = *bit & carry;
+            *bit = (*bit ^ carry) & differs;
+            carry = next;
+        }
+
+        // The counters roll over to zero on the last poll in a row,
+        // carrying out of the top bit, which is when the state toggles.
+        // Clean channels just follow the sample.
+        let toggle = carry | ((sample ^ *state) & self.clean);
+        *state ^= toggle;
+
+        BankEdges {
+            rising: toggle & *state,
+            falling: toggle & !*state,
+        }
+    }
+
+    /// The current debounced levels of all the channels.
+    ///
+    /// On targets that load a `u32` in one access, this is a coherent
+    /// view of every channel.  On 8- and 16-bit targets, the read may
+    /// be torn by a poll; use [`snapshot()`](#method.snapshot) there.
+    #[inline(always)]
+    pub fn state(&self) -> u32 {
+        let state_ptr = self.state.get();
+        // This is safe since the read is atomic.
+        unsafe { *state_ptr }
+    }
+
+    /// The current debounced levels of all the channels, read inside a
+    /// critical section.
+    ///
+    /// Every channel is captured as of the same poll, even on targets
+    /// where reading the state word takes more than one access.
+    ///
+    /// ```
+    /// use unflappable::bank::DebouncerBank;
+    ///
+    /// static BANK: DebouncerBank<2> = DebouncerBank::new(0b11);
+    ///
+    /// // Both buttons pressed (low) together.
+    /// let chord = BANK.snapshot() & 0b11 == 0;
+    /// # assert!(!chord);
+    /// ```
+    #[cfg(feature = "critical-section")]
+    #[inline]
+    pub fn snapshot(&self) -> u32 {
+        critical_section::with(|_| self.state())
+    }
+
+    /// A debounced view of a single channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel` is not in the bank.
+    #[inline]
+    pub fn pin(&self, channel: usize) -> BankPin<'_> {
+        assert!(channel < N, "no such channel in the DebouncerBank");
+
+        BankPin {
+            state: &self.state,
+            mask: 1 << channel,
+        }
+    }
+}
+
+/// A debounced channel of a [`DebouncerBank`](DebouncerBank).
+#[derive(Clone, Copy)]
+pub struct BankPin<'bank> {
+    state: &'bank UnsafeCell<u32>,
+    mask: u32,
+}
+
+impl<'bank> BankPin<'bank> {
+    /// The current debounced level.
+    #[inline(always)]
+    pub fn state(&self) -> Level {
+        let state_ptr = self.state.get();
+        // This is safe since the read is atomic.
+        let state = unsafe { *state_ptr };
+        Level::from(state & self.mask != 0)
+    }
+}
+
+impl<'bank> InputPin for BankPin<'bank> {
+    type Error = Infallible;
+
+    #[inline(always)]
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.state().is_high())
+    }
+
+    #[inline(always)]
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.state().is_low())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn four_in_a_row() {
+        let bank: DebouncerBank<4> = DebouncerBank::new(0b0011);
+        let pin = bank.pin(0);
+
+        // It is always safe to update a stack-scoped DebouncerBank.
+        let update = |sample| unsafe { bank.update(sample) };
+
+        assert_eq!(BankEdges::default(), update(0b0110));
+        assert_eq!(BankEdges::default(), update(0b0110));
+        assert_eq!(BankEdges::default(), update(0b0110));
+        assert_eq!(true, pin.is_high().unwrap());
+
+        assert_eq!(
+            BankEdges {
+                rising: 0b0100,
+                falling: 0b0001,
+            },
+            update(0b0110)
+        );
+        assert_eq!(Level::Low, pin.state());
+        assert_eq!(0b0110, bank.state());
+
+        assert_eq!(false, update(0b0110).any());
+    }
+
+    #[test]
+    fn wider_counters() {
+        let bank: DebouncerBank<2, 3> = DebouncerBank::new(0b01);
+
+        // It is always safe to update a stack-scoped DebouncerBank.
+        let update = |sample| unsafe { bank.update(sample) };
+
+        for _ in 1..8 {
+            assert_eq!(false, update(0b10).any());
+        }
+        assert_eq!(
+            BankEdges {
+                rising: 0b10,
+                falling: 0b01,
+            },
+            update(0b10)
+        );
+
+        // A bounce restarts the count here too.
+        for sample in [
+            0b00, 0b00, 0b00, 0b10, 0b00, 0b00, 0b00, 0b00, 0b00, 0b00, 0b00,
+        ] {
+            assert_eq!(false, update(sample).any());
+        }
+        assert_eq!(0b10, update(0b00).falling);
+    }
+
+    #[test]
+    fn clean_channels_pass_through() {
+        let bank: DebouncerBank<3> = DebouncerBank::new(0).with_clean_channels(0b1100);
+
+        // It is always safe to update a stack-scoped DebouncerBank.
+        let update = |sample| unsafe { bank.update(sample) };
+
+        assert_eq!(0b100, update(0b111).rising);
+        assert_eq!(0b100, update(0b011).falling);
+        assert_eq!(0b100, update(0b111).rising);
+        assert_eq!(0b011, update(0b111).rising);
+        assert_eq!(0b111, bank.state());
+    }
+
+    #[test]
+    #[should_panic(expected = "2 to 4 counter bits")]
+    fn too_many_counter_bits() {
+        let _: DebouncerBank<1, 5> = DebouncerBank::new(0);
+    }
+
+    #[test]
+    fn bounce_restarts_count() {
+        let bank: DebouncerBank<2> = DebouncerBank::new(0);
+
+        // It is always safe to update a stack-scoped DebouncerBank.
+        let update = |sample| unsafe { bank.update(sample) };
+
+        for sample in [0b01, 0b01, 0b01, 0b00, 0b01, 0b01, 0b01] {
+            assert_eq!(false, update(sample).any());
+        }
+        assert_eq!(0b01, update(0b01).rising);
+        assert_eq!(0b01, bank.state());
+    }
+
+    #[test]
+    fn unused_bits_ignored() {
+        let bank: DebouncerBank<3> = DebouncerBank::new(!0);
+        assert_eq!(0b111, bank.state());
+
+        // It is always safe to update a stack-scoped DebouncerBank.
+        let update = |sample| unsafe { bank.update(sample) };
+
+        for _ in 0..8 {
+            assert_eq!(false, update(0b111).any());
+        }
+        for _ in 0..3 {
+            update(0);
+        }
+        assert_eq!(0b111, update(0).falling);
+    }
+
+    #[test]
+    fn poll_port() {
+        struct Expander {
+            readings: [Result<u32, ()>; 5],
+            reads: usize,
+        }
+
+        impl PortRead for Expander {
+            type Error = ();
+
+            fn read_port(&mut self) -> Result<u32, Self::Error> {
+                self.reads += 1;
+                self.readings[self.reads - 1]
+            }
+        }
+
+        let mut expander = Expander {
+            readings: [Ok(0b10), Ok(0b10), Err(()), Ok(0b10), Ok(0b10)],
+            reads: 0,
+        };
+        let bank: DebouncerBank<2> = DebouncerBank::new(0);
+
+        // It is always safe to poll a stack-scoped DebouncerBank.
+        let mut poll = || unsafe { bank.poll(&mut expander) };
+
+        assert_eq!(Ok(false), poll().map(|edges| edges.any()));
+        assert_eq!(Ok(false), poll().map(|edges| edges.any()));
+        assert_eq!(Err(()), poll());
+        assert_eq!(Ok(false), poll().map(|edges| edges.any()));
+        assert_eq!(Ok(0b10), poll().map(|edges| edges.rising));
+        assert_eq!(5, expander.reads);
+    }
+
+    #[test]
+    #[cfg(feature = "critical-section")]
+    fn snapshot_matches_state() {
+        let bank: DebouncerBank<4> = DebouncerBank::new(0b1010);
+        assert_eq!(0b1010, bank.snapshot());
+
+        for _ in 0..4 {
+            // It is always safe to update a stack-scoped DebouncerBank.
+            unsafe { bank.update(0b0101) };
+        }
+        assert_eq!(0b0101, bank.snapshot());
+        assert_eq!(bank.state(), bank.snapshot());
+    }
+
+    #[test]
+    fn poll_pins() {
+        use embedded_hal_mock::pin;
+
+        let high = pin::Transaction::get(pin::State::High);
+        let low = pin::Transaction::get(pin::State::Low);
+        let mut pins = [
+            pin::Mock::new(&[low.clone(), low.clone(), low.clone(), low.clone()]),
+            pin::Mock::new(&[high.clone(), high.clone(), high.clone(), high]),
+            pin::Mock::new(&[low.clone(), low.clone(), low.clone(), low]),
+        ];
+        let bank: DebouncerBank<3> = DebouncerBank::new(0b101);
+
+        // It is always safe to poll a stack-scoped DebouncerBank.
+        let mut poll = || unsafe { bank.poll_pins(&mut pins) }.unwrap();
+
+        for _ in 0..3 {
+            assert_eq!(false, poll().any());
+        }
+        assert_eq!(
+            BankEdges {
+                rising: 0b010,
+                falling: 0b101,
+            },
+            poll()
+        );
+
+        for pin in pins.iter_mut() {
+            pin.done();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "more pins than channels")]
+    fn poll_too_many_pins() {
+        use embedded_hal_mock::pin;
+
+        let bank: DebouncerBank<1> = DebouncerBank::new(0);
+        let mut pins = [pin::Mock::new(&[]), pin::Mock::new(&[])];
+        // It is always safe to poll a stack-scoped DebouncerBank.
+        let _ = unsafe { bank.poll_pins(&mut pins) };
+    }
+
+    #[test]
+    #[should_panic(expected = "no such channel")]
+    fn pin_out_of_range() {
+        let bank: DebouncerBank<3> = DebouncerBank::new(0);
+        bank.pin(3);
+    }
+}
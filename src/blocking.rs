@@ -0,0 +1,525 @@
+//! Debounce a single reading, blocking.
+//!
+//! Sometimes a pin only needs to be read once, such as a bootloader
+//! checking a "stay in DFU mode" strap at reset.  Setting up a timer
+//! interrupt and a static [`Debouncer`](crate::Debouncer) for that is
+//! overkill.  The [`read_stable()`](read_stable) function just takes a
+//! quick burst of samples, spaced out with a delay, and returns the
+//! debounced level.
+//!
+//! To debounce with a [`Debounce`](crate::Debounce) configuration
+//! instead, and to be told when the pin never settles rather than
+//! getting a best guess, use [`settle_and_read()`](settle_and_read).
+//! Or, if a `Debouncer` is
+//! already set up but there's no interrupt polling it yet, such as
+//! early in boot, [`poll_until_stable()`](poll_until_stable) polls it
+//! in a loop.  With the `embedded-hal-1` feature,
+//! [`debounce_read()`](debounce_read) does the same as
+//! `settle_and_read()` for 1.0 pins and delays.
+//!
+//! Each of these takes an optional timeout, counted in samples, so that
+//! boot code can't hang forever on a broken switch.
+
+use embedded_hal::blocking::delay::DelayUs;
+
+use crate::transition::{self, Params};
+use crate::{Debounce, DebounceExt, Debouncer, Level, PollError, Sampler};
+
+/// An error that arose while settling a pin.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SettleError<PinError> {
+    /// The pin didn't settle within the timeout.
+    TimedOut,
+
+    /// The [`Debounce`](crate::Debounce) configuration is invalid.
+    ///
+    /// With the `debug-config` feature, an invalid configuration panics
+    /// instead.
+    BadConfig,
+
+    /// An error reading the pin.
+    Pin(PinError),
+}
+
+/// Read the debounced level of `pin`, blocking until it is known.
+///
+/// Samples are taken `interval_us` microseconds apart, and integrated
+/// just as the [`Debouncer`](crate::Debouncer) would, starting from the
+/// midpoint.  The level is settled once the integrator reaches either
+/// end, which takes at least `samples / 2` samples.  So that a pin that
+/// never settles can't hang the caller, at most `4 * samples` samples
+/// are taken, after which the level the integrator leans toward is
+/// returned.
+///
+/// ```
+/// # struct Strap;
+/// # impl embedded_hal::digital::v2::InputPin for Strap {
+/// #     type Error = core::convert::Infallible;
+/// #     fn is_high(&self) -> Result<bool, Self::Error> { Ok(false) }
+/// #     fn is_low(&self) -> Result<bool, Self::Error> { Ok(true) }
+/// # }
+/// # struct Delay;
+/// # impl embedded_hal::blocking::delay::DelayUs<u32> for Delay {
+/// #     fn delay_us(&mut self, _: u32) {}
+/// # }
+/// # let (mut strap, mut delay) = (Strap, Delay);
+/// use unflappable::{blocking::read_stable, Level};
+///
+/// if read_stable(&mut strap, &mut delay, 10, 1_000)? == Level::Low {
+///     // stay in the bootloader
+/// }
+/// # Ok::<(), core::convert::Infallible>(())
+/// ```
+pub fn read_stable<S, D>(
+    pin: &mut S,
+    delay: &mut D,
+    samples: u16,
+    interval_us: u32,
+) -> Result<Level, S::Error>
+where
+    S: Sampler,
+    D: DelayUs<u32>,
+{
+    let max = samples.max(1) as u32;
+    let mut midpoint = Midpoint::new(max);
+
+    let settled = settle(
+        || pin.sample(),
+        || delay.delay_us(interval_us),
+        Some(4 * max),
+        |high| midpoint.integrate(high),
+    )?;
+    Ok(settled.unwrap_or_else(|| midpoint.lean()))
+}
+
+/// Read the debounced level of `pin` with the given configuration,
+/// blocking until it settles.
+///
+/// The samples are fed to a [`Debouncer`](crate::Debouncer) with this
+/// configuration, so every setting applies: the algorithm, the counts
+/// and thresholds, and the initial state.  The pin has settled once the
+/// debouncer has, and its level is returned.  Starting from
+/// [`INIT_HIGH`](crate::Debounce#associatedconstant.INIT_HIGH) means a
+/// pin already at the initial level settles at once; to make no
+/// assumption, set
+/// [`INIT_COUNT`](crate::Debounce#associatedconstant.INIT_COUNT)
+/// between the thresholds.
+///
+/// If the pin hasn't settled after `timeout` samples, returns
+/// [`SettleError::TimedOut`](SettleError::TimedOut).  With no timeout,
+/// this blocks until the pin settles.  An invalid configuration returns
+/// [`SettleError::BadConfig`](SettleError::BadConfig).
+///
+/// ```
+/// # struct Strap;
+/// # impl embedded_hal::digital::v2::InputPin for Strap {
+/// #     type Error = core::convert::Infallible;
+/// #     fn is_high(&self) -> Result<bool, Self::Error> { Ok(false) }
+/// #     fn is_low(&self) -> Result<bool, Self::Error> { Ok(true) }
+/// # }
+/// # struct Delay;
+/// # impl embedded_hal::blocking::delay::DelayUs<u32> for Delay {
+/// #     fn delay_us(&mut self, _: u32) {}
+/// # }
+/// # let (mut strap, mut delay) = (Strap, Delay);
+/// use unflappable::{blocking::settle_and_read, default::ActiveLow, Level};
+///
+/// // Sampling every 10ms, as if polled at 100Hz, for at most a second.
+/// let level = settle_and_read::<ActiveLow, _, _>(&mut strap, &mut delay, 10_000, Some(100));
+/// # assert_eq!(Ok(Level::Low), level);
+/// ```
+pub fn settle_and_read<Cfg, S, D>(
+    pin: &mut S,
+    delay: &mut D,
+    interval_us: u32,
+    timeout: Option<u32>,
+) -> Result<Level, SettleError<S::Error>>
+where
+    Cfg: Debounce,
+    S: Sampler,
+    D: DelayUs<u32>,
+{
+    settle_debounced::<Cfg, _>(|| pin.sample(), || delay.delay_us(interval_us), timeout)
+}
+
+/// Read the debounced level of an `embedded-hal` 1.0 `pin` with the
+/// given configuration, blocking until it settles.
+///
+/// This is [`settle_and_read()`](settle_and_read) for the 1.0 traits,
+/// taking a `DelayNs` to space out the samples.  It debounces with every
+/// setting of the configuration in just the same way, and reports the
+/// same errors.  It's just the thing
+/// for configuration DIP switches read once at boot: no statics, and no
+/// interrupt.
+///
+/// ```
+/// # struct Switch;
+/// # impl embedded_hal_1::digital::ErrorType for Switch {
+/// #     type Error = core::convert::Infallible;
+/// # }
+/// # impl embedded_hal_1::digital::InputPin for Switch {
+/// #     fn is_high(&mut self) -> Result<bool, Self::Error> { Ok(true) }
+/// #     fn is_low(&mut self) -> Result<bool, Self::Error> { Ok(false) }
+/// # }
+/// # struct Delay;
+/// # impl embedded_hal_1::delay::DelayNs for Delay {
+/// #     fn delay_ns(&mut self, _: u32) {}
+/// # }
+/// # let (mut switch, mut delay) = (Switch, Delay);
+/// use unflappable::{blocking::debounce_read, default::ActiveHigh, Level};
+///
+/// let level = debounce_read::<ActiveHigh, _, _>(&mut switch, &mut delay, 10_000, Some(100));
+/// # assert_eq!(Ok(Level::High), level);
+/// ```
+#[cfg(feature = "embedded-hal-1")]
+pub fn debounce_read<Cfg, Pin, D>(
+    pin: &mut Pin,
+    delay: &mut D,
+    interval_us: u32,
+    timeout: Option<u32>,
+) -> Result<Level, SettleError<Pin::Error>>
+where
+    Cfg: Debounce,
+    Pin: embedded_hal_1::digital::InputPin,
+    D: embedded_hal_1::delay::DelayNs,
+{
+    settle_debounced::<Cfg, _>(|| pin.is_high(), || delay.delay_us(interval_us), timeout)
+}
+
+/// Poll `debouncer` in a loop until its level is stable.
+///
+/// Polls are made `interval_us` microseconds apart, until the
+/// debouncer has settled at its current level, at which point the
+/// level is returned.  Unlike [`settle_and_read()`](settle_and_read),
+/// this leaves the debouncer ready for regular polling to take over.
+/// If it hasn't settled after `timeout` polls, returns
+/// [`SettleError::TimedOut`](SettleError::TimedOut).
+///
+/// # Safety
+///
+/// The same requirements as
+/// [`Debouncer::poll()`](crate::Debouncer#method.poll) apply.
+pub unsafe fn poll_until_stable<Pin, Cfg, D>(
+    debouncer: &Debouncer<Pin, Cfg>,
+    delay: &mut D,
+    interval_us: u32,
+    timeout: Option<u32>,
+) -> Result<Level, SettleError<PollError<Pin::Error>>>
+where
+    Pin: Sampler,
+    Cfg: Debounce,
+    D: DelayUs<u32>,
+{
+    let mut polled = 0;
+
+    loop {
+        if matches!(timeout, Some(timeout) if polled >= timeout) {
+            return Err(SettleError::TimedOut);
+        }
+        if polled != 0 {
+            delay.delay_us(interval_us);
+        }
+
+        debouncer.poll().map_err(SettleError::Pin)?;
+        polled += 1;
+
+        if debouncer.is_settled() {
+            return Ok(debouncer.debounced().state());
+        }
+    }
+}
+
+// Feed up to `limit` samples to `integrate`, returning the level once
+// it reports one.
+fn settle<E>(
+    mut sample: impl FnMut() -> Result<bool, E>,
+    mut delay: impl FnMut(),
+    limit: Option<u32>,
+    mut integrate: impl FnMut(bool) -> Option<Level>,
+) -> Result<Option<Level>, E> {
+    let mut taken = 0;
+
+    while !matches!(limit, Some(limit) if taken >= limit) {
+        if taken != 0 {
+            delay();
+        }
+
+        if let Some(level) = integrate(sample()?) {
+            return Ok(Some(level));
+        }
+
+        taken += 1;
+    }
+
+    Ok(None)
+}
+
+// Settle a fresh debouncer with the configuration `Cfg`.
+fn settle_debounced<Cfg: Debounce, E>(
+    sample: impl FnMut() -> Result<bool, E>,
+    delay: impl FnMut(),
+    timeout: Option<u32>,
+) -> Result<Level, SettleError<E>> {
+    let debouncer: Debouncer<(), Cfg> = Debouncer::uninit(Cfg::zero());
+    if debouncer.validate_config(()).is_err() {
+        return Err(SettleError::BadConfig);
+    }
+    // A fresh debouncer is never already initialized.
+    let _ = debouncer.init_linted(());
+
+    let integrate = |high| {
+        debouncer.integrate(high);
+        if debouncer.is_settled() {
+            Some(debouncer.debounced().state())
+        } else {
+            None
+        }
+    };
+
+    settle(sample, delay, timeout, integrate)
+        .map_err(SettleError::Pin)?
+        .ok_or(SettleError::TimedOut)
+}
+
+// The integrator of `read_stable()`, starting from the midpoint and
+// settled at either end.
+struct Midpoint {
+    params: Params<u32>,
+    state: u32,
+}
+
+impl Midpoint {
+    fn new(max: u32) -> Self {
+        Midpoint {
+            params: Params::integrator(max << 2),
+            state: (max / 2) << 2,
+        }
+    }
+
+    fn integrate(&mut self, high: bool) -> Option<Level> {
+        let (state, _) = transition::advance(&self.params, self.state, high);
+        self.state = state;
+
+        if state >> 2 == 0 {
+            Some(Level::Low)
+        } else if state >> 2 == self.params.rise_max >> 2 {
+            Some(Level::High)
+        } else {
+            None
+        }
+    }
+
+    // The level the integrator leans toward.
+    fn lean(&self) -> Level {
+        Level::from((self.state >> 2) * 2 >= self.params.rise_max >> 2)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::*;
+
+    struct Samples<'a> {
+        levels: &'a [bool],
+        taken: usize,
+    }
+
+    impl<'a> Sampler for Samples<'a> {
+        type Error = ();
+
+        fn sample(&mut self) -> Result<bool, Self::Error> {
+            let level = self.levels.get(self.taken).copied().ok_or(())?;
+            self.taken += 1;
+            Ok(level)
+        }
+    }
+
+    #[derive(Default)]
+    struct Delay {
+        total_us: u32,
+    }
+
+    impl DelayUs<u32> for Delay {
+        fn delay_us(&mut self, us: u32) {
+            self.total_us += us;
+        }
+    }
+
+    fn read(levels: &[bool], samples: u16) -> (Result<Level, ()>, usize, u32) {
+        let mut pin = Samples { levels, taken: 0 };
+        let mut delay = Delay::default();
+        let level = read_stable(&mut pin, &mut delay, samples, 100);
+        (level, pin.taken, delay.total_us)
+    }
+
+    #[test]
+    fn steady() {
+        assert_eq!((Ok(Level::High), 2, 100), read(&[true; 10], 4));
+        assert_eq!((Ok(Level::Low), 2, 100), read(&[false; 10], 4));
+    }
+
+    #[test]
+    fn bouncy() {
+        let levels = [true, false, true, true, false, true, true];
+        assert_eq!((Ok(Level::High), 7, 600), read(&levels, 6));
+    }
+
+    #[test]
+    fn never_settles() {
+        let levels = [true, false].repeat(8);
+        assert_eq!((Ok(Level::High), 16, 1500), read(&levels, 4));
+    }
+
+    #[test]
+    fn sample_error() {
+        assert_eq!((Err(()), 3, 300), read(&[true, false, true], 8));
+    }
+
+    #[test]
+    fn settle_with_config() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 4;
+            const INIT_HIGH: bool = false;
+            const INIT_COUNT: Option<u8> = Some(2);
+        }
+
+        let settle = |levels: &[bool]| {
+            let mut pin = Samples { levels, taken: 0 };
+            settle_and_read::<Cfg, _, _>(&mut pin, &mut Delay::default(), 100, Some(8))
+        };
+
+        assert_eq!(Ok(Level::Low), settle(&[false; 10]));
+        assert_eq!(Err(SettleError::TimedOut), settle(&[true, false].repeat(5)));
+        assert_eq!(Err(SettleError::Pin(())), settle(&[true]));
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "debug-config",
+        should_panic(expected = "Debounce::MAX_COUNT cannot be zero")
+    )]
+    fn settle_bad_config() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 0;
+            const INIT_HIGH: bool = false;
+        }
+
+        let mut pin = Samples {
+            levels: &[false; 4],
+            taken: 0,
+        };
+        let level = settle_and_read::<Cfg, _, _>(&mut pin, &mut Delay::default(), 100, None);
+        assert_eq!(Err(SettleError::BadConfig), level);
+        assert_eq!(0, pin.taken);
+    }
+
+    #[test]
+    fn settle_from_config() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u16;
+            const MAX_COUNT: u16 = 6;
+            const INIT_HIGH: bool = true;
+            const MAX_COUNT_FALL: u16 = 2;
+        }
+
+        // Starting high, it takes only the fall count to settle low.
+        let mut pin = Samples {
+            levels: &[false; 10],
+            taken: 0,
+        };
+        let level = settle_and_read::<Cfg, _, _>(&mut pin, &mut Delay::default(), 100, None);
+        assert_eq!(Ok(Level::Low), level);
+        assert_eq!(2, pin.taken);
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-hal-1")]
+    fn debounce_read_hal1() {
+        use embedded_hal_1::digital::{ErrorType, InputPin};
+
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 4;
+            const INIT_HIGH: bool = false;
+            const INIT_COUNT: Option<u8> = Some(2);
+        }
+
+        struct Switch<'a>(Samples<'a>);
+        impl<'a> ErrorType for Switch<'a> {
+            type Error = core::convert::Infallible;
+        }
+        impl<'a> InputPin for Switch<'a> {
+            fn is_high(&mut self) -> Result<bool, Self::Error> {
+                Ok(self.0.sample().unwrap())
+            }
+            fn is_low(&mut self) -> Result<bool, Self::Error> {
+                self.is_high().map(|high| !high)
+            }
+        }
+
+        #[derive(Default)]
+        struct DelayNs {
+            total_ns: u32,
+        }
+        impl embedded_hal_1::delay::DelayNs for DelayNs {
+            fn delay_ns(&mut self, ns: u32) {
+                self.total_ns += ns;
+            }
+        }
+
+        let levels = [true, false, true, false, true, true];
+        let mut switch = Switch(Samples {
+            levels: &levels,
+            taken: 0,
+        });
+        let mut delay = DelayNs::default();
+        let level = debounce_read::<Cfg, _, _>(&mut switch, &mut delay, 100, None);
+        assert_eq!(Ok(Level::High), level);
+        assert_eq!(6, switch.0.taken);
+        assert_eq!(5 * 100_000, delay.total_ns);
+    }
+
+    #[test]
+    fn poll_debouncer_until_stable() {
+        use crate::debouncer_uninit;
+
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 2;
+            const INIT_HIGH: bool = true;
+        }
+
+        let levels = [false, false, true, false];
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe {
+            debouncer.init(Samples {
+                levels: &levels,
+                taken: 0,
+            })
+        }
+        .unwrap();
+
+        let mut delay = Delay::default();
+        // It is always safe to poll a stack-scoped Debouncer.
+        let level = unsafe { poll_until_stable(&debouncer, &mut delay, 100, None) };
+        assert_eq!(Some(Level::Low), level.ok());
+        assert_eq!(100, delay.total_us);
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        let level = unsafe { poll_until_stable(&debouncer, &mut delay, 100, Some(1)) };
+        assert!(matches!(level, Err(SettleError::TimedOut)));
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+//! Check the interrupt budget of the poll routine.
+//!
+//! The usual way to poll a [`Debouncer`](crate::Debouncer) is from a
+//! periodic timer interrupt.  During bring-up it's easy to overcommit
+//! that interrupt: a few more inputs, a slower clock, an expander read
+//! over a sluggish bus, and suddenly the poll routine eats most of the
+//! CPU.  An [`IsrBudget`](IsrBudget) describes how much of each poll
+//! period the routine may use, and can check a measured worst-case cost
+//! against it (for instance, a cycle count read from the Cortex-M DWT).
+
+/// The time available to a periodic poll routine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IsrBudget {
+    /// The CPU clock frequency, in hertz.
+    pub cpu_hz: u32,
+
+    /// The frequency the poll routine is called, in hertz.
+    pub poll_hz: u32,
+
+    /// The share of each poll period (in percent) that must be left
+    /// free for everything else.
+    pub headroom_percent: u8,
+}
+
+impl IsrBudget {
+    /// Describe the budget of a poll routine.
+    #[inline]
+    pub const fn new(cpu_hz: u32, poll_hz: u32, headroom_percent: u8) -> Self {
+        IsrBudget {
+            cpu_hz,
+            poll_hz,
+            headroom_percent,
+        }
+    }
+
+    /// The number of CPU cycles in each poll period.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `poll_hz` is zero.
+    #[inline]
+    pub const fn cycles_per_poll(&self) -> u32 {
+        assert!(self.poll_hz != 0, "the poll rate cannot be zero");
+        self.cpu_hz / self.poll_hz
+    }
+
+    /// The most cycles the poll routine may take and still leave the
+    /// required headroom.
+    #[inline]
+    pub const fn max_poll_cycles(&self) -> u32 {
+        let headroom = if self.headroom_percent > 100 {
+            100
+        } else {
+            self.headroom_percent
+        };
+
+        (self.cycles_per_poll() as u64 * (100 - headroom) as u64 / 100) as u32
+    }
+
+    /// Does a poll routine costing `worst_case_cycles` fit the budget?
+    #[inline]
+    pub const fn fits(&self, worst_case_cycles: u32) -> bool {
+        worst_case_cycles <= self.max_poll_cycles()
+    }
+
+    /// Assert that a poll routine costing `worst_case_cycles` fits the
+    /// budget.
+    ///
+    /// Like `debug_assert!`, this only checks anything in debug builds,
+    /// so it can be left in place after bring-up.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if the cost exceeds the budget.
+    #[inline]
+    #[track_caller]
+    pub fn debug_assert_fits(&self, worst_case_cycles: u32) {
+        debug_assert!(
+            self.fits(worst_case_cycles),
+            "poll routine takes {} cycles, but only {} fit in the budget",
+            worst_case_cycles,
+            self.max_poll_cycles()
+        );
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn budget() {
+        // 8MHz polled at 1kHz, keeping 75% for the application.
+        let budget = IsrBudget::new(8_000_000, 1_000, 75);
+
+        assert_eq!(8_000, budget.cycles_per_poll());
+        assert_eq!(2_000, budget.max_poll_cycles());
+        assert_eq!(true, budget.fits(2_000));
+        assert_eq!(false, budget.fits(2_001));
+
+        budget.debug_assert_fits(1_500);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "poll routine takes 2500 cycles")]
+    fn over_budget() {
+        IsrBudget::new(8_000_000, 1_000, 75).debug_assert_fits(2_500);
+    }
+
+    #[test]
+    #[should_panic(expected = "poll rate")]
+    fn zero_poll_rate() {
+        IsrBudget::new(8_000_000, 0, 75).cycles_per_poll();
+    }
+}
@@ -0,0 +1,198 @@
+//! A button, on top of the debounced pin.
+//!
+//! Most application code doesn't want to think in terms of pin levels
+//! and edges, but in terms of a button: is it pressed, has it been
+//! pressed since I last looked, and for how long has it been held?  The
+//! [`Button`](Button) answers these, given a debounced pin (or any
+//! other [`Source`](crate::filter::Source)) and which level means
+//! "pressed".
+//!
+//! ```
+//! # struct PinType;
+//! # impl embedded_hal::digital::v2::InputPin for PinType {
+//! #     type Error = core::convert::Infallible;
+//! #     fn is_high(&self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! use unflappable::{button::Button, debouncer_uninit, Debouncer, Level, default::ActiveLow};
+//!
+//! static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+//!
+//! # let input_pin = PinType;
+//! let debounced = unsafe { DEBOUNCER.init(input_pin) }.unwrap();
+//! let mut button = Button::new(debounced, Level::Low);
+//!
+//! // Once per poll period:
+//! button.update();
+//!
+//! // Whenever convenient:
+//! if button.was_pressed() {
+//!     // start something
+//! }
+//! ```
+
+use crate::filter::Source;
+use crate::Level;
+
+/// A button read through a debounced pin.
+pub struct Button<S> {
+    source: S,
+    active: Level,
+    pressed: bool,
+    was_pressed: bool,
+    was_released: bool,
+    held: u32,
+}
+
+impl<S: Source> Button<S> {
+    /// Create a button that is pressed when `source` is at the `active`
+    /// level.
+    ///
+    /// The button starts out released.
+    #[inline]
+    pub const fn new(source: S, active: Level) -> Self {
+        Button {
+            source,
+            active,
+            pressed: false,
+            was_pressed: false,
+            was_released: false,
+            held: 0,
+        }
+    }
+
+    /// Update the button from its source.
+    ///
+    /// This should be done once per poll of the debouncer, since the
+    /// hold time is counted in updates.
+    pub fn update(&mut self) {
+        let pressed = self.source.next_level() == self.active;
+
+        if pressed && !self.pressed {
+            self.was_pressed = true;
+            self.held = 0;
+        } else if !pressed && self.pressed {
+            self.was_released = true;
+        } else if pressed {
+            self.held = self.held.saturating_add(1);
+        }
+
+        self.pressed = pressed;
+    }
+
+    /// Is the button currently pressed?
+    #[inline]
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Has the button been pressed since the last call?
+    ///
+    /// A press is remembered until it's checked for, even if the button
+    /// has since been released.
+    #[inline]
+    pub fn was_pressed(&mut self) -> bool {
+        core::mem::replace(&mut self.was_pressed, false)
+    }
+
+    /// Has the button been released since the last call?
+    ///
+    /// A release is remembered until it's checked for, even if the
+    /// button has since been pressed again.
+    #[inline]
+    pub fn was_released(&mut self) -> bool {
+        core::mem::replace(&mut self.was_released, false)
+    }
+
+    /// The number of updates the button has been held for, or zero if
+    /// it isn't pressed.
+    #[inline]
+    pub fn held_ticks(&self) -> u32 {
+        if self.pressed {
+            self.held
+        } else {
+            0
+        }
+    }
+
+    /// Destroy the button, returning its source.
+    #[inline]
+    pub fn release(self) -> S {
+        self.source
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    use crate::{debouncer_uninit, Debounce, Debouncer};
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 1;
+        const INIT_HIGH: bool = true;
+    }
+
+    #[test]
+    fn press_hold_release() {
+        let debouncer: Debouncer<(), Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        let mut button = Button::new(debounced, Level::Low);
+        button.update();
+        assert_eq!(false, button.is_pressed());
+        assert_eq!(false, button.was_pressed());
+
+        for _ in 0..4 {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_with(false) }.unwrap();
+            button.update();
+        }
+        assert_eq!(true, button.is_pressed());
+        assert_eq!(3, button.held_ticks());
+        assert_eq!(true, button.was_pressed());
+        assert_eq!(false, button.was_pressed());
+        assert_eq!(false, button.was_released());
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll_with(true) }.unwrap();
+        button.update();
+        assert_eq!(false, button.is_pressed());
+        assert_eq!(0, button.held_ticks());
+        assert_eq!(true, button.was_released());
+        assert_eq!(false, button.was_released());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(button.release()) }.unwrap();
+    }
+
+    #[test]
+    fn sticky_press() {
+        let debouncer: Debouncer<(), Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        let mut button = Button::new(debounced, Level::Low);
+
+        // A quick tap, between checks.
+        for &sample in [false, true, true].iter() {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_with(sample) }.unwrap();
+            button.update();
+        }
+        assert_eq!(false, button.is_pressed());
+        assert_eq!(true, button.was_pressed());
+        assert_eq!(true, button.was_released());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(button.release()) }.unwrap();
+    }
+}
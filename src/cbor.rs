@@ -0,0 +1,143 @@
+//! Export debounce statistics in CBOR.
+//!
+//! To ship the [`Stats`](crate::Stats) of a set of inputs to a companion
+//! app over BLE or a UART, [`stats_to_cbor()`](stats_to_cbor) encodes
+//! them in [CBOR][0], which any platform can decode without a custom
+//! wire format.  The encoding is an array with an entry per channel,
+//! each a two-element array of its transitions and flips, in the order
+//! given, using the shortest form of each integer.
+//!
+//! ```
+//! use unflappable::{cbor::{stats_to_cbor, max_cbor_len}, Stats};
+//!
+//! let stats = [
+//!     Stats { transitions: 1, flips: 2 },
+//!     Stats { transitions: 300, flips: 70_000 },
+//! ];
+//!
+//! let mut buf = [0; max_cbor_len(2)];
+//! let len = stats_to_cbor(&stats, &mut buf);
+//! assert_eq!(
+//!     [0x82, 0x82, 0x01, 0x02, 0x82, 0x19, 0x01, 0x2c, 0x1a, 0x00, 0x01, 0x11, 0x70],
+//!     buf[..len]
+//! );
+//! ```
+//!
+//! [0]: https://www.rfc-editor.org/rfc/rfc8949
+
+use core::convert::TryFrom;
+
+use crate::Stats;
+
+const MAJOR_UNSIGNED: u8 = 0 << 5;
+const MAJOR_ARRAY: u8 = 4 << 5;
+
+/// The most bytes the stats of `channels` channels can take.
+#[inline]
+pub const fn max_cbor_len(channels: usize) -> usize {
+    // The outer array header, and for each channel its header and two
+    // full-width integers.
+    5 + channels * (1 + 2 * 5)
+}
+
+/// Encode the `stats` of each channel into `buf`, returning the number
+/// of bytes written.
+///
+/// Returns zero if `buf` is too small, in which case its contents are
+/// unspecified.  A buffer of [`max_cbor_len()`](max_cbor_len) bytes is
+/// always big enough.
+pub fn stats_to_cbor(stats: &[Stats], buf: &mut [u8]) -> usize {
+    let mut writer = Writer { buf, len: 0 };
+
+    match writer.stats(stats) {
+        Some(()) => writer.len,
+        None => 0,
+    }
+}
+
+struct Writer<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Writer<'a> {
+    fn stats(&mut self, stats: &[Stats]) -> Option<()> {
+        self.put(MAJOR_ARRAY, u32::try_from(stats.len()).ok()?)?;
+        for channel in stats {
+            self.put(MAJOR_ARRAY, 2)?;
+            self.put(MAJOR_UNSIGNED, channel.transitions)?;
+            self.put(MAJOR_UNSIGNED, channel.flips)?;
+        }
+        Some(())
+    }
+
+    // Write a header with the shortest encoding of `value`.
+    fn put(&mut self, major: u8, value: u32) -> Option<()> {
+        let (info, bytes) = match value {
+            0..=23 => (value as u8, 0),
+            24..=0xff => (24, 1),
+            0x100..=0xffff => (25, 2),
+            _ => (26, 4),
+        };
+
+        let end = self.len + 1 + bytes;
+        let out = self.buf.get_mut(self.len..end)?;
+        out[0] = major | info;
+        out[1..].copy_from_slice(&value.to_be_bytes()[4 - bytes..]);
+        self.len = end;
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn integer_widths() {
+        let stats = [
+            Stats {
+                transitions: 23,
+                flips: 24,
+            },
+            Stats {
+                transitions: 0xff,
+                flips: 0x100,
+            },
+            Stats {
+                transitions: 0xffff,
+                flips: u32::MAX,
+            },
+        ];
+
+        let mut buf = [0; max_cbor_len(3)];
+        let len = stats_to_cbor(&stats, &mut buf);
+        assert_eq!(
+            [
+                0x83, 0x82, 0x17, 0x18, 0x18, 0x82, 0x18, 0xff, 0x19, 0x01, 0x00, 0x82, 0x19, 0xff,
+                0xff, 0x1a, 0xff, 0xff, 0xff, 0xff,
+            ],
+            buf[..len]
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let mut buf = [0xaa; 1];
+        assert_eq!(1, stats_to_cbor(&[], &mut buf));
+        assert_eq!([0x80], buf);
+    }
+
+    #[test]
+    fn buffer_too_small() {
+        let stats = [Stats {
+            transitions: 1000,
+            flips: 2000,
+        }];
+
+        let mut buf = [0; 8];
+        assert_eq!(8, stats_to_cbor(&stats, &mut buf));
+        assert_eq!(0, stats_to_cbor(&stats, &mut buf[..7]));
+        assert_eq!(0, stats_to_cbor(&stats, &mut []));
+    }
+}
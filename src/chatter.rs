@@ -0,0 +1,217 @@
+//! Detect inputs that chatter without ever settling.
+//!
+//! A healthy switch bounces for a few milliseconds and then settles,
+//! with the integrator coming to rest at a rail.  A floating input, or
+//! one with a failing contact, can instead keep the integrator wandering
+//! for as long as you care to watch, with only the occasional spurious
+//! edge getting through.
+//!
+//! A [`ChatterDetector`](ChatterDetector) counts the polls since its
+//! debouncer was last settled.  Once that reaches the configured limit,
+//! [`is_chattering()`](ChatterDetector#method.is_chattering) reports
+//! it, until the input settles again, so firmware can raise a
+//! maintenance alert.
+//!
+//! ```
+//! # struct PinType;
+//! # impl embedded_hal::digital::v2::InputPin for PinType {
+//! #     type Error = core::convert::Infallible;
+//! #     fn is_high(&self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! # fn raise_maintenance_alert() {}
+//! use unflappable::{chatter::ChatterDetector, default::ActiveLow};
+//!
+//! // Polling at 100Hz, alert after ten seconds of chatter.
+//! static DOOR: ChatterDetector<PinType, ActiveLow> = ChatterDetector::uninit(0, 1000);
+//!
+//! # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+//! # let input_pin = PinType;
+//! let debounced = unsafe { DOOR.init(input_pin) }.unwrap();
+//!
+//! // In the interrupt service routine:
+//! unsafe { DOOR.poll() }?;
+//!
+//! // In the application:
+//! if DOOR.is_chattering() {
+//!     raise_maintenance_alert();
+//! }
+//! #     Ok(())
+//! # }
+//! ```
+
+use core::cell::UnsafeCell;
+
+use crate::{Debounce, Debounced, Debouncer, DeinitError, Edge, InitError, PollError, Sampler};
+
+/// A debouncer that flags inputs that don't settle.
+pub struct ChatterDetector<Pin, Cfg: Debounce> {
+    debouncer: Debouncer<Pin, Cfg>,
+    limit: u32,
+    unsettled: UnsafeCell<u32>,
+}
+
+// We demand particular mutex requirements as documented on the methods
+// marked as unsafe.  They are expected to be enforced statically by
+// the user, outside of the type system.
+unsafe impl<Pin, Cfg: Debounce> Sync for ChatterDetector<Pin, Cfg> {}
+
+impl<Pin, Cfg: Debounce> ChatterDetector<Pin, Cfg> {
+    /// Create a new, uninitialized chatter detector, which flags the
+    /// input once it has gone `limit` polls without settling.
+    ///
+    /// As for [`Debouncer::uninit()`](crate::Debouncer#method.uninit),
+    /// you must pass in the zero value of the storage type.
+    ///
+    /// The limit should be well above the longest bounce you expect
+    /// from a healthy switch.
+    #[inline]
+    pub const fn uninit(zero: Cfg::Storage, limit: u32) -> Self {
+        ChatterDetector {
+            debouncer: Debouncer::uninit(zero),
+            limit,
+            unsettled: UnsafeCell::new(0),
+        }
+    }
+
+    /// Initialize the chatter detector for a given input pin.
+    ///
+    /// Returns an error if it has already been initialized.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as
+    /// [`Debouncer::init()`](crate::Debouncer#method.init) apply.
+    pub unsafe fn init(&self, pin: Pin) -> Result<Debounced<'_, Cfg>, InitError<Pin>> {
+        let pin = self.debouncer.validate_config(pin)?;
+        let debounced = self.debouncer.init_linted(pin)?;
+
+        // This is safe because we demand from the caller that this
+        // method completes before any call to `poll()`.
+        *self.unsettled.get() = 0;
+
+        Ok(debounced)
+    }
+
+    /// Destroy the debounced pin, returning the original input pin.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as
+    /// [`Debouncer::deinit()`](crate::Debouncer#method.deinit) apply.
+    pub unsafe fn deinit<'a>(&self, pin: Debounced<'a, Cfg>) -> Result<Pin, DeinitError<'a, Cfg>> {
+        let pin = self.debouncer.deinit_linted(pin)?;
+
+        // This is safe because we demand from the caller that this is
+        // an exclusive call.
+        *self.unsettled.get() = 0;
+
+        Ok(pin)
+    }
+
+    /// Has the input gone at least `limit` polls without settling?
+    ///
+    /// This is cleared as soon as the input settles again.
+    #[inline]
+    pub fn is_chattering(&self) -> bool {
+        // This is safe since the read is atomic.
+        let unsettled = unsafe { *self.unsettled.get() };
+        unsettled >= self.limit
+    }
+
+    // The number of polls without settling that counts as chatter.
+    #[inline(always)]
+    pub(crate) fn limit(&self) -> u32 {
+        self.limit
+    }
+}
+
+impl<Pin: Sampler, Cfg: Debounce> ChatterDetector<Pin, Cfg> {
+    /// Poll the chatter detector.
+    ///
+    /// Returns the debounced edge, if any, just like
+    /// [`Debouncer::poll()`](crate::Debouncer#method.poll), and updates
+    /// the count of polls since the input last settled.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as
+    /// [`Debouncer::poll()`](crate::Debouncer#method.poll) apply.
+    pub unsafe fn poll(&self) -> Result<Edge, PollError<Pin::Error>> {
+        self.poll_sample().map(|(_, edge)| edge)
+    }
+
+    // Poll, returning the raw sample along with the edge, for detectors
+    // built on this one.
+    //
+    // The same requirements as `poll()` apply.
+    #[inline(always)]
+    pub(crate) unsafe fn poll_sample(&self) -> Result<(bool, Edge), PollError<Pin::Error>> {
+        let high = self.debouncer.sample_linted()?;
+        let edge = self.debouncer.integrate(high);
+
+        // This is safe because we demand from the caller that this is
+        // an exclusive call.
+        let unsettled = &mut *self.unsettled.get();
+
+        if self.debouncer.is_settled() {
+            *unsettled = 0;
+        } else {
+            *unsettled = unsettled.saturating_add(1);
+        }
+
+        Ok((high, edge))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    use embedded_hal_mock::pin;
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 4;
+        const INIT_HIGH: bool = false;
+    }
+
+    #[test]
+    fn flags_unsettled_input() {
+        let low = pin::Transaction::get(pin::State::Low);
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [
+            high.clone(),
+            high.clone(),
+            low.clone(),
+            high,
+            low.clone(),
+            low,
+        ];
+
+        let detector: ChatterDetector<_, Cfg> = ChatterDetector::uninit(0, 4);
+        // It is always safe to init a stack-scoped ChatterDetector.
+        let debounced = unsafe { detector.init(pin::Mock::new(&expectations)) }.unwrap();
+
+        // The integrator wanders without reaching either rail.
+        for &chattering in [false, false, false, true, true].iter() {
+            // It is always safe to poll a stack-scoped ChatterDetector.
+            assert_eq!(Edge::NoChange, unsafe { detector.poll() }.unwrap());
+            assert_eq!(chattering, detector.is_chattering());
+        }
+
+        // Settling clears it.
+        // It is always safe to poll a stack-scoped ChatterDetector.
+        assert_eq!(Edge::NoChange, unsafe { detector.poll() }.unwrap());
+        assert_eq!(false, detector.is_chattering());
+
+        // It is always safe to deinit a stack-scoped ChatterDetector.
+        unsafe { detector.deinit(debounced) }.unwrap().done();
+    }
+}
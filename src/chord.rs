@@ -0,0 +1,215 @@
+//! Recognize chords of buttons pressed together.
+//!
+//! A front panel may give a meaning to holding two buttons at once,
+//! distinct from pressing either one alone.  People never press two
+//! buttons at quite the same moment, so the
+//! [`ChordDetector`](ChordDetector) waits a configurable window after
+//! the first press, gathering any other buttons pressed in the meantime
+//! into a single chord.
+//!
+//! The detector is fed a mask of the buttons pressed, once per poll.
+//! For a [`DebouncerBank`](crate::bank::DebouncerBank) that's just its
+//! [`state()`](crate::bank::DebouncerBank::state) (inverted for
+//! active-low buttons).  For separate debounced pins, build the mask
+//! with [`mask()`](mask).
+//!
+//! ```
+//! use unflappable::chord::{mask, ChordDetector, ChordEvent};
+//!
+//! const A: u32 = 1 << 0;
+//! const B: u32 = 1 << 1;
+//!
+//! // Polling at 100Hz, allow 50ms between presses of a chord.
+//! let mut chords = ChordDetector::new(5);
+//! # let (a_pressed, b_pressed) = (true, true);
+//!
+//! // Once per poll:
+//! match chords.update(mask(&[a_pressed, b_pressed])) {
+//!     Some(ChordEvent::Pressed(chord)) if chord == A | B => {
+//!         // A+B together
+//!     }
+//!     Some(ChordEvent::Pressed(A)) => {
+//!         // A alone
+//!     }
+//!     _ => {}
+//! }
+//! ```
+
+/// An event from a [`ChordDetector`](ChordDetector).
+///
+/// Each carries the mask of the buttons in the chord, which may be just
+/// a single button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChordEvent {
+    /// The chord was pressed.
+    Pressed(u32),
+
+    /// The chord was released.
+    Released(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    Gathering { chord: u32, remaining: u16 },
+    Held { chord: u32 },
+}
+
+/// A chord recognizer.
+///
+/// Once a button is pressed, any other buttons pressed within the
+/// window (measured in polls) join it in the chord.  The chord is
+/// reported when the window closes, or as soon as one of its buttons is
+/// released.  Once reported, the chord is fixed: other buttons pressed
+/// while it's held are ignored, and the chord is released when all the
+/// buttons are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChordDetector {
+    window: u16,
+    state: State,
+}
+
+impl ChordDetector {
+    /// Create a new chord recognizer with the given window, in polls.
+    ///
+    /// With a window of zero, only buttons pressed on the very same poll
+    /// form a chord.
+    #[inline]
+    pub const fn new(window: u16) -> Self {
+        ChordDetector {
+            window,
+            state: State::Idle,
+        }
+    }
+
+    /// Update the recognizer with the buttons that are pressed.
+    ///
+    /// This should be called once per poll.
+    pub fn update(&mut self, pressed: u32) -> Option<ChordEvent> {
+        match self.state {
+            State::Idle if pressed == 0 => None,
+            State::Idle => {
+                self.state = State::Gathering {
+                    chord: pressed,
+                    remaining: self.window,
+                };
+                self.close_window()
+            }
+            State::Gathering { chord, remaining } => {
+                if pressed & chord != chord {
+                    self.state = State::Held { chord };
+                    return Some(ChordEvent::Pressed(chord));
+                }
+
+                self.state = State::Gathering {
+                    chord: chord | pressed,
+                    remaining: remaining.saturating_sub(1),
+                };
+                self.close_window()
+            }
+            State::Held { chord } if pressed == 0 => {
+                self.state = State::Idle;
+                Some(ChordEvent::Released(chord))
+            }
+            State::Held { .. } => None,
+        }
+    }
+
+    fn close_window(&mut self) -> Option<ChordEvent> {
+        match self.state {
+            State::Gathering {
+                chord,
+                remaining: 0,
+            } => {
+                self.state = State::Held { chord };
+                Some(ChordEvent::Pressed(chord))
+            }
+            _ => None,
+        }
+    }
+
+    /// The chord currently held, if one has been reported.
+    #[inline]
+    pub fn held(&self) -> Option<u32> {
+        match self.state {
+            State::Held { chord } => Some(chord),
+            _ => None,
+        }
+    }
+}
+
+/// Build a mask of pressed buttons, with bit `n` set if `pressed[n]`.
+///
+/// # Panics
+///
+/// Panics if there are more than 32 buttons.
+pub fn mask(pressed: &[bool]) -> u32 {
+    assert!(pressed.len() <= 32, "a chord has at most 32 buttons");
+
+    pressed
+        .iter()
+        .enumerate()
+        .filter(|&(_, &pressed)| pressed)
+        .fold(0, |mask, (n, _)| mask | 1 << n)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const A: u32 = 0b01;
+    const B: u32 = 0b10;
+
+    #[test]
+    fn chord_within_window() {
+        let mut chords = ChordDetector::new(3);
+
+        assert_eq!(None, chords.update(0));
+        assert_eq!(None, chords.update(A));
+        assert_eq!(None, chords.update(A | B));
+        assert_eq!(None, chords.update(A | B));
+        assert_eq!(Some(ChordEvent::Pressed(A | B)), chords.update(A | B));
+        assert_eq!(Some(A | B), chords.held());
+
+        assert_eq!(None, chords.update(B));
+        assert_eq!(Some(ChordEvent::Released(A | B)), chords.update(0));
+        assert_eq!(None, chords.held());
+    }
+
+    #[test]
+    fn one_then_the_other() {
+        let mut chords = ChordDetector::new(2);
+
+        assert_eq!(None, chords.update(A));
+        assert_eq!(None, chords.update(A));
+        assert_eq!(Some(ChordEvent::Pressed(A)), chords.update(A));
+
+        // Too late to join the chord.
+        assert_eq!(None, chords.update(A | B));
+        assert_eq!(None, chords.update(B));
+        assert_eq!(Some(ChordEvent::Released(A)), chords.update(0));
+    }
+
+    #[test]
+    fn early_release() {
+        let mut chords = ChordDetector::new(10);
+
+        assert_eq!(None, chords.update(A));
+        assert_eq!(Some(ChordEvent::Pressed(A)), chords.update(0));
+        assert_eq!(Some(ChordEvent::Released(A)), chords.update(0));
+    }
+
+    #[test]
+    fn zero_window() {
+        let mut chords = ChordDetector::new(0);
+
+        assert_eq!(Some(ChordEvent::Pressed(A | B)), chords.update(A | B));
+        assert_eq!(Some(ChordEvent::Released(A | B)), chords.update(0));
+    }
+
+    #[test]
+    fn build_mask() {
+        assert_eq!(0, mask(&[]));
+        assert_eq!(0b101, mask(&[true, false, true]));
+    }
+}
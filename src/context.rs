@@ -0,0 +1,151 @@
+//! Check that polling always happens in the same context.
+//!
+//! The safety requirements of [`Debouncer::poll()`](crate::Debouncer#method.poll)
+//! boil down to calling it from one place.  A common way to break that
+//! by accident is to poll from two interrupt service routines, say the
+//! timer ISR and a pin-change ISR, which may preempt each other if they
+//! run at different priorities.
+//!
+//! A [`ContextCheck`](ContextCheck) records the execution context (such
+//! as the active interrupt number) of the first call, and asserts that
+//! every later call comes from the same one.  With the `cortex-m`
+//! feature enabled, in debug builds on Cortex-M targets, every
+//! `Debouncer` carries one, and `poll()` checks the active exception
+//! number against it.  Other builds don't pay for it.
+//!
+//! The check is only ever made from the place it guards, so it needs
+//! no atomics, and works on targets without them.
+
+use core::cell::UnsafeCell;
+
+const UNRECORDED: u16 = u16::MAX;
+
+/// A record of the context something is expected to run in.
+pub struct ContextCheck {
+    context: UnsafeCell<u16>,
+}
+
+// The context is only written by `check()` and `reset()`, whose callers
+// promise not to overlap them with anything else.
+unsafe impl Sync for ContextCheck {}
+
+impl ContextCheck {
+    /// Create a new context check, which has yet to see any context.
+    #[inline]
+    pub const fn new() -> Self {
+        ContextCheck {
+            context: UnsafeCell::new(UNRECORDED),
+        }
+    }
+
+    /// The context recorded so far, if any.
+    #[inline]
+    pub fn recorded(&self) -> Option<u16> {
+        // This is safe since the read is atomic.
+        match unsafe { *self.context.get() } {
+            UNRECORDED => None,
+            context => Some(context),
+        }
+    }
+
+    /// Check that we are running in `context`.
+    ///
+    /// The first call records the context; later calls compare against
+    /// it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `context` differs from the recorded context.
+    ///
+    /// # Safety
+    ///
+    /// This must not be called concurrently with itself or
+    /// [`reset()`](#method.reset).  That's just what the guarded code
+    /// promises anyway, so call it from there.
+    #[inline]
+    #[track_caller]
+    pub unsafe fn check(&self, context: u16) {
+        match self.recorded() {
+            None => *self.context.get() = context,
+            Some(recorded) => assert!(
+                recorded == context,
+                "polled from context {}, but first polled from context {}",
+                context,
+                recorded
+            ),
+        }
+    }
+
+    /// Forget the recorded context.
+    ///
+    /// # Safety
+    ///
+    /// This must not be called concurrently with
+    /// [`check()`](#method.check) or itself.
+    #[inline]
+    pub unsafe fn reset(&self) {
+        *self.context.get() = UNRECORDED;
+    }
+}
+
+impl core::fmt::Debug for ContextCheck {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ContextCheck")
+            .field("context", &self.recorded())
+            .finish()
+    }
+}
+
+impl Default for ContextCheck {
+    fn default() -> Self {
+        ContextCheck::new()
+    }
+}
+
+/// The number of the active exception, or zero in thread mode.
+///
+/// Interrupt `n` is exception number `n + 16`.
+#[cfg(all(feature = "cortex-m", target_arch = "arm"))]
+#[inline]
+pub fn current() -> u16 {
+    let scb = cortex_m::peripheral::SCB::PTR;
+    // This is safe since reading the ICSR has no side effects.
+    let icsr = unsafe { (*scb).icsr.read() };
+    (icsr & 0x1ff) as u16
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn same_context() {
+        let check = ContextCheck::new();
+        assert_eq!(None, check.recorded());
+
+        // It is always safe to check a stack-scoped ContextCheck.
+        unsafe {
+            check.check(31);
+            check.check(31);
+        }
+        assert_eq!(Some(31), check.recorded());
+
+        // It is always safe to check a stack-scoped ContextCheck.
+        unsafe {
+            check.reset();
+            check.check(0);
+        }
+        assert_eq!(Some(0), check.recorded());
+    }
+
+    #[test]
+    #[should_panic(expected = "polled from context 17, but first polled from context 31")]
+    fn different_context() {
+        let check = ContextCheck::new();
+        // It is always safe to check a stack-scoped ContextCheck.
+        unsafe {
+            check.check(31);
+            check.check(17);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Differential inputs.
+//!
+//! For long cable runs out to a panel, a single-ended input can pick up
+//! more noise than any debouncer should be asked to handle.  Driving the
+//! signal differentially, on a pair of wires carrying opposite levels,
+//! makes it far more robust, and also lets the receiver notice when the
+//! pair disagrees with itself (say, from a broken wire).
+//!
+//! The [`Differential`](Differential) type reads both halves of the pair
+//! and presents the decoded signal as an ordinary `InputPin`, so it can
+//! be handed straight to a [`Debouncer`](crate::Debouncer).  When both
+//! halves read the same level, the read fails with a
+//! [`Fault`](DifferentialError::Fault), which the debouncer reports as a
+//! [`PollError::Pin`](crate::PollError::Pin) without disturbing the
+//! debounced state.
+
+use embedded_hal::digital::v2::InputPin;
+
+/// An error reading a differential input.
+#[derive(Debug)]
+pub enum DifferentialError<PinError> {
+    /// Both halves of the pair read the same level.
+    Fault,
+
+    /// An error reading one of the underlying pins.
+    Pin(PinError),
+}
+
+/// A signal carried on a differential pair of input pins.
+///
+/// The signal is high when the positive pin `P` is high and the
+/// negative pin `N` is low, and low when `P` is low and `N` is high.
+pub struct Differential<P, N> {
+    positive: P,
+    negative: N,
+}
+
+impl<P, N> Differential<P, N>
+where
+    P: InputPin,
+    N: InputPin<Error = P::Error>,
+{
+    /// Combine the two halves of a differential pair.
+    #[inline]
+    pub fn new(positive: P, negative: N) -> Self {
+        Differential { positive, negative }
+    }
+
+    /// Destroy the differential input, returning the positive and
+    /// negative pins.
+    #[inline]
+    pub fn release(self) -> (P, N) {
+        (self.positive, self.negative)
+    }
+}
+
+impl<P, N> InputPin for Differential<P, N>
+where
+    P: InputPin,
+    N: InputPin<Error = P::Error>,
+{
+    type Error = DifferentialError<P::Error>;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        let positive = self.positive.is_high().map_err(DifferentialError::Pin)?;
+        let negative = self.negative.is_high().map_err(DifferentialError::Pin)?;
+
+        if positive == negative {
+            Err(DifferentialError::Fault)
+        } else {
+            Ok(positive)
+        }
+    }
+
+    #[inline]
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    use embedded_hal_mock::pin;
+
+    use crate::{debouncer_uninit, Debounce, Debouncer, PollError};
+
+    #[test]
+    fn debounce_differential() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 2;
+            const INIT_HIGH: bool = false;
+        }
+
+        let high = pin::Transaction::get(pin::State::High);
+        let low = pin::Transaction::get(pin::State::Low);
+
+        let positive = pin::Mock::new(&[high.clone(), high.clone(), high.clone()]);
+        let negative = pin::Mock::new(&[low.clone(), high, low]);
+
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(Differential::new(positive, negative)) }.unwrap();
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll() }.unwrap();
+        assert_eq!(false, debounced.is_high().unwrap());
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        match unsafe { debouncer.poll() } {
+            Err(PollError::Pin(DifferentialError::Fault)) => {}
+            other => panic!("expected a fault, got {:?}", other),
+        }
+        assert_eq!(false, debounced.is_high().unwrap());
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll() }.unwrap();
+        assert_eq!(true, debounced.is_high().unwrap());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        let (mut positive, mut negative) =
+            unsafe { debouncer.deinit(debounced) }.unwrap().release();
+        positive.done();
+        negative.done();
+    }
+}
@@ -0,0 +1,411 @@
+//! Debounce a number of inputs only known at runtime.
+//!
+//! The rest of this crate sizes everything at compile time, which is
+//! just right for a microcontroller with a fixed set of buttons.
+//! Larger systems, such as gateways and HMIs running on `std` or an
+//! RTOS, may instead discover their inputs at runtime: plug-in cards,
+//! configured channels, and the like.
+//!
+//! With the `alloc` feature enabled, a [`DynamicBank`](DynamicBank)
+//! keeps its inputs in a `Vec`, so they can be added and removed as
+//! they come and go, and each may have a boxed handler called on its
+//! debounced edges.  The rest of the crate stays free of allocation.
+//!
+//! ```
+//! # struct PinType;
+//! # impl embedded_hal::digital::v2::InputPin for PinType {
+//! #     type Error = core::convert::Infallible;
+//! #     fn is_high(&self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! # let discovered = [PinType, PinType];
+//! use unflappable::{default::ActiveLow, dynamic::DynamicBank};
+//!
+//! let mut bank: DynamicBank<PinType, ActiveLow> = DynamicBank::new();
+//! for pin in discovered {
+//!     bank.add_with_handler(pin, |edge| {
+//!         // the input changed
+//!     });
+//! }
+//!
+//! // In the poll routine:
+//! bank.poll()?;
+//! # Ok::<(), core::convert::Infallible>(())
+//! ```
+//!
+//! For products with detachable modules, such as a keypad that may or
+//! may not be plugged in, a [`ModuleBank`](ModuleBank) keeps a
+//! `DynamicBank` for each attached module.  Modules are attached and
+//! detached at runtime, and the attachment and detachment are reported
+//! in order with the inputs' edges as a [`BankEvent`](BankEvent).
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::{Debounce, DebounceExt, Debouncer, Edge, Level, Sampler};
+
+/// A handler called with each debounced edge of an input.
+pub type Handler = Box<dyn FnMut(Edge) + Send>;
+
+struct Input<Pin, Cfg: Debounce> {
+    pin: Pin,
+    debouncer: Debouncer<(), Cfg>,
+    handler: Option<Handler>,
+}
+
+/// A growable bank of debounced inputs.
+///
+/// Inputs are identified by their index, in the order they were added.
+/// Removing an input shifts the indices of those after it down by one.
+pub struct DynamicBank<Pin, Cfg: Debounce> {
+    inputs: Vec<Input<Pin, Cfg>>,
+}
+
+impl<Pin: Sampler, Cfg: Debounce> DynamicBank<Pin, Cfg> {
+    /// Create a new, empty bank.
+    #[inline]
+    pub const fn new() -> Self {
+        DynamicBank { inputs: Vec::new() }
+    }
+
+    /// Add an input, returning its index.
+    #[inline]
+    pub fn add(&mut self, pin: Pin) -> usize {
+        self.push(pin, None)
+    }
+
+    /// Add an input with a handler to call on each of its debounced
+    /// edges, returning its index.
+    #[inline]
+    pub fn add_with_handler<F>(&mut self, pin: Pin, handler: F) -> usize
+    where
+        F: FnMut(Edge) + Send + 'static,
+    {
+        self.push(pin, Some(Box::new(handler)))
+    }
+
+    fn push(&mut self, pin: Pin, handler: Option<Handler>) -> usize {
+        let debouncer = Debouncer::uninit(Cfg::zero());
+        debouncer.check_config();
+        // A new debouncer is never already initialized.
+        let _ = debouncer.init_linted(());
+
+        self.inputs.push(Input {
+            pin,
+            debouncer,
+            handler,
+        });
+        self.inputs.len() - 1
+    }
+
+    /// Remove the input at `index`, returning its pin.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no input at `index`.
+    #[inline]
+    pub fn remove(&mut self, index: usize) -> Pin {
+        self.inputs.remove(index).pin
+    }
+
+    /// The number of inputs in the bank.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Is the bank empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// The current debounced level of the input at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no input at `index`.
+    #[inline]
+    pub fn state(&self, index: usize) -> Level {
+        Level::from(self.inputs[index].debouncer.state_flag())
+    }
+
+    /// Poll every input, calling the handlers of any that changed.
+    ///
+    /// Stops at the first error sampling a pin, leaving the inputs
+    /// after it unpolled.
+    #[inline]
+    pub fn poll(&mut self) -> Result<(), Pin::Error> {
+        self.poll_with(|_, _| {})
+    }
+
+    /// Poll every input, calling the handlers of any that changed, as
+    /// well as `on_edge` with the index and edge of each.
+    ///
+    /// Stops at the first error sampling a pin, leaving the inputs
+    /// after it unpolled.
+    pub fn poll_with<F: FnMut(usize, Edge)>(&mut self, mut on_edge: F) -> Result<(), Pin::Error> {
+        for (index, input) in self.inputs.iter_mut().enumerate() {
+            let high = input.pin.sample()?;
+
+            let edge = input.debouncer.integrate(high);
+            if edge == Edge::NoChange {
+                continue;
+            }
+
+            if let Some(handler) = input.handler.as_mut() {
+                handler(edge);
+            }
+            on_edge(index, edge);
+        }
+
+        Ok(())
+    }
+
+    /// Destroy the bank, returning the pins in order.
+    pub fn release(self) -> Vec<Pin> {
+        self.inputs.into_iter().map(|input| input.pin).collect()
+    }
+}
+
+impl<Pin: Sampler, Cfg: Debounce> Default for DynamicBank<Pin, Cfg> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An event from a [`ModuleBank`](ModuleBank).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BankEvent<Id> {
+    /// The module was attached.
+    ModuleAttached(Id),
+
+    /// The module was detached.
+    ModuleDetached(Id),
+
+    /// An input of a module changed.
+    Edge {
+        /// The module of the input.
+        module: Id,
+        /// The index of the input in its module.
+        input: usize,
+        /// The debounced edge.
+        edge: Edge,
+    },
+}
+
+struct Module<Id, Pin, Cfg: Debounce> {
+    id: Id,
+    bank: DynamicBank<Pin, Cfg>,
+}
+
+/// A bank of hot-pluggable modules of inputs.
+///
+/// Each module is identified by an `Id` of your choosing, such as its
+/// slot or bus address.  Attaching a module starts each of its inputs
+/// afresh at the configured initial state, and detaching it tears down
+/// their debounce state and returns the pins.
+pub struct ModuleBank<Id, Pin, Cfg: Debounce> {
+    modules: Vec<Module<Id, Pin, Cfg>>,
+    pending: Vec<BankEvent<Id>>,
+}
+
+impl<Id: Copy + Eq, Pin: Sampler, Cfg: Debounce> ModuleBank<Id, Pin, Cfg> {
+    /// Create a new bank, with no modules attached.
+    #[inline]
+    pub const fn new() -> Self {
+        ModuleBank {
+            modules: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Attach a module with the given inputs.
+    ///
+    /// The [`ModuleAttached`](BankEvent::ModuleAttached) event is
+    /// reported on the next poll.  Returns the pins if a module with
+    /// that id is already attached.
+    pub fn attach<I>(&mut self, id: Id, pins: I) -> Result<(), Vec<Pin>>
+    where
+        I: IntoIterator<Item = Pin>,
+    {
+        if self.is_attached(id) {
+            return Err(pins.into_iter().collect());
+        }
+
+        let mut bank = DynamicBank::new();
+        for pin in pins {
+            bank.add(pin);
+        }
+
+        self.modules.push(Module { id, bank });
+        self.pending.push(BankEvent::ModuleAttached(id));
+        Ok(())
+    }
+
+    /// Detach a module, returning its pins.
+    ///
+    /// The [`ModuleDetached`](BankEvent::ModuleDetached) event is
+    /// reported on the next poll.  Returns `None` if no module with
+    /// that id is attached.
+    pub fn detach(&mut self, id: Id) -> Option<Vec<Pin>> {
+        let index = self.modules.iter().position(|module| module.id == id)?;
+        let module = self.modules.remove(index);
+
+        self.pending.push(BankEvent::ModuleDetached(id));
+        Some(module.bank.release())
+    }
+
+    /// Is a module with the given id attached?
+    #[inline]
+    pub fn is_attached(&self, id: Id) -> bool {
+        self.modules.iter().any(|module| module.id == id)
+    }
+
+    /// The current debounced level of an input of a module, or `None`
+    /// if the module isn't attached.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the module has no input at `input`.
+    pub fn state(&self, id: Id, input: usize) -> Option<Level> {
+        self.modules
+            .iter()
+            .find(|module| module.id == id)
+            .map(|module| module.bank.state(input))
+    }
+
+    /// Poll every input of every attached module.
+    ///
+    /// `on_event` is first called with any attachments and detachments
+    /// since the last poll, in the order they happened, and then with
+    /// each edge.  Stops at the first error sampling a pin, leaving the
+    /// inputs after it unpolled.
+    pub fn poll<F: FnMut(BankEvent<Id>)>(&mut self, mut on_event: F) -> Result<(), Pin::Error> {
+        for event in self.pending.drain(..) {
+            on_event(event);
+        }
+
+        for module in self.modules.iter_mut() {
+            let id = module.id;
+            module.bank.poll_with(|input, edge| {
+                on_event(BankEvent::Edge {
+                    module: id,
+                    input,
+                    edge,
+                })
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<Id: Copy + Eq, Pin: Sampler, Cfg: Debounce> Default for ModuleBank<Id, Pin, Cfg> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    use alloc::sync::Arc;
+    use alloc::vec;
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    use embedded_hal_mock::pin;
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 2;
+        const INIT_HIGH: bool = false;
+    }
+
+    #[test]
+    fn grow_and_shrink() {
+        let high = pin::Transaction::get(pin::State::High);
+        let low = pin::Transaction::get(pin::State::Low);
+
+        let rises = Arc::new(AtomicU8::new(0));
+        let mut bank: DynamicBank<_, Cfg> = DynamicBank::new();
+        assert!(bank.is_empty());
+
+        let handler_rises = rises.clone();
+        let first =
+            bank.add_with_handler(pin::Mock::new(&[high.clone(), high.clone()]), move |edge| {
+                if edge == Edge::Rising {
+                    handler_rises.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        let second = bank.add(pin::Mock::new(&[low.clone(), low, high]));
+        assert_eq!((0, 1), (first, second));
+        assert_eq!(2, bank.len());
+
+        bank.poll().unwrap();
+        let mut edges = [None; 2];
+        bank.poll_with(|index, edge| edges[index] = Some(edge))
+            .unwrap();
+        assert_eq!([Some(Edge::Rising), None], edges);
+        assert_eq!(1, rises.load(Ordering::SeqCst));
+        assert_eq!(Level::High, bank.state(first));
+        assert_eq!(Level::Low, bank.state(second));
+
+        bank.remove(first).done();
+        assert_eq!(Level::Low, bank.state(0));
+        bank.poll().unwrap();
+
+        for mut pin in bank.release() {
+            pin.done();
+        }
+    }
+
+    #[test]
+    fn hot_plug() {
+        let high = pin::Transaction::get(pin::State::High);
+
+        let mut bank: ModuleBank<u8, _, Cfg> = ModuleBank::new();
+        bank.attach(7, vec![pin::Mock::new(&[high.clone(), high])])
+            .unwrap();
+        assert_eq!(true, bank.is_attached(7));
+
+        let duplicate = bank.attach(7, vec![pin::Mock::new(&[])]).unwrap_err();
+        for mut pin in duplicate {
+            pin.done();
+        }
+
+        let mut events = vec![];
+        bank.poll(|event| events.push(event)).unwrap();
+        bank.poll(|event| events.push(event)).unwrap();
+        assert_eq!(Some(Level::High), bank.state(7, 0));
+
+        for mut pin in bank.detach(7).unwrap() {
+            pin.done();
+        }
+        assert_eq!(None, bank.state(7, 0));
+        assert!(bank.detach(7).is_none());
+
+        bank.poll(|event| events.push(event)).unwrap();
+        assert_eq!(
+            vec![
+                BankEvent::ModuleAttached(7),
+                BankEvent::Edge {
+                    module: 7,
+                    input: 0,
+                    edge: Edge::Rising
+                },
+                BankEvent::ModuleDetached(7),
+            ],
+            events
+        );
+    }
+}
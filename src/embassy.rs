@@ -0,0 +1,174 @@
+//! Deliver debounced edges to Embassy tasks.
+//!
+//! Embassy applications usually poll the debouncer from an interrupt
+//! service routine or a timer task, and handle button events in other
+//! tasks.  The [`poll_signal()`](crate::Debouncer#method.poll_signal)
+//! and [`poll_channel()`](crate::Debouncer#method.poll_channel) methods
+//! bridge the two, publishing each edge to an [`embassy_sync`][0]
+//! primitive that the handling task can `await`.
+//!
+//! Use a `Signal` if the task only cares about the latest edge, and a
+//! `Channel` if it must see every one.
+//!
+//! ```
+//! # struct PinType;
+//! # impl embedded_hal::digital::v2::InputPin for PinType {
+//! #     type Error = core::convert::Infallible;
+//! #     fn is_high(&self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+//! use embassy_sync::channel::Channel;
+//! use unflappable::{debouncer_uninit, Debouncer, Edge, default::ActiveLow};
+//!
+//! static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+//! static EDGES: Channel<CriticalSectionRawMutex, Edge, 4> = Channel::new();
+//!
+//! // In the interrupt service routine:
+//! # fn isr() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+//! unsafe { DEBOUNCER.poll_channel(&EDGES) }?;
+//! #     Ok(())
+//! # }
+//!
+//! // In the button task:
+//! async fn button_task() {
+//!     loop {
+//!         if EDGES.receive().await == Edge::Falling {
+//!             // the button was pressed
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! [0]: https://docs.rs/embassy-sync/0.7
+
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::channel::Channel;
+use embassy_sync::signal::Signal;
+
+use crate::{Debounce, Debouncer, Edge, PollError, Sampler};
+
+impl<Pin: Sampler, Cfg: Debounce> Debouncer<Pin, Cfg> {
+    /// Poll the pin debouncer, signaling any resulting edge.
+    ///
+    /// Other than signaling the edge, this is the same as
+    /// [`poll()`](#method.poll), and returns the edge too.  An edge
+    /// that hasn't been taken yet is replaced by the new one.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`poll()`](#method.poll) apply.
+    #[inline]
+    pub unsafe fn poll_signal<M: RawMutex>(
+        &self,
+        signal: &Signal<M, Edge>,
+    ) -> Result<Edge, PollError<Pin::Error>> {
+        let edge = self.poll()?;
+
+        if edge != Edge::NoChange {
+            signal.signal(edge);
+        }
+
+        Ok(edge)
+    }
+
+    /// Poll the pin debouncer, sending any resulting edge to a channel.
+    ///
+    /// Other than sending the edge, this is the same as
+    /// [`poll()`](#method.poll), and returns the edge too.  If the
+    /// channel is full, the new edge is dropped, so make sure there's
+    /// room for as many edges as can happen before the receiving task
+    /// catches up.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`poll()`](#method.poll) apply.
+    #[inline]
+    pub unsafe fn poll_channel<M: RawMutex, const N: usize>(
+        &self,
+        channel: &Channel<M, Edge, N>,
+    ) -> Result<Edge, PollError<Pin::Error>> {
+        let edge = self.poll()?;
+
+        if edge != Edge::NoChange {
+            let _ = channel.try_send(edge);
+        }
+
+        Ok(edge)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use embedded_hal_mock::pin;
+
+    use crate::debouncer_uninit;
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 1;
+        const INIT_HIGH: bool = true;
+    }
+
+    #[test]
+    fn signal_latest_edge() {
+        let low = pin::Transaction::get(pin::State::Low);
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [low.clone(), high, low];
+
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin::Mock::new(&expectations)) }.unwrap();
+
+        let signal: Signal<NoopRawMutex, Edge> = Signal::new();
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll_signal(&signal) }.unwrap();
+        assert_eq!(Some(Edge::Falling), signal.try_take());
+        assert_eq!(None, signal.try_take());
+
+        for _ in 0..2 {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_signal(&signal) }.unwrap();
+        }
+        assert_eq!(Some(Edge::Falling), signal.try_take());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
+
+    #[test]
+    fn channel_every_edge() {
+        let low = pin::Transaction::get(pin::State::Low);
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [low.clone(), high.clone(), low, high];
+
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin::Mock::new(&expectations)) }.unwrap();
+
+        let channel: Channel<NoopRawMutex, Edge, 3> = Channel::new();
+
+        for _ in 0..expectations.len() {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_channel(&channel) }.unwrap();
+        }
+
+        // Only three edges fit, so the last is dropped.
+        assert_eq!(Ok(Edge::Falling), channel.try_receive());
+        assert_eq!(Ok(Edge::Rising), channel.try_receive());
+        assert_eq!(Ok(Edge::Falling), channel.try_receive());
+        assert!(channel.try_receive().is_err());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
+}
@@ -0,0 +1,636 @@
+//! Rotary encoder support.
+//!
+//! Mechanical rotary encoders present two phase signals, usually called
+//! `A` and `B`, which trace out a two-bit Gray code as the shaft turns.
+//! The contacts bounce just like a button's, but since each valid
+//! transition only ever changes one phase, a decoder that tracks the
+//! full sequence can reject the bounce without any integration.
+//!
+//! The [`Quadrature`](Quadrature) decoder implements the full-step
+//! state machine popularized by Ben Buxton, reporting a step only once
+//! a complete cycle from rest back to rest has been observed.
+//!
+//! Cheap encoders can chatter badly enough, or for long enough, to
+//! fool the decoder anyway.  The [`Encoder`](Encoder) type runs each
+//! phase through its own integrator before decoding, at the cost of
+//! limiting the top speed it can follow, and reports each full step as
+//! a [`Direction`](Direction).
+//!
+//! Many encoders include a push switch on the shaft.  The
+//! [`EncoderWithButton`](EncoderWithButton) type combines the decoder
+//! with a [`Debounced`](crate::Debounced) switch, producing a single
+//! stream of [`Event`](Event)s.
+//!
+//! If you've run out of input pins, two encoders can share the same
+//! phase lines through a multiplexer.  The
+//! [`DualEncoder`](DualEncoder) type drives the select line and keeps a
+//! separate decoder for each.
+
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+use crate::{bad_config, Debounce, DebounceExt, Debounced, Debouncer, InitError};
+
+const START: u8 = 0x0;
+const CW_FINAL: u8 = 0x1;
+const CW_BEGIN: u8 = 0x2;
+const CW_NEXT: u8 = 0x3;
+const CCW_BEGIN: u8 = 0x4;
+const CCW_FINAL: u8 = 0x5;
+const CCW_NEXT: u8 = 0x6;
+
+const DIR_CW: u8 = 0x10;
+const DIR_CCW: u8 = 0x20;
+const STATE_MASK: u8 = 0x0f;
+
+// Indexed first by the current state, then by the pin state `(b << 1) | a`.
+const TRANSITIONS: [[u8; 4]; 7] = [
+    // START
+    [START, CW_BEGIN, CCW_BEGIN, START],
+    // CW_FINAL
+    [CW_NEXT, START, CW_FINAL, START | DIR_CW],
+    // CW_BEGIN
+    [CW_NEXT, CW_BEGIN, START, START],
+    // CW_NEXT
+    [CW_NEXT, CW_BEGIN, CW_FINAL, START],
+    // CCW_BEGIN
+    [CCW_NEXT, START, CCW_BEGIN, START],
+    // CCW_FINAL
+    [CCW_NEXT, CCW_FINAL, START, START | DIR_CCW],
+    // CCW_NEXT
+    [CCW_NEXT, CCW_FINAL, CCW_BEGIN, START],
+];
+
+/// A full-step quadrature decoder.
+///
+/// The decoder expects the encoder to rest with both phases high, as
+/// is the case for the usual wiring with pull-up resistors.  If your
+/// encoder turns the wrong way, swap the `A` and `B` phases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quadrature {
+    state: u8,
+}
+
+impl Quadrature {
+    /// Create a new decoder in the rest state.
+    #[inline]
+    pub const fn new() -> Self {
+        Quadrature { state: START }
+    }
+
+    /// Feed the decoder one sample of the two phases.
+    ///
+    /// Returns `1` if a clockwise step was completed by this sample,
+    /// `-1` if a counterclockwise step was completed, and `0` otherwise.
+    #[inline]
+    pub fn update(&mut self, a: bool, b: bool) -> i8 {
+        let pins = ((b as usize) << 1) | a as usize;
+        let next = TRANSITIONS[(self.state & STATE_MASK) as usize][pins];
+        self.state = next & STATE_MASK;
+
+        match next & !STATE_MASK {
+            DIR_CW => 1,
+            DIR_CCW => -1,
+            _ => 0,
+        }
+    }
+}
+
+impl Default for Quadrature {
+    fn default() -> Self {
+        Quadrature::new()
+    }
+}
+
+/// The direction of a step of an [`Encoder`](Encoder).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The shaft was turned one step clockwise.
+    Clockwise,
+
+    /// The shaft was turned one step counterclockwise.
+    CounterClockwise,
+}
+
+/// A rotary encoder with both phases debounced.
+///
+/// Each phase is sampled on every call to [`poll()`](#method.poll) and
+/// run through an integrator with the given configuration, and the
+/// debounced phases are fed to a [`Quadrature`](Quadrature) decoder.
+/// Since the encoder rests with both phases high, the configuration
+/// should have [`INIT_HIGH`](crate::Debounce#associatedconstant.INIT_HIGH)
+/// set.
+///
+/// Each phase must hold a level for `MAX_COUNT` polls to register, so
+/// poll fast enough that this is well under the time the phase spends
+/// at each level when turning the shaft quickly.
+pub struct Encoder<A, B, Cfg: Debounce> {
+    a: A,
+    b: B,
+    a_debouncer: Debouncer<(), Cfg>,
+    b_debouncer: Debouncer<(), Cfg>,
+    decoder: Quadrature,
+}
+
+impl<A, B, Cfg> Encoder<A, B, Cfg>
+where
+    A: InputPin,
+    B: InputPin<Error = A::Error>,
+    Cfg: Debounce,
+{
+    /// Create an encoder from its two phase pins.
+    ///
+    /// Returns [`InitError::BadConfig`](crate::InitError::BadConfig),
+    /// with both pins, if the configuration is invalid.
+    pub fn new(a: A, b: B) -> Result<Self, InitError<(A, B)>> {
+        if let Some(message) = Cfg::config_error() {
+            return Err(bad_config((a, b), message));
+        }
+
+        let a_debouncer = Debouncer::uninit(Cfg::zero());
+        let b_debouncer = Debouncer::uninit(Cfg::zero());
+        // A new debouncer is never already initialized.
+        let _ = a_debouncer.init_linted(());
+        let _ = b_debouncer.init_linted(());
+
+        Ok(Encoder {
+            a,
+            b,
+            a_debouncer,
+            b_debouncer,
+            decoder: Quadrature::new(),
+        })
+    }
+
+    /// Sample both phases, returning the direction of a step if one was
+    /// completed.
+    pub fn poll(&mut self) -> Result<Option<Direction>, A::Error> {
+        let a = self.a.is_high()?;
+        let b = self.b.is_high()?;
+
+        self.a_debouncer.integrate(a);
+        self.b_debouncer.integrate(b);
+
+        let a = self.a_debouncer.state_flag();
+        let b = self.b_debouncer.state_flag();
+        Ok(match self.decoder.update(a, b) {
+            1 => Some(Direction::Clockwise),
+            -1 => Some(Direction::CounterClockwise),
+            _ => None,
+        })
+    }
+
+    /// Destroy the encoder, returning the phase pins.
+    pub fn release(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+
+/// An event from an [`EncoderWithButton`](EncoderWithButton).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The shaft was turned the given number of steps with the button
+    /// released.  Positive steps are clockwise.
+    Step(i8),
+
+    /// The button was pressed.
+    Pressed,
+
+    /// The button was released.
+    Released,
+
+    /// The shaft was turned the given number of steps while the button
+    /// was held down.  Positive steps are clockwise.
+    PressedAndTurned(i8),
+}
+
+/// A rotary encoder with a debounced push switch.
+///
+/// The encoder phases are sampled directly on each call to
+/// [`poll()`](#method.poll), which should be made at the same cadence
+/// as the `Debouncer` for the switch.  The switch is considered pressed
+/// when its debounced level differs from the configured
+/// [`INIT_HIGH`](crate::Debounce#associatedconstant.INIT_HIGH), so the
+/// same configuration describes both the debouncer and the button.
+pub struct EncoderWithButton<'a, A, B, Cfg: Debounce> {
+    a: A,
+    b: B,
+    button: Debounced<'a, Cfg>,
+    decoder: Quadrature,
+    pressed: bool,
+}
+
+impl<'a, A, B, Cfg> EncoderWithButton<'a, A, B, Cfg>
+where
+    A: InputPin,
+    B: InputPin<Error = A::Error>,
+    Cfg: Debounce,
+{
+    /// Combine the encoder phases with the debounced switch.
+    ///
+    /// The current state of the switch is taken as the starting point,
+    /// so a button held down at startup will not report `Pressed`.
+    pub fn new(a: A, b: B, button: Debounced<'a, Cfg>) -> Self {
+        let pressed = Self::is_pressed(&button);
+        EncoderWithButton {
+            a,
+            b,
+            button,
+            decoder: Quadrature::new(),
+            pressed,
+        }
+    }
+
+    /// Sample the encoder and check the switch for changes.
+    ///
+    /// At most one event is reported per call.  If the shaft completes
+    /// a step in the same poll that the switch changes, the step is
+    /// reported first and the switch change on the next poll.
+    pub fn poll(&mut self) -> Result<Option<Event>, A::Error> {
+        let a = self.a.is_high()?;
+        let b = self.b.is_high()?;
+
+        let steps = self.decoder.update(a, b);
+        if steps != 0 {
+            return Ok(Some(if self.pressed {
+                Event::PressedAndTurned(steps)
+            } else {
+                Event::Step(steps)
+            }));
+        }
+
+        let pressed = Self::is_pressed(&self.button);
+        if pressed != self.pressed {
+            self.pressed = pressed;
+            return Ok(Some(if pressed {
+                Event::Pressed
+            } else {
+                Event::Released
+            }));
+        }
+
+        Ok(None)
+    }
+
+    /// Is the button currently held down?
+    #[inline]
+    pub fn button_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Destroy the encoder, returning the phase pins and the switch.
+    pub fn release(self) -> (A, B, Debounced<'a, Cfg>) {
+        (self.a, self.b, self.button)
+    }
+
+    #[inline(always)]
+    fn is_pressed(button: &Debounced<'a, Cfg>) -> bool {
+        match button.is_high() {
+            Ok(high) => high != Cfg::INIT_HIGH,
+            Err(never) => match never {},
+        }
+    }
+}
+
+/// Which of the two encoders of a [`DualEncoder`](DualEncoder) is meant.
+///
+/// The encoders are named for the level of the select line that routes
+/// their phases to the shared inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Select {
+    /// The encoder read with the select line low.
+    Low,
+
+    /// The encoder read with the select line high.
+    High,
+}
+
+/// A step reported by a [`DualEncoder`](DualEncoder).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DualStep {
+    /// The encoder that was turned.
+    pub encoder: Select,
+
+    /// The number of steps completed.  Positive steps are clockwise.
+    pub steps: i8,
+}
+
+/// An error that arose while polling a [`DualEncoder`](DualEncoder).
+#[derive(Debug)]
+pub enum DualError<InputError, SelectError> {
+    /// An error reading one of the shared phase inputs.
+    Input(InputError),
+
+    /// An error driving the select line.
+    Select(SelectError),
+}
+
+/// Two rotary encoders multiplexed onto shared phase inputs.
+///
+/// Each call to [`poll()`](#method.poll) samples the phases of the
+/// currently selected encoder and then flips the select line, giving
+/// the multiplexer a full poll period to settle.  Since each encoder is
+/// only sampled on every other poll, you'll want to poll twice as often
+/// as you would a single encoder.
+pub struct DualEncoder<A, B, Sel> {
+    a: A,
+    b: B,
+    select: Sel,
+    selected: Select,
+    decoders: [Quadrature; 2],
+}
+
+impl<A, B, Sel> DualEncoder<A, B, Sel>
+where
+    A: InputPin,
+    B: InputPin<Error = A::Error>,
+    Sel: OutputPin,
+{
+    /// Set up the shared phase inputs and the select line.
+    ///
+    /// The select line is driven low, so the first poll reads the
+    /// [`Select::Low`](Select::Low) encoder.
+    pub fn new(a: A, b: B, mut select: Sel) -> Result<Self, Sel::Error> {
+        select.set_low()?;
+
+        Ok(DualEncoder {
+            a,
+            b,
+            select,
+            selected: Select::Low,
+            decoders: [Quadrature::new(); 2],
+        })
+    }
+
+    /// Sample the selected encoder, then select the other one.
+    ///
+    /// Returns the encoder and the number of steps it completed, if
+    /// any.
+    pub fn poll(&mut self) -> Result<Option<DualStep>, DualError<A::Error, Sel::Error>> {
+        let a = self.a.is_high().map_err(DualError::Input)?;
+        let b = self.b.is_high().map_err(DualError::Input)?;
+
+        let current = self.selected;
+        let steps = self.decoders[current as usize].update(a, b);
+
+        self.selected = match current {
+            Select::Low => {
+                self.select.set_high().map_err(DualError::Select)?;
+                Select::High
+            }
+            Select::High => {
+                self.select.set_low().map_err(DualError::Select)?;
+                Select::Low
+            }
+        };
+
+        Ok(if steps != 0 {
+            Some(DualStep {
+                encoder: current,
+                steps,
+            })
+        } else {
+            None
+        })
+    }
+
+    /// Destroy the encoders, returning the pins.
+    pub fn release(self) -> (A, B, Sel) {
+        (self.a, self.b, self.select)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    use embedded_hal_mock::pin;
+
+    use crate::{debouncer_uninit, Debouncer};
+
+    const CLOCKWISE: [(bool, bool); 4] =
+        [(true, false), (false, false), (false, true), (true, true)];
+
+    #[test]
+    fn quadrature_full_steps() {
+        let mut decoder = Quadrature::new();
+
+        let steps: i8 = CLOCKWISE.iter().map(|&(a, b)| decoder.update(a, b)).sum();
+        assert_eq!(1, steps);
+
+        let steps: i8 = CLOCKWISE.iter().map(|&(a, b)| decoder.update(b, a)).sum();
+        assert_eq!(-1, steps);
+    }
+
+    #[test]
+    fn quadrature_ignores_bounce() {
+        let mut decoder = Quadrature::new();
+
+        // Each contact chatters as it changes before the step completes.
+        let samples = [
+            (true, false),
+            (true, true),
+            (true, false),
+            (true, true),
+            (true, false),
+            (false, false),
+            (true, false),
+            (false, false),
+            (false, true),
+            (true, true),
+        ];
+        let steps: i8 = samples.iter().map(|&(a, b)| decoder.update(a, b)).sum();
+        assert_eq!(1, steps);
+    }
+
+    #[test]
+    fn debounced_encoder() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 2;
+            const INIT_HIGH: bool = true;
+        }
+
+        fn get(high: bool) -> pin::Transaction {
+            pin::Transaction::get(if high {
+                pin::State::High
+            } else {
+                pin::State::Low
+            })
+        }
+
+        // A single-sample glitch on each phase, then a full
+        // counterclockwise step with each phase held for two samples.
+        let a = [
+            false, true, true, false, false, false, false, true, true, true, true,
+        ];
+        let b = [
+            true, true, false, true, true, false, false, false, false, true, true,
+        ];
+        let mut encoder: Encoder<_, _, Cfg> =
+            Encoder::new(pin::Mock::new(&a.map(get)), pin::Mock::new(&b.map(get))).unwrap();
+
+        let mut steps = [None; 11];
+        for step in steps.iter_mut() {
+            *step = encoder.poll().unwrap();
+        }
+        assert_eq!(
+            [
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(Direction::CounterClockwise)
+            ],
+            steps
+        );
+
+        let (mut a, mut b) = encoder.release();
+        a.done();
+        b.done();
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "debug-config",
+        should_panic(expected = "Debounce::MAX_COUNT")
+    )]
+    fn encoder_bad_config() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 0;
+            const INIT_HIGH: bool = true;
+        }
+
+        let result: Result<Encoder<_, _, Cfg>, _> =
+            Encoder::new(pin::Mock::new(&[]), pin::Mock::new(&[]));
+        match result {
+            Err(InitError::BadConfig((mut a, mut b))) => {
+                a.done();
+                b.done();
+            }
+            _ => panic!("expected a bad config"),
+        }
+    }
+
+    #[test]
+    fn encoder_with_button() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 1;
+            const INIT_HIGH: bool = true;
+        }
+
+        fn get(high: bool) -> pin::Transaction {
+            pin::Transaction::get(if high {
+                pin::State::High
+            } else {
+                pin::State::Low
+            })
+        }
+
+        let switch = pin::Mock::new(&[get(false), get(true)]);
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let button = unsafe { debouncer.init(switch) }.unwrap();
+
+        let a = pin::Mock::new(
+            &[
+                true, true, true, false, false, true, true, true, false, false, true,
+            ]
+            .map(get),
+        );
+        let b = pin::Mock::new(
+            &[
+                true, true, false, false, true, true, true, false, false, true, true,
+            ]
+            .map(get),
+        );
+
+        let mut encoder = EncoderWithButton::new(a, b, button);
+        assert_eq!(false, encoder.button_pressed());
+        assert_eq!(None, encoder.poll().unwrap());
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll() }.unwrap();
+        assert_eq!(Some(Event::Pressed), encoder.poll().unwrap());
+        assert_eq!(true, encoder.button_pressed());
+
+        assert_eq!(None, encoder.poll().unwrap());
+        assert_eq!(None, encoder.poll().unwrap());
+        assert_eq!(None, encoder.poll().unwrap());
+        assert_eq!(Some(Event::PressedAndTurned(1)), encoder.poll().unwrap());
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll() }.unwrap();
+        assert_eq!(Some(Event::Released), encoder.poll().unwrap());
+
+        assert_eq!(None, encoder.poll().unwrap());
+        assert_eq!(None, encoder.poll().unwrap());
+        assert_eq!(None, encoder.poll().unwrap());
+        assert_eq!(Some(Event::Step(1)), encoder.poll().unwrap());
+
+        let (mut a, mut b, button) = encoder.release();
+        a.done();
+        b.done();
+        // It is always safe to deinit a stack-scoped Debouncer.
+        let mut switch = unsafe { debouncer.deinit(button) }.unwrap();
+        switch.done();
+    }
+
+    #[test]
+    fn dual_encoder() {
+        fn get(high: bool) -> pin::Transaction {
+            pin::Transaction::get(if high {
+                pin::State::High
+            } else {
+                pin::State::Low
+            })
+        }
+
+        // The low encoder turns clockwise while the high encoder turns
+        // counterclockwise, each sampled on alternating polls.
+        let a = pin::Mock::new(&[true, false, false, false, false, true, true, true].map(get));
+        let b = pin::Mock::new(&[false, true, false, false, true, false, true, true].map(get));
+
+        let low = pin::Transaction::set(pin::State::Low);
+        let high = pin::Transaction::set(pin::State::High);
+        let select = pin::Mock::new(&[
+            low.clone(),
+            high.clone(),
+            low.clone(),
+            high.clone(),
+            low.clone(),
+            high.clone(),
+            low.clone(),
+            high,
+            low,
+        ]);
+
+        let mut encoders = DualEncoder::new(a, b, select).unwrap();
+
+        for _ in 0..6 {
+            assert_eq!(None, encoders.poll().unwrap());
+        }
+        let step = encoders.poll().unwrap().expect("low encoder step");
+        assert_eq!(Select::Low, step.encoder);
+        assert_eq!(1, step.steps);
+        let step = encoders.poll().unwrap().expect("high encoder step");
+        assert_eq!(Select::High, step.encoder);
+        assert_eq!(-1, step.steps);
+
+        let (mut a, mut b, mut select) = encoders.release();
+        a.done();
+        b.done();
+        select.done();
+    }
+}
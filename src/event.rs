@@ -0,0 +1,129 @@
+//! Debounce discrete events.
+//!
+//! The rest of this crate deals with level inputs, where the question
+//! is what state the signal has settled in.  Some inputs instead arrive
+//! as discrete events, such as the packets from an infrared or radio
+//! remote control.  These transmitters commonly repeat each packet
+//! several times (and keep repeating while a key is held), so the same
+//! event shows up again and again in quick succession.
+//!
+//! The [`EventDebouncer`](EventDebouncer) suppresses duplicates of an
+//! event that arrive within a configurable window of the previous one,
+//! tracking the timing for each event identity separately.
+
+use crate::time::ticks_since;
+
+/// A debouncer for discrete events.
+///
+/// Each event identity is tracked separately, so a burst of `A` does
+/// not suppress an intervening `B`.  Up to `N` identities are tracked at
+/// once; if more are seen, the one heard from least recently is
+/// forgotten.
+///
+/// Time is measured in ticks of whatever clock you pass to
+/// [`accept()`](#method.accept), which is allowed to wrap around.  The
+/// window is restarted by each duplicate, so an event that keeps
+/// repeating (for instance, a held remote control key) is only
+/// accepted once until it has been quiet for the full window.
+pub struct EventDebouncer<Id, const N: usize> {
+    window: u32,
+    seen: [Option<(Id, u32)>; N],
+}
+
+impl<Id: Copy + Eq, const N: usize> EventDebouncer<Id, N> {
+    /// Create a new event debouncer with the given suppression window.
+    #[inline]
+    pub const fn new(window: u32) -> Self {
+        EventDebouncer {
+            window,
+            seen: [None; N],
+        }
+    }
+
+    /// Handle an event that arrived at time `now`.
+    ///
+    /// Returns `true` if the event should be acted upon, or `false` if
+    /// it is a duplicate to be ignored.
+    pub fn accept(&mut self, id: Id, now: u32) -> bool {
+        let mut free = None;
+        let mut oldest: Option<(usize, u32)> = None;
+
+        for (index, slot) in self.seen.iter_mut().enumerate() {
+            match slot {
+                Some((seen_id, last)) if *seen_id == id => {
+                    let duplicate = ticks_since(now, *last) < self.window;
+                    *last = now;
+                    return !duplicate;
+                }
+                Some((_, last)) => {
+                    let age = ticks_since(now, *last);
+                    match oldest {
+                        Some((_, oldest_age)) if oldest_age >= age => {}
+                        _ => oldest = Some((index, age)),
+                    }
+                }
+                None => {
+                    if free.is_none() {
+                        free = Some(index);
+                    }
+                }
+            }
+        }
+
+        if let Some(index) = free.or(oldest.map(|(index, _)| index)) {
+            self.seen[index] = Some((id, now));
+        }
+
+        true
+    }
+
+    /// The suppression window, in ticks.
+    #[inline]
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+
+    /// Forget all events seen so far.
+    pub fn reset(&mut self) {
+        self.seen = [None; N];
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn suppresses_repeats() {
+        let mut debouncer: EventDebouncer<u8, 4> = EventDebouncer::new(10);
+
+        assert_eq!(true, debouncer.accept(1, 100));
+        assert_eq!(false, debouncer.accept(1, 105));
+        assert_eq!(true, debouncer.accept(2, 106));
+        assert_eq!(false, debouncer.accept(1, 114));
+        assert_eq!(false, debouncer.accept(2, 115));
+        assert_eq!(true, debouncer.accept(1, 124));
+    }
+
+    #[test]
+    fn wraps_around() {
+        let mut debouncer: EventDebouncer<u8, 1> = EventDebouncer::new(10);
+
+        assert_eq!(true, debouncer.accept(7, u32::MAX - 2));
+        assert_eq!(false, debouncer.accept(7, 3));
+        assert_eq!(true, debouncer.accept(7, 13));
+    }
+
+    #[test]
+    fn forgets_least_recent() {
+        let mut debouncer: EventDebouncer<u8, 2> = EventDebouncer::new(10);
+
+        assert_eq!(true, debouncer.accept(1, 0));
+        assert_eq!(true, debouncer.accept(2, 1));
+        assert_eq!(false, debouncer.accept(1, 2));
+        assert_eq!(true, debouncer.accept(3, 3));
+        assert_eq!(false, debouncer.accept(1, 4));
+        assert_eq!(true, debouncer.accept(2, 5));
+    }
+}
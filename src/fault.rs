@@ -0,0 +1,255 @@
+//! Handle inputs that fail persistently.
+//!
+//! Pins that are read over a bus, such as those on an I/O expander, can
+//! fail to read for extended periods: a cable is unplugged, or the
+//! expander browns out.  Left alone, the [`Debouncer`](crate::Debouncer)
+//! reports a [`PollError::Pin`](crate::PollError::Pin) on every single
+//! poll, and the debounced state freezes wherever it happened to be.
+//!
+//! The [`Supervised`](Supervised) pin wrapper applies a
+//! [`FaultPolicy`](FaultPolicy) instead.  A few errors in a row are
+//! passed through as usual, but once the configured limit is reached
+//! the input is marked faulted, a single
+//! [`Fault`](SupervisedError::Fault) error is reported, and from then on
+//! reads return the configured safe state.  The debouncer then settles
+//! into the safe state just as it would for a real input.
+//!
+//! The policy may also ask for the faulted pin to be re-probed every so
+//! often.  As soon as a re-probe succeeds, the fault is cleared, a
+//! single [`Recovered`](SupervisedError::Recovered) notification is
+//! reported, and reads go back to the pin itself.
+
+use core::cell::Cell;
+
+use embedded_hal::digital::v2::InputPin;
+
+use crate::Level;
+
+/// How to handle persistent errors reading a pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaultPolicy {
+    /// The number of consecutive errors after which the pin is marked
+    /// faulted.
+    pub max_errors: u8,
+
+    /// The level reported while the pin is faulted.
+    pub safe_state: Level,
+
+    /// How often (in reads) to re-probe a faulted pin, or zero to never
+    /// re-probe.
+    pub reprobe_interval: u16,
+}
+
+impl FaultPolicy {
+    /// Create a new fault policy.
+    ///
+    /// A pin faulted under this policy is never re-probed, see
+    /// [`reprobe_every()`](#method.reprobe_every).
+    #[inline]
+    pub const fn new(max_errors: u8, safe_state: Level) -> Self {
+        FaultPolicy {
+            max_errors,
+            safe_state,
+            reprobe_interval: 0,
+        }
+    }
+
+    /// Re-probe a faulted pin once every `interval` reads.
+    #[inline]
+    pub const fn reprobe_every(self, interval: u16) -> Self {
+        FaultPolicy {
+            reprobe_interval: interval,
+            ..self
+        }
+    }
+}
+
+/// An error reading a [`Supervised`](Supervised) pin.
+#[derive(Debug)]
+pub enum SupervisedError<PinError> {
+    /// An error reading the underlying pin.
+    Pin(PinError),
+
+    /// The pin has failed too many times in a row, and has been marked
+    /// faulted.  This is reported once, after which reads return the
+    /// safe state.
+    Fault,
+
+    /// A re-probe of the faulted pin succeeded, and the fault has been
+    /// cleared.  This isn't really an error, but is reported once so
+    /// that you can tell the pin has come back.
+    Recovered,
+}
+
+/// A pin with a fault policy applied.
+///
+/// Once faulted, the pin is no longer read, except to re-probe it if
+/// the policy asks for that.
+pub struct Supervised<Pin> {
+    pin: Pin,
+    policy: FaultPolicy,
+    errors: Cell<u8>,
+    faulted: Cell<bool>,
+    since_probe: Cell<u16>,
+}
+
+impl<Pin: InputPin> Supervised<Pin> {
+    /// Apply the fault policy to the pin.
+    #[inline]
+    pub fn new(pin: Pin, policy: FaultPolicy) -> Self {
+        Supervised {
+            pin,
+            policy,
+            errors: Cell::new(0),
+            faulted: Cell::new(false),
+            since_probe: Cell::new(0),
+        }
+    }
+
+    /// Has the pin been marked faulted?
+    #[inline]
+    pub fn is_faulted(&self) -> bool {
+        self.faulted.get()
+    }
+
+    /// Destroy the wrapper, returning the original pin.
+    #[inline]
+    pub fn release(self) -> Pin {
+        self.pin
+    }
+
+    fn read(&self) -> Result<bool, SupervisedError<Pin::Error>> {
+        if self.faulted.get() {
+            return self.reprobe();
+        }
+
+        match self.pin.is_high() {
+            Ok(high) => {
+                self.errors.set(0);
+                Ok(high)
+            }
+            Err(e) => {
+                let errors = self.errors.get().saturating_add(1);
+                self.errors.set(errors);
+
+                if errors >= self.policy.max_errors {
+                    self.faulted.set(true);
+                    self.since_probe.set(0);
+                    Err(SupervisedError::Fault)
+                } else {
+                    Err(SupervisedError::Pin(e))
+                }
+            }
+        }
+    }
+}
+
+impl<Pin: InputPin> Supervised<Pin> {
+    fn reprobe(&self) -> Result<bool, SupervisedError<Pin::Error>> {
+        let interval = self.policy.reprobe_interval;
+        if interval == 0 {
+            return Ok(self.policy.safe_state.is_high());
+        }
+
+        let since_probe = self.since_probe.get() + 1;
+        if since_probe < interval {
+            self.since_probe.set(since_probe);
+            return Ok(self.policy.safe_state.is_high());
+        }
+        self.since_probe.set(0);
+
+        match self.pin.is_high() {
+            Ok(_) => {
+                self.faulted.set(false);
+                self.errors.set(0);
+                Err(SupervisedError::Recovered)
+            }
+            Err(_) => Ok(self.policy.safe_state.is_high()),
+        }
+    }
+}
+
+impl<Pin: InputPin> InputPin for Supervised<Pin> {
+    type Error = SupervisedError<Pin::Error>;
+
+    #[inline]
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.read()
+    }
+
+    #[inline]
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.read().map(|high| !high)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    extern crate std;
+
+    use super::*;
+
+    use embedded_hal_mock::pin;
+    use embedded_hal_mock::MockError;
+
+    fn error() -> pin::Transaction {
+        let e = MockError::Io(std::io::ErrorKind::NotConnected);
+        pin::Transaction::get(pin::State::Low).with_error(e)
+    }
+
+    #[test]
+    fn escalates_to_fault() {
+        let expectations = [
+            error(),
+            pin::Transaction::get(pin::State::Low),
+            error(),
+            error(),
+            error(),
+        ];
+        let pin = Supervised::new(
+            pin::Mock::new(&expectations),
+            FaultPolicy::new(3, Level::High),
+        );
+
+        assert!(matches!(pin.is_high(), Err(SupervisedError::Pin(_))));
+        assert_eq!(false, pin.is_high().unwrap());
+        assert!(matches!(pin.is_high(), Err(SupervisedError::Pin(_))));
+        assert!(matches!(pin.is_high(), Err(SupervisedError::Pin(_))));
+        assert_eq!(false, pin.is_faulted());
+
+        assert!(matches!(pin.is_high(), Err(SupervisedError::Fault)));
+        assert_eq!(true, pin.is_faulted());
+
+        assert_eq!(true, pin.is_high().unwrap());
+        assert_eq!(false, pin.is_low().unwrap());
+
+        pin.release().done();
+    }
+
+    #[test]
+    fn reprobe_and_recover() {
+        let expectations = [
+            error(),
+            error(),
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::High),
+        ];
+        let policy = FaultPolicy::new(1, Level::Low).reprobe_every(2);
+        let pin = Supervised::new(pin::Mock::new(&expectations), policy);
+
+        assert!(matches!(pin.is_high(), Err(SupervisedError::Fault)));
+        assert_eq!(false, pin.is_high().unwrap());
+
+        // The first re-probe fails, so the pin remains faulted.
+        assert_eq!(false, pin.is_high().unwrap());
+        assert_eq!(true, pin.is_faulted());
+        assert_eq!(false, pin.is_high().unwrap());
+
+        assert!(matches!(pin.is_high(), Err(SupervisedError::Recovered)));
+        assert_eq!(false, pin.is_faulted());
+        assert_eq!(true, pin.is_high().unwrap());
+
+        pin.release().done();
+    }
+}
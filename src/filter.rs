@@ -0,0 +1,508 @@
+//! Post-process the debounced signal.
+//!
+//! Applications often want to do a little more with a debounced input
+//! before using it: invert an active-low button, or stretch a short
+//! press so that a slow main loop can't miss it.  Each of these is a
+//! [`Filter`](Filter), transforming the level once per poll period, and
+//! filters are chained onto a [`Source`](Source) with
+//! [`pipe()`](Source::pipe) rather than by nesting wrapper types.
+//!
+//! ```
+//! # struct PinType;
+//! # impl embedded_hal::digital::v2::InputPin for PinType {
+//! #     type Error = core::convert::Infallible;
+//! #     fn is_high(&self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! use unflappable::{debouncer_uninit, Debouncer, Level, default::ActiveLow};
+//! use unflappable::filter::{Invert, Source, Stretch};
+//!
+//! static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+//!
+//! # let input_pin = PinType;
+//! let debounced = unsafe { DEBOUNCER.init(input_pin) }.unwrap();
+//! let mut pressed = debounced.pipe(Invert).pipe(Stretch::<5>::new());
+//!
+//! // Once per poll period:
+//! if pressed.update() == Level::High {
+//!     // the button is pressed, or was recently
+//! }
+//! ```
+//!
+//! The stateless filters don't care how often they're updated, but
+//! timing-based ones like the [`Stretch`](Stretch) count updates, so
+//! update the pipeline at the poll frequency.
+//!
+//! The filters provided are:
+//!
+//! - [`Invert`](Invert), swapping high and low.
+//! - [`Stretch`](Stretch), holding high pulses for a while longer.
+//! - [`Latch`](Latch), staying high once set, until reset.
+//! - [`Toggle`](Toggle), flipping on each rising edge.
+//! - [`RateLimit`](RateLimit), holding each new level for a while.
+//! - [`Tap`](Tap), passing the level through unchanged, but calling a
+//!   callback with each edge.
+//!
+//! Any of them can also be applied to every channel of a
+//! [`DebouncerBank`](crate::bank::DebouncerBank) with a
+//! [`FilterBank`](FilterBank).
+
+use crate::bank::BankPin;
+use crate::{Debounce, Debounced, Edge, Level};
+
+/// A stage transforming a signal, one level at a time.
+pub trait Filter {
+    /// Feed the next input level through the filter, returning the
+    /// output level.
+    fn update(&mut self, input: Level) -> Level;
+}
+
+/// A signal that filters can be chained onto.
+pub trait Source {
+    /// Take the next level of the signal.
+    fn next_level(&mut self) -> Level;
+
+    /// Chain a filter onto this signal.
+    #[inline]
+    fn pipe<F: Filter>(self, filter: F) -> Pipe<Self, F>
+    where
+        Self: Sized,
+    {
+        Pipe {
+            source: self,
+            filter,
+        }
+    }
+}
+
+impl<'state, Cfg: Debounce> Source for Debounced<'state, Cfg> {
+    #[inline]
+    fn next_level(&mut self) -> Level {
+        self.state()
+    }
+}
+
+impl<'bank> Source for BankPin<'bank> {
+    #[inline]
+    fn next_level(&mut self) -> Level {
+        self.state()
+    }
+}
+
+/// A signal with a filter chained onto it.
+pub struct Pipe<S, F> {
+    source: S,
+    filter: F,
+}
+
+impl<S: Source, F: Filter> Pipe<S, F> {
+    /// Take the next level of the source and feed it through the
+    /// filter, returning the output level.
+    #[inline]
+    pub fn update(&mut self) -> Level {
+        self.next_level()
+    }
+
+    /// Take the pipe apart, returning the source and the filter.
+    #[inline]
+    pub fn release(self) -> (S, F) {
+        (self.source, self.filter)
+    }
+}
+
+impl<S: Source, F: Filter> Source for Pipe<S, F> {
+    #[inline]
+    fn next_level(&mut self) -> Level {
+        let input = self.source.next_level();
+        self.filter.update(input)
+    }
+}
+
+/// Invert the signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Invert;
+
+impl Filter for Invert {
+    #[inline(always)]
+    fn update(&mut self, input: Level) -> Level {
+        !input
+    }
+}
+
+/// Stretch high pulses by `N` updates.
+///
+/// The output goes high as soon as the input does, and stays high for
+/// `N` updates after the input goes low again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stretch<const N: u16> {
+    remaining: u16,
+}
+
+impl<const N: u16> Stretch<N> {
+    /// Create a new pulse stretcher, starting low.
+    #[inline]
+    pub const fn new() -> Self {
+        Stretch { remaining: 0 }
+    }
+}
+
+impl<const N: u16> Filter for Stretch<N> {
+    fn update(&mut self, input: Level) -> Level {
+        if input == Level::High {
+            self.remaining = N;
+            Level::High
+        } else if self.remaining > 0 {
+            self.remaining -= 1;
+            Level::High
+        } else {
+            Level::Low
+        }
+    }
+}
+
+/// Latch high pulses.
+///
+/// The output goes high as soon as the input does, and stays high until
+/// [`reset()`](#method.reset).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Latch {
+    set: bool,
+}
+
+impl Latch {
+    /// Create a new latch, starting low.
+    #[inline]
+    pub const fn new() -> Self {
+        Latch { set: false }
+    }
+
+    /// Reset the latch.
+    ///
+    /// The output is low on the next update, unless the input is still
+    /// high.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.set = false;
+    }
+}
+
+impl Filter for Latch {
+    #[inline]
+    fn update(&mut self, input: Level) -> Level {
+        self.set |= input == Level::High;
+        Level::from(self.set)
+    }
+}
+
+/// Flip the output on each rising edge of the input.
+///
+/// This turns a momentary push button into an on/off switch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Toggle {
+    input: Level,
+    output: Level,
+}
+
+impl Toggle {
+    /// Create a new toggle, with the output starting low.
+    ///
+    /// The input is assumed to start low too, so if it's high on the
+    /// first update, that counts as a rising edge.
+    #[inline]
+    pub const fn new() -> Self {
+        Toggle {
+            input: Level::Low,
+            output: Level::Low,
+        }
+    }
+}
+
+impl Default for Toggle {
+    #[inline]
+    fn default() -> Self {
+        Toggle::new()
+    }
+}
+
+impl Filter for Toggle {
+    fn update(&mut self, input: Level) -> Level {
+        if input == Level::High && self.input == Level::Low {
+            self.output = !self.output;
+        }
+        self.input = input;
+        self.output
+    }
+}
+
+/// Limit the rate of change of the signal.
+///
+/// The output follows the input, but stays at each new level for at
+/// least `N` updates.  A change of input during that time is deferred
+/// until it's over, and dropped if the input has changed back by then.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit<const N: u16> {
+    output: Level,
+    hold: u16,
+}
+
+impl<const N: u16> RateLimit<N> {
+    /// Create a new rate limiter, with the output starting low.
+    #[inline]
+    pub const fn new() -> Self {
+        RateLimit {
+            output: Level::Low,
+            hold: 0,
+        }
+    }
+}
+
+impl<const N: u16> Default for RateLimit<N> {
+    #[inline]
+    fn default() -> Self {
+        RateLimit::new()
+    }
+}
+
+impl<const N: u16> Filter for RateLimit<N> {
+    fn update(&mut self, input: Level) -> Level {
+        self.hold = self.hold.saturating_sub(1);
+
+        if self.hold == 0 && input != self.output {
+            self.output = input;
+            self.hold = N;
+        }
+
+        self.output
+    }
+}
+
+/// Tap the signal for edges.
+///
+/// The level passes through unchanged, and the callback is called with
+/// each edge.  The first update only establishes the level, so it
+/// doesn't report an edge.
+pub struct Tap<F> {
+    last: Option<Level>,
+    tap: F,
+}
+
+impl<F: FnMut(Edge)> Tap<F> {
+    /// Call `tap` with each edge of the signal.
+    #[inline]
+    pub const fn new(tap: F) -> Self {
+        Tap { last: None, tap }
+    }
+
+    /// Destroy the tap, returning the callback.
+    #[inline]
+    pub fn release(self) -> F {
+        self.tap
+    }
+}
+
+impl<F: FnMut(Edge)> Filter for Tap<F> {
+    fn update(&mut self, input: Level) -> Level {
+        match (self.last, input) {
+            (Some(Level::Low), Level::High) => (self.tap)(Edge::Rising),
+            (Some(Level::High), Level::Low) => (self.tap)(Edge::Falling),
+            _ => {}
+        }
+        self.last = Some(input);
+        input
+    }
+}
+
+/// A filter for each channel of a bank.
+///
+/// Feed it the [`state()`](crate::bank::DebouncerBank::state) of the
+/// bank once per poll, and it returns the filtered levels, each
+/// channel's bit having gone through its own filter.
+pub struct FilterBank<F, const N: usize> {
+    filters: [F; N],
+}
+
+impl<F: Filter, const N: usize> FilterBank<F, N> {
+    /// Filter channel `n` of the bank with `filters[n]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 32 channels.
+    #[inline]
+    pub fn new(filters: [F; N]) -> Self {
+        assert!(N <= 32, "a FilterBank has at most 32 channels");
+
+        FilterBank { filters }
+    }
+
+    /// Feed the levels of all the channels through their filters.
+    ///
+    /// Bit `n` of `levels` is the level of channel `n`; bits beyond the
+    /// channels are ignored, and are zero in the result.
+    pub fn update(&mut self, levels: u32) -> u32 {
+        self.filters
+            .iter_mut()
+            .enumerate()
+            .fold(0, |output, (channel, filter)| {
+                let input = Level::from(levels & 1 << channel != 0);
+                match filter.update(input) {
+                    Level::High => output | 1 << channel,
+                    Level::Low => output,
+                }
+            })
+    }
+
+    /// The filter for a channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel` is not in the bank.
+    #[inline]
+    pub fn filter(&mut self, channel: usize) -> &mut F {
+        &mut self.filters[channel]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{debouncer_uninit, Debouncer};
+
+    use Level::{High as H, Low as L};
+
+    struct Levels<'a> {
+        levels: &'a [Level],
+        next: usize,
+    }
+
+    impl<'a> Source for Levels<'a> {
+        fn next_level(&mut self) -> Level {
+            let level = self.levels[self.next];
+            self.next += 1;
+            level
+        }
+    }
+
+    fn run<F: Filter>(filter: F, input: &[Level]) -> [Level; 8] {
+        let mut pipe = Levels {
+            levels: input,
+            next: 0,
+        }
+        .pipe(filter);
+
+        let mut output = [L; 8];
+        for level in output.iter_mut() {
+            *level = pipe.update();
+        }
+        output
+    }
+
+    #[test]
+    fn invert() {
+        assert_eq!(
+            [L, H, H, L, L, L, H, L],
+            run(Invert, &[H, L, L, H, H, H, L, H])
+        );
+    }
+
+    #[test]
+    fn stretch() {
+        assert_eq!(
+            [H, H, H, L, H, H, H, H],
+            run(Stretch::<2>::new(), &[H, L, L, L, H, L, H, L])
+        );
+    }
+
+    #[test]
+    fn invert_then_stretch() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 1;
+            const INIT_HIGH: bool = true;
+        }
+
+        let debouncer: Debouncer<(), Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        let mut pressed = debounced.pipe(Invert).pipe(Stretch::<2>::new());
+        assert_eq!(L, pressed.update());
+
+        // A single-poll press is stretched out.
+        for &(sample, level) in [(false, H), (true, H), (true, H), (true, L)].iter() {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_with(sample) }.unwrap();
+            assert_eq!(level, pressed.update());
+        }
+
+        let (inverted, _) = pressed.release();
+        let (debounced, _) = inverted.release();
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    fn latch() {
+        let mut latch = Latch::new();
+        assert_eq!(L, latch.update(L));
+        assert_eq!(H, latch.update(H));
+        assert_eq!(H, latch.update(L));
+
+        latch.reset();
+        assert_eq!(L, latch.update(L));
+    }
+
+    #[test]
+    fn toggle() {
+        assert_eq!(
+            [L, H, H, H, L, L, H, H],
+            run(Toggle::new(), &[L, H, H, L, H, L, H, H])
+        );
+    }
+
+    #[test]
+    fn rate_limit() {
+        assert_eq!(
+            [H, H, H, L, L, L, L, H],
+            run(RateLimit::<3>::new(), &[H, L, L, L, H, L, L, H])
+        );
+    }
+
+    #[test]
+    fn tap() {
+        extern crate std;
+        use std::vec::Vec;
+
+        let mut edges = Vec::new();
+        let output = run(Tap::new(|edge| edges.push(edge)), &[H, H, L, L, H, L, L, L]);
+
+        assert_eq!([H, H, L, L, H, L, L, L], output);
+        assert_eq!([Edge::Falling, Edge::Rising, Edge::Falling], edges[..]);
+    }
+
+    #[test]
+    fn filter_bank() {
+        use crate::bank::DebouncerBank;
+
+        let bank: DebouncerBank<3> = DebouncerBank::new(0b010);
+        let mut filters = FilterBank::new([Toggle::new(), Toggle::new(), Toggle::new()]);
+
+        assert_eq!(0b010, filters.update(bank.state()));
+        assert_eq!(0b010, filters.update(bank.state()));
+
+        for _ in 0..4 {
+            // It is always safe to update a stack-scoped bank.
+            unsafe { bank.update(0b101) };
+        }
+        assert_eq!(0b111, filters.update(bank.state()));
+
+        // A bank channel is a source too.
+        let mut pin = bank.pin(0).pipe(Invert);
+        assert_eq!(L, pin.update());
+    }
+}
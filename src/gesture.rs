@@ -0,0 +1,668 @@
+//! Higher-level button gestures.
+//!
+//! Once an input has been debounced, applications often want to
+//! recognize more than just presses and releases: holding a button for
+//! a while, clicking it several times in quick succession, or having a
+//! held button repeat.  All of these depend on timing, which is
+//! collected in a single [`GestureTiming`](GestureTiming) so that it
+//! can be defined once and shared consistently across buttons.
+//!
+//! The recognizers are fed the debounced state of a button once per
+//! poll, and keep just enough state to report their gestures.
+//!
+//! - [`LongPress`](LongPress) recognizes a button held past the
+//!   long-press threshold, and reports progress toward it along the way
+//!   (handy for "keep holding to reset..." indicators).
+//! - [`AutoRepeat`](AutoRepeat) repeats the press of a held button,
+//!   like a keyboard's typematic repeat.
+//!
+//! For a [`DebouncerBank`](crate::bank::DebouncerBank) or a keypad,
+//! where only a few of the buttons need these gestures, a
+//! [`BankGestures`](BankGestures) keeps recognizers for just the
+//! channels named in its configuration, and reports their gestures
+//! along with the presses and releases of every channel as a single
+//! stream of [`BankGesture`](BankGesture)s.
+//!
+//! ```
+//! use unflappable::bank::DebouncerBank;
+//! use unflappable::gesture::{BankGesture, BankGestures, GestureTiming};
+//!
+//! // Eight active-low buttons; only the first two need gestures.
+//! static BANK: DebouncerBank<8> = DebouncerBank::new(0xff);
+//! const TIMING: GestureTiming = GestureTiming::from_millis(100, 500, 300, 500, 100);
+//! let mut gestures = BankGestures::new(TIMING, [0], [1]);
+//! # fn read_port() -> u32 { 0xfe }
+//!
+//! // In the poll routine:
+//! unsafe { BANK.update(read_port()) };
+//! gestures.update(!BANK.state(), |gesture| match gesture {
+//!     BankGesture::LongPress(0) => {
+//!         // reset to factory settings
+//!     }
+//!     _ => {}
+//! });
+//! ```
+
+/// Timing parameters shared by the gesture recognizers.
+///
+/// All durations are measured in polls of the underlying debouncer.
+/// If you'd rather think in real time, use
+/// [`from_millis()`](#method.from_millis) to convert given the poll
+/// frequency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GestureTiming {
+    /// How long a button must be held to count as a long press.
+    pub long_press: u16,
+
+    /// The longest gap between clicks that still counts as part of a
+    /// multi-click.
+    pub multi_click: u16,
+
+    /// How long a button must be held before it begins to repeat.
+    pub repeat_delay: u16,
+
+    /// The time between repeats once a held button begins repeating.
+    pub repeat_interval: u16,
+}
+
+impl GestureTiming {
+    /// Convert gesture timing given in milliseconds to polls, given the
+    /// poll frequency `poll_hz`.
+    ///
+    /// Each duration is rounded to the nearest whole number of polls,
+    /// but never less than one.
+    ///
+    /// ```
+    /// use unflappable::gesture::GestureTiming;
+    /// const TIMING: GestureTiming = GestureTiming::from_millis(200, 600, 250, 400, 50);
+    /// assert_eq!(120, TIMING.long_press);
+    /// assert_eq!(10, TIMING.repeat_interval);
+    /// ```
+    pub const fn from_millis(
+        poll_hz: u32,
+        long_press: u32,
+        multi_click: u32,
+        repeat_delay: u32,
+        repeat_interval: u32,
+    ) -> Self {
+        GestureTiming {
+            long_press: polls_from_millis(poll_hz, long_press),
+            multi_click: polls_from_millis(poll_hz, multi_click),
+            repeat_delay: polls_from_millis(poll_hz, repeat_delay),
+            repeat_interval: polls_from_millis(poll_hz, repeat_interval),
+        }
+    }
+}
+
+/// Reasonable defaults for a button polled at 100Hz: a long press at
+/// 500ms, a multi-click window of 300ms, and repeating ten times a
+/// second after half a second.
+impl Default for GestureTiming {
+    fn default() -> Self {
+        GestureTiming::from_millis(100, 500, 300, 500, 100)
+    }
+}
+
+/// Convert a duration in milliseconds to a number of polls.
+///
+/// The result is rounded to the nearest whole number of polls, but is
+/// at least one and saturates at `u16::MAX`.
+pub const fn polls_from_millis(poll_hz: u32, millis: u32) -> u16 {
+    let polls = (poll_hz as u64 * millis as u64 + 500) / 1000;
+
+    if polls == 0 {
+        1
+    } else if polls > u16::MAX as u64 {
+        u16::MAX
+    } else {
+        polls as u16
+    }
+}
+
+/// A long-press recognizer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LongPress {
+    threshold: u16,
+    held: u16,
+}
+
+impl LongPress {
+    /// Create a new long-press recognizer with the given timing.
+    #[inline]
+    pub const fn new(timing: GestureTiming) -> Self {
+        LongPress {
+            threshold: timing.long_press,
+            held: 0,
+        }
+    }
+
+    /// Adopt new timing, such as one adjusted at runtime.
+    ///
+    /// A button already held past the new threshold counts as a long
+    /// press without reporting it again.
+    #[inline]
+    pub fn set_timing(&mut self, timing: GestureTiming) {
+        self.threshold = timing.long_press;
+        if self.held > self.threshold {
+            self.held = self.threshold;
+        }
+    }
+
+    /// Update the recognizer with the debounced state of the button.
+    ///
+    /// This should be called once per poll.  Returns `true` on the poll
+    /// that the button has been held for the long-press threshold.
+    pub fn update(&mut self, pressed: bool) -> bool {
+        if !pressed {
+            self.held = 0;
+            return false;
+        }
+
+        if self.held < self.threshold {
+            self.held += 1;
+            self.held == self.threshold
+        } else {
+            false
+        }
+    }
+
+    /// The number of polls the button has been held, up to the
+    /// long-press threshold.
+    #[inline]
+    pub fn held(&self) -> u16 {
+        self.held
+    }
+
+    /// Has the button been held long enough to count as a long press?
+    #[inline]
+    pub fn is_long_press(&self) -> bool {
+        self.held >= self.threshold
+    }
+
+    /// Progress toward the long-press threshold, as a percentage.
+    ///
+    /// This is `0` while the button is released, climbs while it is
+    /// held, and reaches `100` once it counts as a long press.
+    pub fn hold_progress(&self) -> u8 {
+        if self.threshold == 0 {
+            return 100;
+        }
+
+        (self.held as u32 * 100 / self.threshold as u32) as u8
+    }
+}
+
+/// An auto-repeat (typematic) generator.
+///
+/// Reports the press of a button, then once the button has been held
+/// for the repeat delay, reports it again every repeat interval for as
+/// long as it is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoRepeat {
+    delay: u16,
+    interval: u16,
+    pressed: bool,
+    countdown: u16,
+}
+
+impl AutoRepeat {
+    /// Create a new auto-repeat generator with the given timing.
+    #[inline]
+    pub const fn new(timing: GestureTiming) -> Self {
+        AutoRepeat {
+            delay: timing.repeat_delay,
+            interval: timing.repeat_interval,
+            pressed: false,
+            countdown: 0,
+        }
+    }
+
+    /// Adopt new timing, such as one adjusted at runtime.
+    ///
+    /// The change takes effect from the next repeat.
+    #[inline]
+    pub fn set_timing(&mut self, timing: GestureTiming) {
+        self.delay = timing.repeat_delay;
+        self.interval = timing.repeat_interval;
+    }
+
+    /// Update the generator with the debounced state of the button.
+    ///
+    /// This should be called once per poll.  Returns `true` on the poll
+    /// that the button is pressed, and on each repeat while it's held.
+    pub fn update(&mut self, pressed: bool) -> bool {
+        if !pressed {
+            self.pressed = false;
+            return false;
+        }
+
+        if !self.pressed {
+            self.pressed = true;
+            self.countdown = self.delay;
+            return true;
+        }
+
+        self.countdown = self.countdown.saturating_sub(1);
+        if self.countdown == 0 {
+            self.countdown = self.interval;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// A gesture of one channel of a bank, as reported by
+/// [`BankGestures`](BankGestures).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BankGesture {
+    /// The channel was pressed.
+    Pressed(u8),
+
+    /// The channel was released.
+    Released(u8),
+
+    /// The channel was held past the long-press threshold.
+    LongPress(u8),
+
+    /// The held channel repeated its press.
+    Repeat(u8),
+}
+
+/// Gesture recognizers for selected channels of a bank.
+///
+/// Every channel reports its presses and releases, but only the `L`
+/// channels given for long presses get a [`LongPress`](LongPress), and
+/// only the `R` channels given for repeats get an
+/// [`AutoRepeat`](AutoRepeat), so the buttons that don't need rich
+/// gestures don't pay for them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BankGestures<const L: usize, const R: usize> {
+    pressed: u32,
+    long_press: [(u8, LongPress); L],
+    repeat: [(u8, AutoRepeat); R],
+}
+
+impl<const L: usize, const R: usize> BankGestures<L, R> {
+    /// Recognize long presses on the `long_press` channels, and repeats
+    /// on the `repeat` channels, with the given timing.  All channels
+    /// start out released.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a channel is beyond the 32 of a bank.
+    pub const fn new(timing: GestureTiming, long_press: [u8; L], repeat: [u8; R]) -> Self {
+        let mut gestures = BankGestures {
+            pressed: 0,
+            long_press: [(0, LongPress::new(timing)); L],
+            repeat: [(0, AutoRepeat::new(timing)); R],
+        };
+
+        let mut i = 0;
+        while i < L {
+            assert!(long_press[i] < 32, "a bank has at most 32 channels");
+            gestures.long_press[i].0 = long_press[i];
+            i += 1;
+        }
+        let mut i = 0;
+        while i < R {
+            assert!(repeat[i] < 32, "a bank has at most 32 channels");
+            gestures.repeat[i].0 = repeat[i];
+            i += 1;
+        }
+
+        gestures
+    }
+
+    /// Update the recognizers with the debounced state of the bank.
+    ///
+    /// Bit `n` of `pressed` is set while channel `n` is pressed; for
+    /// active-low buttons, that's the inverse of the bank's
+    /// [`state()`](crate::bank::DebouncerBank#method.state).  This
+    /// should be called once per poll.  Calls `on_gesture` with the
+    /// presses and releases, in channel order, and then with any long
+    /// presses and repeats.
+    pub fn update<F: FnMut(BankGesture)>(&mut self, pressed: u32, mut on_gesture: F) {
+        let changed = pressed ^ self.pressed;
+        self.pressed = pressed;
+
+        for channel in 0..32 {
+            if changed & (1 << channel) != 0 {
+                on_gesture(if pressed & (1 << channel) != 0 {
+                    BankGesture::Pressed(channel)
+                } else {
+                    BankGesture::Released(channel)
+                });
+            }
+        }
+
+        for (channel, long_press) in self.long_press.iter_mut() {
+            if long_press.update(pressed & (1 << *channel) != 0) {
+                on_gesture(BankGesture::LongPress(*channel));
+            }
+        }
+
+        for (channel, repeat) in self.repeat.iter_mut() {
+            let mask = 1 << *channel;
+            // The press itself was reported above.
+            if repeat.update(pressed & mask != 0) && changed & mask == 0 {
+                on_gesture(BankGesture::Repeat(*channel));
+            }
+        }
+    }
+
+    /// The long-press recognizer of `channel`, if it has one, to check
+    /// on its progress.
+    pub fn long_press(&self, channel: u8) -> Option<&LongPress> {
+        self.long_press
+            .iter()
+            .find(|(long_press_channel, _)| *long_press_channel == channel)
+            .map(|(_, long_press)| long_press)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_timing() {
+        let timing = GestureTiming::default();
+
+        assert_eq!(50, timing.long_press);
+        assert_eq!(30, timing.multi_click);
+        assert_eq!(50, timing.repeat_delay);
+        assert_eq!(10, timing.repeat_interval);
+    }
+
+    #[test]
+    fn millis_conversion() {
+        assert_eq!(1, polls_from_millis(100, 0));
+        assert_eq!(1, polls_from_millis(100, 4));
+        assert_eq!(1, polls_from_millis(100, 14));
+        assert_eq!(2, polls_from_millis(100, 15));
+        assert_eq!(3, polls_from_millis(1, 3000));
+        assert_eq!(u16::MAX, polls_from_millis(10_000, 60_000));
+    }
+
+    #[test]
+    fn long_press_progress() {
+        let timing = GestureTiming {
+            long_press: 4,
+            ..GestureTiming::default()
+        };
+        let mut long_press = LongPress::new(timing);
+
+        assert_eq!(false, long_press.update(false));
+        assert_eq!(0, long_press.hold_progress());
+
+        assert_eq!(false, long_press.update(true));
+        assert_eq!(25, long_press.hold_progress());
+        assert_eq!(false, long_press.update(true));
+        assert_eq!(false, long_press.update(true));
+        assert_eq!(75, long_press.hold_progress());
+        assert_eq!(false, long_press.is_long_press());
+
+        assert_eq!(true, long_press.update(true));
+        assert_eq!(100, long_press.hold_progress());
+        assert_eq!(true, long_press.is_long_press());
+
+        assert_eq!(false, long_press.update(true));
+        assert_eq!(100, long_press.hold_progress());
+
+        assert_eq!(false, long_press.update(false));
+        assert_eq!(0, long_press.hold_progress());
+        assert_eq!(0, long_press.held());
+    }
+
+    #[test]
+    fn long_press_retimed() {
+        let timing = GestureTiming {
+            long_press: 4,
+            ..GestureTiming::default()
+        };
+        let mut long_press = LongPress::new(timing);
+
+        long_press.update(true);
+        long_press.update(true);
+        long_press.update(true);
+
+        long_press.set_timing(GestureTiming {
+            long_press: 2,
+            ..timing
+        });
+        assert_eq!(true, long_press.is_long_press());
+        assert_eq!(false, long_press.update(true));
+    }
+
+    #[test]
+    fn auto_repeat() {
+        let timing = GestureTiming {
+            repeat_delay: 3,
+            repeat_interval: 2,
+            ..GestureTiming::default()
+        };
+        let mut repeat = AutoRepeat::new(timing);
+
+        assert_eq!(false, repeat.update(false));
+
+        let held = [true, false, false, true, false, true, false, true];
+        for &expected in held.iter() {
+            assert_eq!(expected, repeat.update(true));
+        }
+
+        // Releasing and pressing again starts over.
+        assert_eq!(false, repeat.update(false));
+        assert_eq!(true, repeat.update(true));
+        assert_eq!(false, repeat.update(true));
+    }
+
+    #[test]
+    fn bank_gestures_per_channel() {
+        extern crate std;
+        use std::vec::Vec;
+
+        let timing = GestureTiming {
+            long_press: 3,
+            repeat_delay: 2,
+            repeat_interval: 2,
+            ..GestureTiming::default()
+        };
+        let mut gestures = BankGestures::new(timing, [0], [1]);
+
+        let mut events = Vec::new();
+        for &pressed in [0b111, 0b111, 0b111, 0b111, 0b010, 0].iter() {
+            gestures.update(pressed, |gesture| events.push(gesture));
+        }
+
+        // Channel 2 gets presses and releases, but no gestures.
+        assert_eq!(
+            events,
+            [
+                BankGesture::Pressed(0),
+                BankGesture::Pressed(1),
+                BankGesture::Pressed(2),
+                BankGesture::LongPress(0),
+                BankGesture::Repeat(1),
+                BankGesture::Released(0),
+                BankGesture::Released(2),
+                BankGesture::Repeat(1),
+                BankGesture::Released(1),
+            ]
+        );
+        assert_eq!(false, gestures.long_press(0).unwrap().is_long_press());
+        assert!(gestures.long_press(2).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 32 channels")]
+    fn bank_gestures_channel_out_of_range() {
+        BankGestures::new(GestureTiming::default(), [32], []);
+    }
+
+    /// Acceptance tests describing button behavior in human terms.
+    ///
+    /// Each scenario lists how long the button is held in each position,
+    /// which is compiled into one raw sample per poll at the given poll
+    /// rate and run through a `Debouncer` and the gesture recognizers.
+    /// The exact sequence of events is then compared, along with the
+    /// poll at which each was reported.
+    mod scenario {
+        extern crate std;
+
+        use std::vec::Vec;
+
+        use core::cell::Cell;
+
+        use embedded_hal::digital::v2::InputPin;
+
+        use super::*;
+        use crate::{debouncer_uninit, default::ActiveLow, Debouncer};
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        enum Gesture {
+            Pressed,
+            Released,
+            LongPress,
+        }
+
+        #[derive(Debug, Clone, Copy)]
+        enum Step {
+            Press(u32),
+            Release(u32),
+        }
+        use Step::*;
+
+        // An active-low button wired to a cell we control.
+        struct Button<'a>(&'a Cell<bool>);
+
+        impl<'a> InputPin for Button<'a> {
+            type Error = core::convert::Infallible;
+
+            fn is_high(&self) -> Result<bool, Self::Error> {
+                Ok(!self.0.get())
+            }
+
+            fn is_low(&self) -> Result<bool, Self::Error> {
+                Ok(self.0.get())
+            }
+        }
+
+        fn run(poll_hz: u32, steps: &[Step]) -> Vec<(u32, Gesture)> {
+            let timing = GestureTiming::from_millis(poll_hz, 500, 300, 500, 100);
+            let mut long_press = LongPress::new(timing);
+
+            let pressed = Cell::new(false);
+            let debouncer: Debouncer<_, ActiveLow> = debouncer_uninit!();
+            // It is always safe to init a stack-scoped Debouncer.
+            let debounced = unsafe { debouncer.init(Button(&pressed)) }.unwrap();
+
+            let mut was_pressed = false;
+            let mut events = Vec::new();
+            let mut poll = 0;
+
+            for &step in steps {
+                let (level, millis) = match step {
+                    Press(millis) => (true, millis),
+                    Release(millis) => (false, millis),
+                };
+                pressed.set(level);
+
+                for _ in 0..(millis * poll_hz / 1000) {
+                    poll += 1;
+
+                    // It is always safe to poll a stack-scoped Debouncer.
+                    unsafe { debouncer.poll() }.unwrap();
+                    let is_pressed = debounced.is_low().unwrap();
+
+                    if is_pressed != was_pressed {
+                        was_pressed = is_pressed;
+                        let event = if is_pressed {
+                            Gesture::Pressed
+                        } else {
+                            Gesture::Released
+                        };
+                        events.push((poll, event));
+                    }
+
+                    if long_press.update(is_pressed) {
+                        events.push((poll, Gesture::LongPress));
+                    }
+                }
+            }
+
+            events
+        }
+
+        #[test]
+        fn click() {
+            assert_eq!(
+                run(100, &[Release(100), Press(100), Release(100)]),
+                [(14, Gesture::Pressed), (24, Gesture::Released)]
+            );
+        }
+
+        #[test]
+        fn bounce_is_ignored() {
+            assert_eq!(
+                run(
+                    100,
+                    &[
+                        Release(100),
+                        Press(30),
+                        Release(50),
+                        Press(20),
+                        Release(100)
+                    ]
+                ),
+                []
+            );
+        }
+
+        #[test]
+        fn bouncy_click() {
+            assert_eq!(
+                run(
+                    100,
+                    &[
+                        Press(20),
+                        Release(10),
+                        Press(60),
+                        Release(20),
+                        Press(10),
+                        Release(100)
+                    ]
+                ),
+                [(6, Gesture::Pressed), (15, Gesture::Released)]
+            );
+        }
+
+        #[test]
+        fn long_press() {
+            assert_eq!(
+                run(100, &[Press(400), Release(50), Press(600), Release(100)]),
+                [
+                    (4, Gesture::Pressed),
+                    (44, Gesture::Released),
+                    (49, Gesture::Pressed),
+                    (98, Gesture::LongPress),
+                    (109, Gesture::Released),
+                ]
+            );
+        }
+
+        #[test]
+        fn long_press_at_faster_poll_rate() {
+            assert_eq!(
+                run(1000, &[Press(520), Release(30)]),
+                [
+                    (4, Gesture::Pressed),
+                    (503, Gesture::LongPress),
+                    (524, Gesture::Released)
+                ]
+            );
+        }
+    }
+}
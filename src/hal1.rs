@@ -0,0 +1,119 @@
+//! Mix pins from `embedded-hal` 0.2 and 1.0.
+//!
+//! Firmware migrating from `embedded-hal` 0.2 to 1.0 often ends up with
+//! pins from both generations for a while.  The
+//! [`Debouncer`](crate::Debouncer) reads any 0.2 `InputPin` directly,
+//! since those implement [`Sampler`](crate::Sampler).  Wrap a 1.0
+//! `InputPin` in a [`Hal1Pin`](Hal1Pin) to debounce it just the same,
+//! choosing the generation for each debouncer separately.
+//!
+//! The debounced pin implements the `InputPin` traits of both
+//! generations, so it can be handed on to either kind of driver.
+//!
+//! ```
+//! # struct NewPin;
+//! # impl embedded_hal_1::digital::ErrorType for NewPin {
+//! #     type Error = core::convert::Infallible;
+//! # }
+//! # impl embedded_hal_1::digital::InputPin for NewPin {
+//! #     fn is_high(&mut self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&mut self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow, hal1::Hal1Pin};
+//!
+//! static DEBOUNCER: Debouncer<Hal1Pin<NewPin>, ActiveLow> = debouncer_uninit!();
+//!
+//! # let input_pin = NewPin;
+//! let debounced = unsafe { DEBOUNCER.init(Hal1Pin(input_pin)) }.unwrap();
+//! ```
+
+use core::convert::Infallible;
+
+use embedded_hal_1::digital::{ErrorType, InputPin};
+
+use crate::{Debounce, Debounced, Sampler};
+
+/// An `embedded-hal` 1.0 input pin, to be debounced.
+#[derive(Debug)]
+pub struct Hal1Pin<Pin>(pub Pin);
+
+impl<Pin> Hal1Pin<Pin> {
+    /// Unwrap the input pin.
+    #[inline]
+    pub fn into_inner(self) -> Pin {
+        self.0
+    }
+}
+
+impl<Pin: InputPin> Sampler for Hal1Pin<Pin> {
+    type Error = Pin::Error;
+
+    #[inline(always)]
+    fn sample(&mut self) -> Result<bool, Self::Error> {
+        self.0.is_low().map(|low| !low)
+    }
+}
+
+impl<'state, Cfg: Debounce> ErrorType for Debounced<'state, Cfg> {
+    type Error = Infallible;
+}
+
+impl<'state, Cfg: Debounce> InputPin for Debounced<'state, Cfg> {
+    #[inline(always)]
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        embedded_hal::digital::v2::InputPin::is_high(self)
+    }
+
+    #[inline(always)]
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        embedded_hal::digital::v2::InputPin::is_low(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{debouncer_uninit, Debouncer, Edge, Level};
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 2;
+        const INIT_HIGH: bool = false;
+    }
+
+    struct NewPin;
+    impl ErrorType for NewPin {
+        type Error = Infallible;
+    }
+    impl InputPin for NewPin {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            Ok(true)
+        }
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            Ok(false)
+        }
+    }
+
+    #[test]
+    fn debounce_hal1_pin() {
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let mut debounced = unsafe { debouncer.init(Hal1Pin(NewPin)) }.unwrap();
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(Edge::NoChange, unsafe { debouncer.poll() }.unwrap());
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(Edge::Rising, unsafe { debouncer.poll() }.unwrap());
+        assert_eq!(Level::High, debounced.state());
+        assert_eq!(Ok(true), InputPin::is_high(&mut debounced));
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        let NewPin = unsafe { debouncer.deinit(debounced) }.unwrap().into_inner();
+    }
+}
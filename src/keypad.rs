@@ -0,0 +1,242 @@
+//! Scan a matrix keypad.
+//!
+//! A keypad of `R` rows and `C` columns needs only `R + C` pins for its
+//! `R * C` keys.  Each row is driven low in turn, with the others held
+//! high, and the columns (pulled up) read low wherever a key in the
+//! driven row is pressed.
+//!
+//! The [`Keypad`](Keypad) drives the rows and samples the columns,
+//! running each key through its own integrator, and reports each
+//! debounced press and release as a [`KeyEvent`](KeyEvent).
+//!
+//! ```
+//! # use core::convert::Infallible;
+//! # struct Row;
+//! # impl embedded_hal::digital::v2::OutputPin for Row {
+//! #     type Error = Infallible;
+//! #     fn set_low(&mut self) -> Result<(), Infallible> { Ok(()) }
+//! #     fn set_high(&mut self) -> Result<(), Infallible> { Ok(()) }
+//! # }
+//! # struct Col;
+//! # impl embedded_hal::digital::v2::InputPin for Col {
+//! #     type Error = Infallible;
+//! #     fn is_high(&self) -> Result<bool, Infallible> { Ok(true) }
+//! #     fn is_low(&self) -> Result<bool, Infallible> { Ok(false) }
+//! # }
+//! # let (r0, r1, r2, r3, c0, c1, c2) = (Row, Row, Row, Row, Col, Col, Col);
+//! use unflappable::{default::ActiveLow, keypad::{KeyEvent, Keypad}};
+//!
+//! let mut keypad: Keypad<_, _, ActiveLow, 4, 3> =
+//!     Keypad::new([r0, r1, r2, r3], [c0, c1, c2]).unwrap();
+//!
+//! // In the poll routine:
+//! keypad.scan(|event| match event {
+//!     KeyEvent::Down { row, col } => {
+//!         // a key was pressed
+//!     }
+//!     KeyEvent::Up { row, col } => {
+//!         // a key was released
+//!     }
+//! })
+//! .unwrap();
+//! ```
+
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+use crate::{Debounce, DebounceExt, Debouncer, Edge};
+
+/// An event from a [`Keypad`](Keypad).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEvent {
+    /// The key at the given row and column was pressed.
+    Down {
+        /// The row of the key.
+        row: usize,
+        /// The column of the key.
+        col: usize,
+    },
+
+    /// The key at the given row and column was released.
+    Up {
+        /// The row of the key.
+        row: usize,
+        /// The column of the key.
+        col: usize,
+    },
+}
+
+/// An error that arose while scanning a [`Keypad`](Keypad).
+#[derive(Debug)]
+pub enum KeypadError<RowError, ColError> {
+    /// An error driving one of the rows.
+    Row(RowError),
+
+    /// An error reading one of the columns.
+    Col(ColError),
+}
+
+/// A matrix keypad of `ROWS` rows and `COLS` columns.
+///
+/// Each call to [`scan()`](#method.scan) samples the columns for the
+/// currently driven row and then drives the next one, giving the lines
+/// a full poll period to settle.  Since each key is only sampled on
+/// every `ROWS`th scan, you'll want to scan `ROWS` times as often as
+/// you would poll a single button.
+///
+/// A key is considered pressed when its debounced level differs from
+/// the configured
+/// [`INIT_HIGH`](crate::Debounce#associatedconstant.INIT_HIGH), so with
+/// the usual pull-ups on the columns, use a configuration with
+/// `INIT_HIGH` set.
+pub struct Keypad<Row, Col, Cfg: Debounce, const ROWS: usize, const COLS: usize> {
+    rows: [Row; ROWS],
+    cols: [Col; COLS],
+    keys: [[Debouncer<(), Cfg>; COLS]; ROWS],
+    driven: usize,
+}
+
+impl<Row, Col, Cfg, const ROWS: usize, const COLS: usize> Keypad<Row, Col, Cfg, ROWS, COLS>
+where
+    Row: OutputPin,
+    Col: InputPin,
+    Cfg: Debounce,
+{
+    /// Set up the keypad with its row and column pins.
+    ///
+    /// The first row is driven low, and the rest high, so the first
+    /// scan reads the first row.
+    pub fn new(mut rows: [Row; ROWS], cols: [Col; COLS]) -> Result<Self, Row::Error> {
+        for (n, row) in rows.iter_mut().enumerate() {
+            if n == 0 {
+                row.set_low()?;
+            } else {
+                row.set_high()?;
+            }
+        }
+
+        let keys = [(); ROWS].map(|_| {
+            [(); COLS].map(|_| {
+                let key = Debouncer::uninit(Cfg::zero());
+                key.check_config();
+                // A new debouncer is never already initialized.
+                let _ = key.init_linted(());
+                key
+            })
+        });
+
+        Ok(Keypad {
+            rows,
+            cols,
+            keys,
+            driven: 0,
+        })
+    }
+
+    /// Sample the driven row, then drive the next one.
+    ///
+    /// `on_event` is called for each key in the row that was pressed or
+    /// released.
+    pub fn scan<F: FnMut(KeyEvent)>(
+        &mut self,
+        mut on_event: F,
+    ) -> Result<(), KeypadError<Row::Error, Col::Error>> {
+        let row = self.driven;
+
+        for (col, pin) in self.cols.iter().enumerate() {
+            let high = pin.is_high().map_err(KeypadError::Col)?;
+
+            let pressed = match self.keys[row][col].integrate(high) {
+                Edge::NoChange => continue,
+                Edge::Rising => !Cfg::INIT_HIGH,
+                Edge::Falling => Cfg::INIT_HIGH,
+            };
+            on_event(if pressed {
+                KeyEvent::Down { row, col }
+            } else {
+                KeyEvent::Up { row, col }
+            });
+        }
+
+        if ROWS > 1 {
+            self.driven = (row + 1) % ROWS;
+            self.rows[row].set_high().map_err(KeypadError::Row)?;
+            self.rows[self.driven].set_low().map_err(KeypadError::Row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Is the key at the given row and column pressed?
+    ///
+    /// # Panics
+    ///
+    /// Panics if the row or column is out of range.
+    #[inline]
+    pub fn is_pressed(&self, row: usize, col: usize) -> bool {
+        self.keys[row][col].state_flag() != Cfg::INIT_HIGH
+    }
+
+    /// Destroy the keypad, returning the row and column pins.
+    pub fn release(self) -> ([Row; ROWS], [Col; COLS]) {
+        (self.rows, self.cols)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    use embedded_hal_mock::pin;
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 1;
+        const INIT_HIGH: bool = true;
+    }
+
+    #[test]
+    fn scan_two_by_two() {
+        let set_low = pin::Transaction::set(pin::State::Low);
+        let set_high = pin::Transaction::set(pin::State::High);
+        let low = pin::Transaction::get(pin::State::Low);
+        let high = pin::Transaction::get(pin::State::High);
+
+        let rows = [
+            pin::Mock::new(&[
+                set_low.clone(),
+                set_high.clone(),
+                set_low.clone(),
+                set_high.clone(),
+            ]),
+            pin::Mock::new(&[set_high.clone(), set_low.clone(), set_high, set_low]),
+        ];
+        let cols = [
+            pin::Mock::new(&[low, high.clone(), high.clone()]),
+            pin::Mock::new(&[high.clone(), high.clone(), high]),
+        ];
+
+        let mut keypad: Keypad<_, _, Cfg, 2, 2> = Keypad::new(rows, cols).unwrap();
+
+        let mut events = [None; 3];
+        for event in events.iter_mut() {
+            keypad.scan(|e| *event = Some(e)).unwrap();
+        }
+        assert_eq!(
+            [
+                Some(KeyEvent::Down { row: 0, col: 0 }),
+                None,
+                Some(KeyEvent::Up { row: 0, col: 0 })
+            ],
+            events
+        );
+        assert_eq!(false, keypad.is_pressed(0, 0));
+        assert_eq!(false, keypad.is_pressed(1, 0));
+
+        let (mut rows, mut cols) = keypad.release();
+        for pin in rows.iter_mut().chain(cols.iter_mut()) {
+            pin.done();
+        }
+    }
+}
@@ -17,7 +17,28 @@
 //! # Minimum supported Rust version
 //!
 //! This crate makes use of trait bounds on a `const fn`, which
-//! requires Rust 1.61.
+//! requires Rust 1.61.  Some optional features depend on crates that
+//! need a newer compiler, and so they do too:
+//!
+//! - `derive` requires Rust 1.71, for `syn` 2.
+//! - `async`, `stream`, `embassy-sync` and `heapless` require Rust
+//!   1.75, for async traits (`heapless` makes no promise of its own,
+//!   and is checked at the same version).
+//! - `defmt` requires Rust 1.76, for `defmt` 1.0.
+//! - `cortex-m` requires Rust 1.85, for the latest `cortex-m` 0.7.
+//!
+//! CI checks each of these at its minimum version.
+//!
+//! # No allocation
+//!
+//! This crate never allocates, and doesn't depend on `alloc`.  All
+//! state lives in storage you provide, whether a `static` or the stack.
+//! A test exercising the public API under an allocator that refuses
+//! every request keeps it that way.
+//!
+//! The one exception is the optional `alloc` feature, which adds the
+//! [`dynamic`](dynamic) module for systems with a number of inputs
+//! only known at runtime.  Nothing else in the crate uses it.
 //!
 //! # Usage
 //!
@@ -96,8 +117,8 @@
 //! # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
 //! # static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
 //! # struct Error;
-//! # impl From<unflappable::InitError> for Error {
-//! #   fn from(_: unflappable::InitError) -> Error {
+//! # impl<Pin> From<unflappable::InitError<Pin>> for Error {
+//! #   fn from(_: unflappable::InitError<Pin>) -> Error {
 //! #       Error
 //! #   }
 //! # }
@@ -168,27 +189,247 @@
 //! place in your code.  We'd use a `&mut` reference, but, well, it's
 //! in static storage.
 //!
+//! Each call to `poll()` also returns the debounced [`Edge`](Edge), if
+//! any, so an interrupt service routine can react to a press as soon as
+//! it's been debounced.
+//!
+//! # Async
+//!
+//! With the `async` feature enabled, the [`Debounced`](Debounced) pin
+//! also implements [`embedded_hal_async::digital::Wait`][4], so a task
+//! can `await` a debounced edge rather than checking in on the pin.
+//! Each `poll()` that completes a transition wakes the waiting task.
+//! Async traits require Rust 1.75, so this feature does too.
+//!
+//! The `stream` feature goes a step further, offering the debounced
+//! transitions as a [`futures_core::Stream`][5] of edges from
+//! [`Debounced::edges()`](Debounced#method.edges).
+//!
+//! # Atomics
+//!
+//! With the `atomic` feature enabled, the
+//! [`atomic::AtomicDebouncer`](atomic::AtomicDebouncer) keeps `u8`
+//! state in an `AtomicU8`, so that polling and reading the debounced pin
+//! are safe without any critical section.  It's also the one to use
+//! when polling and reading happen on different cores.  On targets
+//! without compare-and-swap, such as the RP2040, it also needs the
+//! `critical-section` feature; the `rp2040` feature turns on both.
+//!
+//! # Critical sections
+//!
+//! With the `critical-section` feature enabled, the
+//! [`shared::SharedDebouncer`](shared::SharedDebouncer) offers safe
+//! versions of `init()`, `poll()` and `deinit()`, each run inside a
+//! critical section.  The `unsafe` methods of `Debouncer` remain for
+//! those who would rather not pay for it.
+//!
+//! # Cortex-M
+//!
+//! With the `cortex-m` feature enabled, debug builds on Cortex-M
+//! targets check that `poll()` is always called from the same
+//! interrupt, catching a common violation of its safety requirements.
+//!
+//! # Embassy
+//!
+//! With the `embassy-sync` feature enabled, the [`embassy`](embassy)
+//! module lets the poll routine publish each debounced edge to an
+//! `embassy-sync` `Signal` or `Channel`, for Embassy tasks to await.
+//!
+//! # Defmt
+//!
+//! With the `defmt` feature enabled, the public status and error types
+//! implement `defmt::Format`, so they can be logged directly.  Types
+//! holding a pin format it as `_`, as with `Debug`.
+//!
+//! # Derive
+//!
+//! With the `derive` feature enabled, a configuration can be derived
+//! rather than written out, and its `MAX_COUNT` is checked against the
+//! storage at compile time:
+//!
+//! ```
+//! # #[cfg(feature = "derive")]
+//! # mod derived {
+//! use unflappable::Debounce;
+//!
+//! #[derive(Debounce)]
+//! #[debounce(storage = u8, max_count = 4, init_high)]
+//! struct Button;
+//! # }
+//! ```
+//!
+//! Instead of `max_count`, give the debounce time and poll rate as
+//! `debounce_ms` and `poll_hz`, and the count is worked out for you,
+//! rounding up:
+//!
+//! ```
+//! # #[cfg(feature = "derive")]
+//! # mod derived {
+//! use unflappable::Debounce;
+//!
+//! // A `MAX_COUNT` of 6.
+//! #[derive(Debounce)]
+//! #[debounce(debounce_ms = 30, poll_hz = 200)]
+//! struct Button;
+//! # }
+//! ```
+//!
+//! # embedded-hal 1.0
+//!
+//! With the `embedded-hal-1` feature enabled (which `async` implies),
+//! the [`hal1`](hal1) module adapts `embedded-hal` 1.0 input pins for
+//! debouncing, and the debounced pin implements the 1.0 `InputPin`
+//! too, so pins from both generations can be mixed in one firmware.
+//!
+//! # Event queue
+//!
+//! With the `heapless` feature enabled, the [`queue`](queue) module lets
+//! the poll routine push each debounced edge into a lock-free queue, so
+//! that the application doesn't miss short presses between checks.
+//!
+//! # fugit
+//!
+//! With the `fugit` feature enabled, debounce times and poll periods can
+//! be given as `fugit` durations, in the same time units as the rest of
+//! an RTIC or Embassy firmware: see
+//! [`default::max_count_for()`](default::max_count_for),
+//! [`Timing::min_delay()`](Timing::min_delay) and
+//! [`PollInterval::from_period()`](time::PollInterval::from_period).
+//! Timestamped edges and the times between them convert to `fugit`
+//! instants and durations, given the rate of the clock: see
+//! [`Timestamped::instant()`](time::Timestamped::instant) and
+//! [`EdgeTiming::since_opposite_duration()`](time::EdgeTiming::since_opposite_duration).
+//!
+//! # Observers
+//!
+//! With the `observer` feature enabled, the debouncer can be initialized
+//! with an [`Observer`](Observer) using
+//! [`init_observed()`](Debouncer#method.init_observed).  Its callbacks
+//! are called from `poll()` on each debounced transition, so the
+//! interrupt service routine can toggle an LED or set a flag directly.
+//!
+//! # Panic dumps
+//!
+//! With the `panic-dump` feature enabled, the
+//! [`panic_dump`](panic_dump) module keeps a set of debouncers to dump
+//! from a panic handler, for crash triage.
+//!
+//! # RTIC
+//!
+//! With the `rtic` feature enabled, the [`rtic`](rtic) module provides
+//! a debouncer that is kept as an ordinary RTIC shared resource, so
+//! that it needn't be a `static`, and needs no `unsafe`.
+//!
+//! # Statistics
+//!
+//! With the `stats` feature enabled, each debouncer counts its debounced
+//! transitions and the flips of its raw samples over its lifetime,
+//! readable with [`stats()`](Debouncer#method.stats).  A rising count of
+//! flips per transition is a sign of a switch wearing out.
+//! With the `cbor` feature, the [`cbor`](cbor) module encodes them for
+//! shipping to a companion app.
+//!
+//! # Testing
+//!
+//! With the `test-util` feature enabled, the [`test_util`](test_util)
+//! module provides a [`VirtualClock`](test_util::VirtualClock) to drive
+//! time-dependent code deterministically in host tests.
+//!
+//! # Tuning
+//!
+//! With the `tuning` feature enabled, the [`tuning`](tuning) module
+//! provides a framed protocol for streaming samples, integrator values
+//! and edges to a host tool, to help choose debounce settings.
+//!
 //! [0]: https://github.com/rust-embedded/embedded-hal
 //! [1]: http://www.kennethkuhn.com/electronics/debounce.c
 //! [2]: https://github.com/rust-lang/rust/issues/67792
 //! [3]: https://docs.rs/embedded-hal/0.2.7/embedded_hal/digital/v2/trait.InputPin.html
+//! [4]: https://docs.rs/embedded-hal-async/1.0.0/embedded_hal_async/digital/trait.Wait.html
+//! [5]: https://docs.rs/futures-core/0.3/futures_core/stream/trait.Stream.html
 
 #![no_std]
 #![deny(missing_docs)]
 #![doc(html_root_url = "https://docs.rs/unflappable/0.2.0")]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 use core::cell::UnsafeCell;
 use core::convert::Infallible;
 use core::marker::PhantomData;
 use core::mem::MaybeUninit;
 use core::ops::{AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, Not, Shl, Shr, SubAssign};
 
-use embedded_hal::digital::v2::InputPin;
+use embedded_hal::digital::v2::{InputPin, PinState};
+use embedded_hal::timer::{CountDown, Periodic};
+
+#[cfg(feature = "derive")]
+pub use unflappable_derive::Debounce;
+
+pub mod analog;
+#[cfg(feature = "atomic")]
+pub mod atomic;
+pub mod audit;
+pub mod bank;
+pub mod blocking;
+pub mod budget;
+pub mod button;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod chatter;
+pub mod chord;
+pub mod context;
+pub mod differential;
+#[cfg(feature = "alloc")]
+pub mod dynamic;
+#[cfg(feature = "embassy-sync")]
+pub mod embassy;
+pub mod encoder;
+pub mod event;
+pub mod fault;
+pub mod filter;
+pub mod gesture;
+#[cfg(feature = "embedded-hal-1")]
+pub mod hal1;
+pub mod keypad;
+#[cfg(feature = "panic-dump")]
+pub mod panic_dump;
+#[cfg(feature = "heapless")]
+pub mod queue;
+pub mod remap;
+#[cfg(feature = "rtic")]
+pub mod rtic;
+pub mod runtime;
+pub mod safety;
+pub mod scheduler;
+#[cfg(feature = "critical-section")]
+pub mod shared;
+pub mod snapshot;
+pub mod stack;
+#[cfg(feature = "stream")]
+pub mod stream;
+pub mod stuck;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod time;
+pub mod transition;
+#[cfg(feature = "tuning")]
+pub mod tuning;
+pub mod typestate;
+pub mod watchdog;
+
+// The `async` feature has its own MSRV.
+#[cfg(feature = "async")]
+#[clippy::msrv = "1.75"]
+mod wait;
 
 /// Static configuration of the debouncing algorithm.
 pub trait Debounce {
     /// The storage type of the state.  For most usages, `u8` is plenty
-    /// big enough.  You almost certainly don't need more than a `u8`.
+    /// big enough.  You almost certainly don't need more than a `u8`,
+    /// unless debouncing very slow signals (see
+    /// [`default::SlowSignal`](default::SlowSignal)).
     type Storage: From<u8>
         + BitAnd<Output = Self::Storage>
         + BitAndAssign
@@ -223,12 +464,110 @@ pub trait Debounce {
     /// any meaningful debouncing, it must be greater than 1.
     const MAX_COUNT: Self::Storage;
 
+    /// The number of samples required to mark a rising edge.
+    ///
+    /// Some switches bounce for much longer on opening than on closing,
+    /// or vice versa.  Set this and
+    /// [`MAX_COUNT_FALL`](#associatedconstant.MAX_COUNT_FALL) to tune
+    /// each direction independently.  The same restrictions as for
+    /// [`MAX_COUNT`](#associatedconstant.MAX_COUNT) apply.  Defaults to
+    /// `MAX_COUNT`.
+    const MAX_COUNT_RISE: Self::Storage = Self::MAX_COUNT;
+
+    /// The number of samples required to mark a falling edge.
+    ///
+    /// See [`MAX_COUNT_RISE`](#associatedconstant.MAX_COUNT_RISE).
+    /// Defaults to `MAX_COUNT`.
+    const MAX_COUNT_FALL: Self::Storage = Self::MAX_COUNT;
+
+    /// The integrator level at which a low pin is set high.
+    ///
+    /// By default the integrator must reach its top rail to change
+    /// state, and the bottom rail to change back.  Setting thresholds
+    /// inside the rails, say at 75% and 25% of `MAX_COUNT`, gives
+    /// Schmitt-trigger behavior: a clean signal changes state sooner,
+    /// while noise around either threshold still can't cause a flap.
+    ///
+    /// `CLEAR_THRESHOLD` must be less than `SET_THRESHOLD`, which must
+    /// be no more than either of `MAX_COUNT_RISE` or `MAX_COUNT_FALL`.
+    /// Defaults to `None`, meaning the top rail.
+    const SET_THRESHOLD: Option<Self::Storage> = None;
+
+    /// The integrator level at which a high pin is cleared low.
+    ///
+    /// See [`SET_THRESHOLD`](#associatedconstant.SET_THRESHOLD).
+    /// Defaults to `None`, meaning the bottom rail.
+    const CLEAR_THRESHOLD: Option<Self::Storage> = None;
+
     /// The initial state of the pin.
     ///
     /// If `INIT_HIGH` is true, the debounced pin will start high and
     /// wait for the first falling edge.  If this is false, the pin
     /// will start low and wait for the first debounced rising edge.
     const INIT_HIGH: bool;
+
+    /// The initial value of the integrator.
+    ///
+    /// By default the integrator starts out at the rail for the initial
+    /// state, so the first transition needs a full count.  Starting it
+    /// part of the way instead, say at mid-scale, lets the first real
+    /// transition through sooner while still absorbing a startup glitch
+    /// in either direction.
+    ///
+    /// With `INIT_HIGH` set this must be above `CLEAR_THRESHOLD` and no
+    /// more than `MAX_COUNT_FALL`, and otherwise it must be below
+    /// `SET_THRESHOLD`.  Ignored by the majority-vote algorithm.
+    /// Defaults to `None`, meaning the rail.
+    const INIT_COUNT: Option<Self::Storage> = None;
+
+    /// The debouncing algorithm to use.
+    ///
+    /// Defaults to [`Algorithm::Integrator`](Algorithm::Integrator).
+    const ALGORITHM: Algorithm = Algorithm::Integrator;
+
+    /// The level of the pin when the input is active, such as a button
+    /// being pressed.
+    ///
+    /// Used to report edges as an [`Activity`](Activity), by
+    /// [`poll_activity()`](Debouncer#method.poll_activity).  Defaults
+    /// to the opposite of the initial state, since an input generally
+    /// starts out inactive.
+    const ACTIVE: Level = if Self::INIT_HIGH {
+        Level::Low
+    } else {
+        Level::High
+    };
+}
+
+/// The debouncing algorithm of a configuration.
+///
+/// The two counting algorithms use the same state, counts and
+/// thresholds, and differ only in how a sample agreeing with the
+/// debounced state is treated.  The majority vote instead keeps a
+/// history of the last few samples, in place of the count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// The integration algorithm: a sample agreeing with the debounced
+    /// state counts one step back toward it.
+    Integrator,
+
+    /// The classic "N identical samples in a row": a sample agreeing
+    /// with the debounced state resets the count.
+    Consecutive,
+
+    /// A majority vote over a sliding window: the state changes once at
+    /// least `votes` of the last `window` samples disagree with it.
+    ///
+    /// This copes better than the integrator with bursty noise, such as
+    /// RF interference.  The counts and thresholds are ignored.  The
+    /// window must fit in two bits fewer than the storage (so up to six
+    /// samples with `u8`), and `votes` must be a majority of it.
+    Majority {
+        /// The number of recent samples considered.
+        window: u8,
+        /// The number of those that must agree to change state.
+        votes: u8,
+    },
 }
 
 trait DebounceExt: Debounce {
@@ -237,7 +576,17 @@ trait DebounceExt: Debounce {
     fn init_mask() -> Self::Storage;
     fn integrator_mask() -> Self::Storage;
     fn integrator_one() -> Self::Storage;
-    fn integrator_max() -> Self::Storage;
+    fn rise_max() -> Self::Storage;
+    fn fall_max() -> Self::Storage;
+    fn set_level() -> Self::Storage;
+    fn clear_level() -> Self::Storage;
+    fn history_mask(window: u8) -> Self::Storage;
+    fn high_rail() -> Self::Storage;
+    fn init_level() -> Self::Storage;
+    fn less_than(a: Self::Storage, b: Self::Storage) -> bool;
+    fn params() -> transition::Params<Self::Storage>;
+    fn step(state: Self::Storage, high: bool) -> (Self::Storage, Edge);
+    fn config_error() -> Option<&'static str>;
 }
 
 impl<D: Debounce> DebounceExt for D {
@@ -269,8 +618,290 @@ impl<D: Debounce> DebounceExt for D {
     }
 
     #[inline(always)]
-    fn integrator_max() -> Self::Storage {
-        Self::MAX_COUNT << 2
+    fn rise_max() -> Self::Storage {
+        Self::MAX_COUNT_RISE << 2
+    }
+
+    #[inline(always)]
+    fn fall_max() -> Self::Storage {
+        Self::MAX_COUNT_FALL << 2
+    }
+
+    #[inline(always)]
+    fn set_level() -> Self::Storage {
+        match Self::SET_THRESHOLD {
+            Some(threshold) => threshold << 2,
+            None => Self::rise_max(),
+        }
+    }
+
+    #[inline(always)]
+    fn clear_level() -> Self::Storage {
+        match Self::CLEAR_THRESHOLD {
+            Some(threshold) => threshold << 2,
+            None => Self::zero(),
+        }
+    }
+
+    #[inline(always)]
+    fn history_mask(window: u8) -> Self::Storage {
+        transition::history_mask(window)
+    }
+
+    #[inline(always)]
+    fn high_rail() -> Self::Storage {
+        match Self::ALGORITHM {
+            Algorithm::Majority { window, .. } => Self::history_mask(window),
+            _ => Self::fall_max(),
+        }
+    }
+
+    #[inline(always)]
+    fn init_level() -> Self::Storage {
+        match (Self::ALGORITHM, Self::INIT_COUNT) {
+            (Algorithm::Majority { .. }, _) | (_, None) => {
+                if Self::INIT_HIGH {
+                    Self::high_rail()
+                } else {
+                    Self::zero()
+                }
+            }
+            (_, Some(count)) => count << 2,
+        }
+    }
+
+    // The storage type isn't required to be ordered, so compare the
+    // bits from the top down.
+    #[inline(always)]
+    fn less_than(a: Self::Storage, b: Self::Storage) -> bool {
+        let one = Self::Storage::from(1);
+        let bits = core::mem::size_of::<Self::Storage>() * 8;
+        for bit in (0..bits as u8).rev() {
+            let a_bit = (a >> bit) & one;
+            let b_bit = (b >> bit) & one;
+            if a_bit != b_bit {
+                return b_bit == one;
+            }
+        }
+        false
+    }
+
+    #[inline(always)]
+    fn params() -> transition::Params<Self::Storage> {
+        transition::Params {
+            rise_max: Self::rise_max(),
+            fall_max: Self::fall_max(),
+            set_level: Self::set_level(),
+            clear_level: Self::clear_level(),
+            algorithm: Self::ALGORITHM,
+        }
+    }
+
+    #[inline(always)]
+    fn step(state: Self::Storage, high: bool) -> (Self::Storage, Edge) {
+        transition::advance(&Self::params(), state, high)
+    }
+
+    fn config_error() -> Option<&'static str> {
+        // TODO: these would be great as static asserts if we could.
+        if Self::MAX_COUNT == Self::zero() {
+            return Some("Debounce::MAX_COUNT cannot be zero");
+        }
+        if (Self::MAX_COUNT << 2) >> 2 != Self::MAX_COUNT {
+            return Some(
+                "Debounce::MAX_COUNT must be represented in two bits fewer than Debounce::Storage",
+            );
+        }
+        if Self::MAX_COUNT_RISE == Self::zero() || Self::MAX_COUNT_FALL == Self::zero() {
+            return Some("Debounce::MAX_COUNT_RISE and Debounce::MAX_COUNT_FALL cannot be zero");
+        }
+        if (Self::MAX_COUNT_RISE << 2) >> 2 != Self::MAX_COUNT_RISE
+            || (Self::MAX_COUNT_FALL << 2) >> 2 != Self::MAX_COUNT_FALL
+        {
+            return Some("Debounce::MAX_COUNT_RISE and Debounce::MAX_COUNT_FALL must be represented in two bits fewer than Debounce::Storage");
+        }
+        if !Self::less_than(Self::clear_level(), Self::set_level()) {
+            return Some("Debounce::SET_THRESHOLD must be greater than Debounce::CLEAR_THRESHOLD");
+        }
+        // A set threshold inside the rails stays put on rising, so it
+        // must fit under both counts.
+        if Self::less_than(Self::rise_max(), Self::set_level())
+            || (Self::set_level() != Self::rise_max()
+                && Self::less_than(Self::fall_max(), Self::set_level()))
+        {
+            return Some("Debounce::SET_THRESHOLD must be no more than Debounce::MAX_COUNT_RISE or Debounce::MAX_COUNT_FALL");
+        }
+        if !Self::less_than(Self::clear_level(), Self::fall_max()) {
+            return Some("Debounce::CLEAR_THRESHOLD must be less than Debounce::MAX_COUNT_FALL");
+        }
+        if let Some(count) = Self::INIT_COUNT {
+            if (count << 2) >> 2 != count {
+                return Some("Debounce::INIT_COUNT must be represented in two bits fewer than Debounce::Storage");
+            }
+        }
+        let init_in_range = if Self::INIT_HIGH {
+            Self::less_than(Self::clear_level(), Self::init_level())
+                && !Self::less_than(Self::high_rail(), Self::init_level())
+        } else {
+            Self::less_than(Self::init_level(), Self::set_level())
+        };
+        if !init_in_range {
+            return Some(
+                "Debounce::INIT_COUNT must be between the thresholds for the initial state",
+            );
+        }
+        if let Algorithm::Majority { window, votes } = Self::ALGORITHM {
+            if window == 0 || window as usize + 2 > core::mem::size_of::<Self::Storage>() * 8 {
+                return Some("Algorithm::Majority window must be represented in two bits fewer than Debounce::Storage");
+            }
+            if votes > window || votes as u16 * 2 <= window as u16 {
+                return Some("Algorithm::Majority votes must be a majority of the window");
+            }
+        }
+        None
+    }
+}
+
+/// The timing characteristics of a configuration.
+///
+/// These are all `const fn`s, so that firmware can check at compile
+/// time that a configuration meets its requirements:
+///
+/// ```
+/// use unflappable::{default::ActiveLow, Timing};
+///
+/// const TIMING: Timing = Timing::of::<ActiveLow>();
+/// const _: () = assert!(TIMING.min_delay_ms(100) >= 30);
+/// ```
+///
+/// A configuration may take longer to report one edge than the other,
+/// so the delays are those of the quicker edge.  Use
+/// [`rise()`](#method.rise) and [`fall()`](#method.fall) to check each
+/// direction on its own:
+///
+/// ```
+/// use unflappable::{default::FastAttack, Timing};
+///
+/// const TIMING: Timing = Timing::of::<FastAttack<2, 50, false>>();
+/// const _: () = assert!(TIMING.rise().min_delay_ms(100) == 20);
+/// const _: () = assert!(TIMING.fall().min_delay_ms(100) == 500);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    rise: u32,
+    fall: u32,
+}
+
+impl Timing {
+    /// The timing of a configuration with `u8` storage.
+    ///
+    /// This takes into account the separate rise and fall counts, the
+    /// set and clear thresholds, and the algorithm.
+    #[inline]
+    pub const fn of<Cfg: Debounce<Storage = u8>>() -> Self {
+        match Cfg::ALGORITHM {
+            Algorithm::Majority { votes, .. } => Timing::from_polls(votes as u32, votes as u32),
+            // A clean edge takes the integrator from its rail to the
+            // threshold, whether or not it counts consecutive samples.
+            _ => {
+                let set = match Cfg::SET_THRESHOLD {
+                    Some(threshold) => threshold,
+                    None => Cfg::MAX_COUNT_RISE,
+                };
+                let clear = match Cfg::CLEAR_THRESHOLD {
+                    Some(threshold) => threshold,
+                    None => 0,
+                };
+                Timing::from_polls(set as u32, Cfg::MAX_COUNT_FALL.saturating_sub(clear) as u32)
+            }
+        }
+    }
+
+    /// The timing of a configuration with the given `MAX_COUNT`.
+    ///
+    /// For configurations with larger storage, pass in
+    /// `Cfg::MAX_COUNT as u32`.
+    #[inline]
+    pub const fn new(max_count: u32) -> Self {
+        Timing::from_polls(max_count, max_count)
+    }
+
+    /// The timing of a configuration taking `rise` polls to report a
+    /// clean rising edge and `fall` polls to report a clean falling
+    /// edge.
+    #[inline]
+    pub const fn from_polls(rise: u32, fall: u32) -> Self {
+        Timing { rise, fall }
+    }
+
+    /// The timing of rising edges alone.
+    #[inline]
+    pub const fn rise(&self) -> Self {
+        Timing::new(self.rise)
+    }
+
+    /// The timing of falling edges alone.
+    #[inline]
+    pub const fn fall(&self) -> Self {
+        Timing::new(self.fall)
+    }
+
+    /// The number of polls a clean transition takes to be reported, in
+    /// the quicker direction.
+    ///
+    /// This is also the shortest pulse that can get through.
+    #[inline]
+    pub const fn polls(&self) -> u32 {
+        if self.rise < self.fall {
+            self.rise
+        } else {
+            self.fall
+        }
+    }
+
+    /// The minimum debounce delay in milliseconds, when polling `poll_hz`
+    /// times a second, rounded down.
+    ///
+    /// A noisy transition takes longer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `poll_hz` is zero.
+    #[inline]
+    pub const fn min_delay_ms(&self, poll_hz: u32) -> u32 {
+        assert!(poll_hz != 0, "the poll rate cannot be zero");
+        (self.polls() as u64 * 1_000 / poll_hz as u64) as u32
+    }
+
+    /// The minimum debounce delay in microseconds, when polling
+    /// `poll_hz` times a second, rounded down.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `poll_hz` is zero.
+    #[inline]
+    pub const fn min_delay_us(&self, poll_hz: u32) -> u32 {
+        assert!(poll_hz != 0, "the poll rate cannot be zero");
+        (self.polls() as u64 * 1_000_000 / poll_hz as u64) as u32
+    }
+
+    /// The minimum debounce delay, when polling every `poll_period`.
+    ///
+    /// ```
+    /// use fugit::MillisDurationU32;
+    /// use unflappable::{default::ActiveLow, Timing};
+    ///
+    /// const TIMING: Timing = Timing::of::<ActiveLow>();
+    /// const POLL_PERIOD: MillisDurationU32 = MillisDurationU32::millis(10);
+    /// assert_eq!(MillisDurationU32::millis(40), TIMING.min_delay(POLL_PERIOD));
+    /// ```
+    #[cfg(feature = "fugit")]
+    #[inline]
+    pub const fn min_delay<const NOM: u32, const DENOM: u32>(
+        &self,
+        poll_period: fugit::Duration<u32, NOM, DENOM>,
+    ) -> fugit::Duration<u32, NOM, DENOM> {
+        fugit::Duration::<u32, NOM, DENOM>::from_ticks(poll_period.ticks() * self.polls())
     }
 }
 
@@ -333,66 +964,582 @@ pub mod default {
         /// comments.
         const INIT_HIGH: bool = false;
     }
-}
 
-/// An error indicating that once-only initialization has been violated.
-#[derive(Debug)]
-pub struct InitError;
+    /// A configuration given entirely by its parameters.
+    ///
+    /// Saves writing a unit struct and `Debounce` impl for every
+    /// distinct debounce time, for the common case of `u8` storage.
+    ///
+    /// ```
+    /// use unflappable::{debouncer_uninit, Debouncer, default::ConstDebounce};
+    /// # struct PinType;
+    ///
+    /// static DEBOUNCER: Debouncer<PinType, ConstDebounce<5, true>> = debouncer_uninit!();
+    /// ```
+    ///
+    /// Rather than working out `MAX_COUNT` by hand, compute it from the
+    /// debounce time and poll rate with [`max_count()`](max_count):
+    ///
+    /// ```
+    /// use unflappable::{debouncer_uninit, Debouncer};
+    /// use unflappable::default::{max_count, ConstDebounce};
+    /// # struct PinType;
+    ///
+    /// // Debounce for 30ms, polling at 200Hz.
+    /// type Button = ConstDebounce<{ max_count(30, 200) }, true>;
+    /// static DEBOUNCER: Debouncer<PinType, Button> = debouncer_uninit!();
+    /// ```
+    pub struct ConstDebounce<const MAX_COUNT: u8, const INIT_HIGH: bool>;
 
-/// An error that arose during polling.
-#[derive(Debug)]
-pub enum PollError<PinError> {
-    /// The `Debouncer` was polled before the call to
-    /// [`init()`](Debouncer#method.init) completed.
-    Init,
+    impl<const MAX_COUNT: u8, const INIT_HIGH: bool> super::Debounce
+        for ConstDebounce<MAX_COUNT, INIT_HIGH>
+    {
+        /// For most usages, `u8` is plenty.
+        type Storage = u8;
 
-    /// An error polling the underlying pin.
-    Pin(PinError),
-}
+        const MAX_COUNT: Self::Storage = MAX_COUNT;
 
-/// An error that arose during deinit.
-pub enum DeinitError<'a, Cfg: Debounce> {
-    /// The `Debouncer` was not initialized.
-    Init,
+        const INIT_HIGH: bool = INIT_HIGH;
+    }
 
-    /// The provided pin does not match this `Debouncer`.
-    Pin(Debounced<'a, Cfg>),
-}
+    /// A fast-attack, slow-release configuration.
+    ///
+    /// Alarm inputs often want to assert as soon as possible, after
+    /// just `ATTACK` samples, but only deassert after a long quiet
+    /// period of `RELEASE` samples.  This sets
+    /// [`MAX_COUNT_RISE`](super::Debounce#associatedconstant.MAX_COUNT_RISE)
+    /// and
+    /// [`MAX_COUNT_FALL`](super::Debounce#associatedconstant.MAX_COUNT_FALL)
+    /// accordingly, where the alarm is asserted at the level opposite
+    /// to `INIT_HIGH`.
+    ///
+    /// ```
+    /// use unflappable::{debouncer_uninit, Debouncer, default::FastAttack};
+    ///
+    /// // An active-low alarm, asserting after two samples and
+    /// // deasserting after fifty.
+    /// type Alarm = FastAttack<2, 50, true>;
+    /// static ALARM: Debouncer<(), Alarm> = debouncer_uninit!();
+    /// ```
+    pub struct FastAttack<const ATTACK: u8, const RELEASE: u8, const INIT_HIGH: bool>;
 
-impl<'a, Cfg: Debounce> core::fmt::Debug for DeinitError<'a, Cfg> {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        match self {
-            DeinitError::Init => f.write_str("Init"),
-            DeinitError::Pin(_) => f.write_str("Pin(_)"),
-        }
+    impl<const ATTACK: u8, const RELEASE: u8, const INIT_HIGH: bool> super::Debounce
+        for FastAttack<ATTACK, RELEASE, INIT_HIGH>
+    {
+        /// For most usages, `u8` is plenty.
+        type Storage = u8;
+
+        const MAX_COUNT: Self::Storage = RELEASE;
+
+        const MAX_COUNT_RISE: Self::Storage = if INIT_HIGH { RELEASE } else { ATTACK };
+
+        const MAX_COUNT_FALL: Self::Storage = if INIT_HIGH { ATTACK } else { RELEASE };
+
+        const INIT_HIGH: bool = INIT_HIGH;
     }
-}
 
-/// A pin debouncer.
-///
-/// Since this needs to be shared between the main application code and
-/// the interupt service routine, it is generally put into a static.
-///
-/// The preferred way to create one is with the macro
-/// [`debouncer_uninit!`](debouncer_uninit), which can be evaluated in
-/// a `const` context.
-///
-/// ```
-/// # struct PinType;
-/// # impl embedded_hal::digital::v2::InputPin for PinType {
-/// #     type Error = core::convert::Infallible;
-/// #     fn is_high(&self) -> Result<bool, Self::Error> {
-/// #         Ok(true)
-/// #     }
-/// #     fn is_low(&self) -> Result<bool, Self::Error> {
-/// #         Ok(false)
-/// #     }
-/// # }
-/// use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
-/// static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
-/// ```
-///
-/// Later, in your main application code, you can initialize it with
+    /// A configuration for very slow signals.
+    ///
+    /// Door and lid sensors, float switches and the like change state
+    /// rarely, and may be sampled as seldom as once a second, yet want
+    /// a debounce window of a minute or more.  That takes a `MAX_COUNT`
+    /// well beyond what fits in a `u8`, so this uses `u32` storage,
+    /// allowing a `MAX_COUNT` up to `2^30 - 1`.  That's over twelve
+    /// days at 1kHz.
+    ///
+    /// Compute `MAX_COUNT` with [`slow_max_count()`](slow_max_count),
+    /// which checks the range at compile time.
+    ///
+    /// ```
+    /// use unflappable::{debouncer_uninit, Debouncer};
+    /// use unflappable::default::{slow_max_count, SlowSignal};
+    ///
+    /// // A lid switch, sampled at 1Hz, that must hold for a minute.
+    /// type Lid = SlowSignal<{ slow_max_count(60, 1) }, false>;
+    /// static LID: Debouncer<(), Lid> = debouncer_uninit!();
+    /// ```
+    pub struct SlowSignal<const MAX_COUNT: u32, const INIT_HIGH: bool>;
+
+    impl<const MAX_COUNT: u32, const INIT_HIGH: bool> super::Debounce
+        for SlowSignal<MAX_COUNT, INIT_HIGH>
+    {
+        /// Long windows need the room.
+        type Storage = u32;
+
+        const MAX_COUNT: Self::Storage = MAX_COUNT;
+
+        const INIT_HIGH: bool = INIT_HIGH;
+    }
+
+    /// The `MAX_COUNT` for a debounce window of `seconds` when polling
+    /// `hz` times a second.
+    ///
+    /// Panics if the result is zero, or too big for
+    /// [`SlowSignal`](SlowSignal).  Used in a constant, that's a
+    /// compile-time error.
+    pub const fn slow_max_count(seconds: u32, hz: u32) -> u32 {
+        let count = seconds as u64 * hz as u64;
+        assert!(count != 0, "debounce window cannot be zero");
+        assert!(count < 1 << 30, "debounce window too long for u32 storage");
+        count as u32
+    }
+
+    /// The `MAX_COUNT` for a debounce time of `debounce_ms` when polling
+    /// `poll_hz` times a second, rounded up.
+    ///
+    /// Panics if the result is zero, or too big for `u8` storage, that
+    /// is, greater than 63.  Used in a constant, such as for
+    /// [`ConstDebounce`](ConstDebounce), that's a compile-time error.
+    /// For longer windows, see [`slow_max_count()`](slow_max_count).
+    pub const fn max_count(debounce_ms: u32, poll_hz: u32) -> u8 {
+        let product = debounce_ms as u64 * poll_hz as u64;
+        let count = product / 1_000;
+        let count = if count * 1_000 < product {
+            count + 1
+        } else {
+            count
+        };
+        assert!(count != 0, "debounce time cannot be zero");
+        assert!(count < 1 << 6, "debounce time too long for u8 storage");
+        count as u8
+    }
+
+    /// The `MAX_COUNT` for a debounce time of `debounce` when polling
+    /// every `poll_period`, rounded up.
+    ///
+    /// This is [`max_count()`](max_count) for `fugit` durations, which
+    /// must be in the same units.  It panics in the same cases.
+    ///
+    /// ```
+    /// use fugit::MillisDurationU32;
+    /// use unflappable::{debouncer_uninit, Debouncer};
+    /// use unflappable::default::{max_count_for, ConstDebounce};
+    ///
+    /// const DEBOUNCE: MillisDurationU32 = MillisDurationU32::millis(30);
+    /// const POLL_PERIOD: MillisDurationU32 = MillisDurationU32::millis(5);
+    ///
+    /// type Button = ConstDebounce<{ max_count_for(DEBOUNCE, POLL_PERIOD) }, true>;
+    /// static DEBOUNCER: Debouncer<(), Button> = debouncer_uninit!();
+    /// ```
+    #[cfg(feature = "fugit")]
+    pub const fn max_count_for<const NOM: u32, const DENOM: u32>(
+        debounce: fugit::Duration<u32, NOM, DENOM>,
+        poll_period: fugit::Duration<u32, NOM, DENOM>,
+    ) -> u8 {
+        let period = poll_period.ticks();
+        assert!(period != 0, "poll period cannot be zero");
+        let count = debounce.ticks() / period;
+        let count = if count * period < debounce.ticks() {
+            count + 1
+        } else {
+            count
+        };
+        assert!(count != 0, "debounce time cannot be zero");
+        assert!(count < 1 << 6, "debounce time too long for u8 storage");
+        count as u8
+    }
+}
+
+/// A source of noisy boolean samples.
+///
+/// The [`Debouncer`](Debouncer) reads its input through this trait,
+/// which is implemented for every `InputPin`.  Implement it yourself to
+/// debounce a signal that isn't a GPIO, such as a comparator flag in a
+/// status register.
+///
+/// ```
+/// use unflappable::{debouncer_uninit, Debouncer, Sampler, default::ActiveHigh};
+///
+/// struct Comparator;
+///
+/// impl Sampler for Comparator {
+///     type Error = core::convert::Infallible;
+///
+///     fn sample(&mut self) -> Result<bool, Self::Error> {
+///         # let status = 1;
+///         // e.g. read a status register
+///         Ok(status & 1 != 0)
+///     }
+/// }
+///
+/// static DEBOUNCER: Debouncer<Comparator, ActiveHigh> = debouncer_uninit!();
+/// ```
+pub trait Sampler {
+    /// The type of error taking a sample.
+    type Error;
+
+    /// Take a sample, returning `true` if the signal is high.
+    fn sample(&mut self) -> Result<bool, Self::Error>;
+}
+
+impl<Pin: InputPin> Sampler for Pin {
+    type Error = Pin::Error;
+
+    #[inline(always)]
+    fn sample(&mut self) -> Result<bool, Self::Error> {
+        // The debouncer has always asked whether the pin is low, and
+        // some pins may only be able to answer one question cheaply.
+        self.is_low().map(|low| !low)
+    }
+}
+
+/// The level of a digital signal.
+///
+/// Newer APIs in this crate use `Level` rather than a bare `bool`, so
+/// that there's no question which way round it goes.  It converts to
+/// and from `bool` (where `true` is high) and the `embedded-hal`
+/// [`PinState`](embedded_hal::digital::v2::PinState).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Level {
+    /// A logic low level.
+    Low,
+
+    /// A logic high level.
+    High,
+}
+
+impl Level {
+    /// Is this the high level?
+    #[inline(always)]
+    pub const fn is_high(self) -> bool {
+        matches!(self, Level::High)
+    }
+
+    /// Is this the low level?
+    #[inline(always)]
+    pub const fn is_low(self) -> bool {
+        matches!(self, Level::Low)
+    }
+}
+
+impl From<bool> for Level {
+    #[inline(always)]
+    fn from(high: bool) -> Self {
+        if high {
+            Level::High
+        } else {
+            Level::Low
+        }
+    }
+}
+
+impl From<Level> for bool {
+    #[inline(always)]
+    fn from(level: Level) -> Self {
+        level.is_high()
+    }
+}
+
+impl From<PinState> for Level {
+    #[inline(always)]
+    fn from(state: PinState) -> Self {
+        match state {
+            PinState::Low => Level::Low,
+            PinState::High => Level::High,
+        }
+    }
+}
+
+impl From<Level> for PinState {
+    #[inline(always)]
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Low => PinState::Low,
+            Level::High => PinState::High,
+        }
+    }
+}
+
+impl Not for Level {
+    type Output = Level;
+
+    #[inline(always)]
+    fn not(self) -> Self::Output {
+        match self {
+            Level::Low => Level::High,
+            Level::High => Level::Low,
+        }
+    }
+}
+
+/// A transition of the debounced signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Edge {
+    /// The debounced signal did not change.
+    NoChange,
+
+    /// The debounced signal went from low to high.
+    Rising,
+
+    /// The debounced signal went from high to low.
+    Falling,
+}
+
+impl Edge {
+    /// The edge in terms of the input's activity, given the level at
+    /// which it is `active`.
+    ///
+    /// ```
+    /// use unflappable::{Activity, Edge, Level};
+    ///
+    /// // An active-low button.
+    /// assert_eq!(Activity::Activated, Edge::Falling.activity(Level::Low));
+    /// assert_eq!(Activity::Deactivated, Edge::Rising.activity(Level::Low));
+    /// ```
+    #[inline(always)]
+    pub const fn activity(self, active: Level) -> Activity {
+        match (self, active) {
+            (Edge::NoChange, _) => Activity::NoChange,
+            (Edge::Rising, Level::High) | (Edge::Falling, Level::Low) => Activity::Activated,
+            (Edge::Rising, Level::Low) | (Edge::Falling, Level::High) => Activity::Deactivated,
+        }
+    }
+}
+
+/// A transition of the debounced signal, in terms of the input's
+/// activity rather than the level of the pin.
+///
+/// Handlers written with these read the same however the input is
+/// wired.  See [`Edge::activity()`](Edge::activity).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Activity {
+    /// The input did not change.
+    NoChange,
+
+    /// The input became active, such as a button being pressed.
+    Activated,
+
+    /// The input became inactive, such as a button being released.
+    Deactivated,
+}
+
+/// Callbacks for debounced transitions.
+///
+/// These are called from within `poll()`, so they should be quick.
+#[cfg(feature = "observer")]
+#[derive(Debug, Clone, Copy)]
+pub struct Observer {
+    /// Called on each debounced rising edge.
+    pub on_rise: fn(),
+
+    /// Called on each debounced falling edge.
+    pub on_fall: fn(),
+}
+
+#[cfg(feature = "observer")]
+impl Observer {
+    #[inline(always)]
+    fn notify(&self, edge: Edge) {
+        match edge {
+            Edge::NoChange => {}
+            Edge::Rising => (self.on_rise)(),
+            Edge::Falling => (self.on_fall)(),
+        }
+    }
+}
+
+/// A snapshot of the state of a [`Debouncer`](Debouncer).
+///
+/// Returned by [`Debouncer::status()`](Debouncer#method.status), to
+/// help tune a configuration or test it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status<Storage> {
+    /// The current debounced level.
+    pub level: Level,
+
+    /// The current integrator value.
+    ///
+    /// For the counting algorithms, this is between zero and the rise
+    /// or fall count.  For
+    /// [`Algorithm::Majority`](Algorithm::Majority), it is the window
+    /// of recent samples, one bit each, most recent lowest.
+    pub integrator: Storage,
+
+    /// Has the `Debouncer` been initialized?
+    pub initialized: bool,
+}
+
+/// Lifetime counters of a [`Debouncer`](Debouncer).
+///
+/// Returned by [`Debouncer::stats()`](Debouncer#method.stats).  The
+/// counters saturate rather than wrap around.
+#[cfg(feature = "stats")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Stats {
+    /// The number of debounced transitions, rising or falling.
+    pub transitions: u32,
+
+    /// The number of times a raw sample differed from the one before.
+    pub flips: u32,
+}
+
+/// An error that arose during init.
+///
+/// Each variant carries the pin that was passed in, so that the
+/// caller doesn't lose it.
+pub enum InitError<Pin> {
+    /// The `Debouncer` was already initialized.  Initialization is
+    /// once-only, until a call to [`deinit()`](Debouncer#method.deinit).
+    AlreadyInit(Pin),
+
+    /// The [`Debounce`](Debounce) configuration is invalid.
+    ///
+    /// With the `debug-config` feature enabled, an invalid configuration
+    /// instead panics, with a message naming the problem.
+    BadConfig(Pin),
+}
+
+impl<Pin> InitError<Pin> {
+    /// Recover the pin that was passed in.
+    #[inline]
+    pub fn into_pin(self) -> Pin {
+        match self {
+            InitError::AlreadyInit(pin) | InitError::BadConfig(pin) => pin,
+        }
+    }
+}
+
+impl<Pin> core::fmt::Debug for InitError<Pin> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InitError::AlreadyInit(_) => f.write_str("AlreadyInit(_)"),
+            InitError::BadConfig(_) => f.write_str("BadConfig(_)"),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<Pin> defmt::Format for InitError<Pin> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            InitError::AlreadyInit(_) => defmt::write!(f, "AlreadyInit(_)"),
+            InitError::BadConfig(_) => defmt::write!(f, "BadConfig(_)"),
+        }
+    }
+}
+
+#[inline(always)]
+fn bad_config<Pin>(pin: Pin, message: &'static str) -> InitError<Pin> {
+    if cfg!(feature = "debug-config") {
+        panic!("{}", message);
+    }
+    InitError::BadConfig(pin)
+}
+
+/// An error that arose during polling.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PollError<PinError> {
+    /// The `Debouncer` was polled before the call to
+    /// [`init()`](Debouncer#method.init) completed.
+    Init,
+
+    /// An error polling the underlying pin.
+    Pin(PinError),
+}
+
+/// An error that arose while initializing from the pin.
+///
+/// Like an [`InitError`](InitError), each variant carries the pin that
+/// was passed in.
+pub enum SeedError<Pin, PinError> {
+    /// The `Debouncer` couldn't be initialized.
+    Init(InitError<Pin>),
+
+    /// An error sampling the underlying pin.
+    Sample(Pin, PinError),
+}
+
+impl<Pin, PinError> SeedError<Pin, PinError> {
+    /// Recover the pin that was passed in.
+    #[inline]
+    pub fn into_pin(self) -> Pin {
+        match self {
+            SeedError::Init(error) => error.into_pin(),
+            SeedError::Sample(pin, _) => pin,
+        }
+    }
+}
+
+impl<Pin, PinError: core::fmt::Debug> core::fmt::Debug for SeedError<Pin, PinError> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SeedError::Init(error) => f.debug_tuple("Init").field(error).finish(),
+            SeedError::Sample(_, error) => {
+                f.debug_tuple("Sample").field(&"_").field(error).finish()
+            }
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<Pin, PinError: defmt::Format> defmt::Format for SeedError<Pin, PinError> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            SeedError::Init(error) => defmt::write!(f, "Init({})", error),
+            SeedError::Sample(_, error) => defmt::write!(f, "Sample(_, {})", error),
+        }
+    }
+}
+
+/// An error that arose during deinit.
+pub enum DeinitError<'a, Cfg: Debounce> {
+    /// The `Debouncer` was not initialized.
+    Init,
+
+    /// The provided pin does not match this `Debouncer`.
+    Pin(Debounced<'a, Cfg>),
+}
+
+impl<'a, Cfg: Debounce> core::fmt::Debug for DeinitError<'a, Cfg> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DeinitError::Init => f.write_str("Init"),
+            DeinitError::Pin(_) => f.write_str("Pin(_)"),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<'a, Cfg: Debounce> defmt::Format for DeinitError<'a, Cfg> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            DeinitError::Init => defmt::write!(f, "Init"),
+            DeinitError::Pin(_) => defmt::write!(f, "Pin(_)"),
+        }
+    }
+}
+
+/// A pin debouncer.
+///
+/// Since this needs to be shared between the main application code and
+/// the interupt service routine, it is generally put into a static.
+///
+/// The preferred way to create one is with the macro
+/// [`debouncer_uninit!`](debouncer_uninit), which can be evaluated in
+/// a `const` context.
+///
+/// ```
+/// # struct PinType;
+/// # impl embedded_hal::digital::v2::InputPin for PinType {
+/// #     type Error = core::convert::Infallible;
+/// #     fn is_high(&self) -> Result<bool, Self::Error> {
+/// #         Ok(true)
+/// #     }
+/// #     fn is_low(&self) -> Result<bool, Self::Error> {
+/// #         Ok(false)
+/// #     }
+/// # }
+/// use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
+/// static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+/// ```
+///
+/// Later, in your main application code, you can initialize it with
 /// the relevant input pin.  This returns the [`Debounced`](Debounced)
 /// pin for your use.
 ///
@@ -409,7 +1556,7 @@ impl<'a, Cfg: Debounce> core::fmt::Debug for DeinitError<'a, Cfg> {
 /// # }
 /// # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
 /// # static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
-/// # fn main() -> Result<(), unflappable::InitError> {
+/// # fn main() -> Result<(), unflappable::InitError<PinType>> {
 /// #     let input_pin = PinType;
 /// let debounced_pin = unsafe { DEBOUNCER.init(input_pin) }?;
 /// #     Ok(())
@@ -444,6 +1591,16 @@ pub struct Debouncer<Pin, Cfg: Debounce> {
     cfg: PhantomData<Cfg>,
     pin: UnsafeCell<MaybeUninit<Pin>>,
     storage: UnsafeCell<Cfg::Storage>,
+    #[cfg(feature = "async")]
+    waker: atomic_waker::AtomicWaker,
+    #[cfg(all(feature = "cortex-m", target_arch = "arm", debug_assertions))]
+    context: context::ContextCheck,
+    #[cfg(feature = "observer")]
+    observer: UnsafeCell<Option<Observer>>,
+    #[cfg(feature = "stats")]
+    stats: UnsafeCell<Stats>,
+    #[cfg(feature = "stats")]
+    last_sample: UnsafeCell<bool>,
 }
 
 // We demand particular mutex requirements as documented on the methods
@@ -451,10 +1608,11 @@ pub struct Debouncer<Pin, Cfg: Debounce> {
 // the user, outside of the type system.
 unsafe impl<Pin, Cfg: Debounce> Sync for Debouncer<Pin, Cfg> {}
 
-impl<Pin: InputPin, Cfg: Debounce> Debouncer<Pin, Cfg> {
+impl<Pin, Cfg: Debounce> Debouncer<Pin, Cfg> {
     /// Initialize the pin debouncer for a given input pin.
     ///
-    /// Returns an error if the `Debouncer` has already be initialized.
+    /// Returns an error if the `Debouncer` has already be initialized,
+    /// or if the [`Debounce`](Debounce) configuration is invalid.
     ///
     /// # Safety
     ///
@@ -478,38 +1636,108 @@ impl<Pin: InputPin, Cfg: Debounce> Debouncer<Pin, Cfg> {
     /// # }
     /// # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
     /// # static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
-    /// # fn main() -> Result<(), unflappable::InitError> {
+    /// # fn main() -> Result<(), unflappable::InitError<PinType>> {
     /// #     let input_pin = PinType;
     /// let debounced_pin = unsafe { DEBOUNCER.init(input_pin) }?;
     /// #     Ok(())
     /// # }
     /// ```
     #[inline]
-    pub unsafe fn init(&self, pin: Pin) -> Result<Debounced<Cfg>, InitError> {
-        // TODO: these would be great as static asserts if we could.
-        assert!(
-            Cfg::MAX_COUNT != Cfg::zero(),
-            "Debounce::MAX_COUNT cannot be zero"
-        );
-        assert!(
-            (Cfg::MAX_COUNT << 2) >> 2 == Cfg::MAX_COUNT,
-            "Debounce::MAX_COUNT must be represented in two bits fewer than Debounce::Storage"
-        );
-
+    pub unsafe fn init(&self, pin: Pin) -> Result<Debounced<'_, Cfg>, InitError<Pin>> {
+        let pin = self.validate_config(pin)?;
         self.init_linted(pin)
     }
 
-    // n.b. defined seperately to ensure that we think about unsafety.
+    /// Initialize the pin debouncer, splitting it into a
+    /// [`Poller`](Poller) for the interrupt service routine and the
+    /// [`Debounced`](Debounced) pin for the application.
+    ///
+    /// The `Poller` is the only handle that can poll, and it can't be
+    /// copied, so its `poll()` is safe.  Move it into the interrupt
+    /// service routine, such as an RTIC local resource, and hand the
+    /// debounced pin to the application code.
+    ///
+    /// ```
+    /// # struct PinType;
+    /// # impl embedded_hal::digital::v2::InputPin for PinType {
+    /// #     type Error = core::convert::Infallible;
+    /// #     fn is_high(&self) -> Result<bool, Self::Error> {
+    /// #         Ok(true)
+    /// #     }
+    /// #     fn is_low(&self) -> Result<bool, Self::Error> {
+    /// #         Ok(false)
+    /// #     }
+    /// # }
+    /// # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
+    /// static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+    /// # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+    /// # let input_pin = PinType;
+    /// let (mut poller, debounced_pin) = unsafe { DEBOUNCER.init_split(input_pin) }.unwrap();
+    ///
+    /// // In the interrupt service routine, no unsafe needed:
+    /// poller.poll()?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`init()`](#method.init) apply.  In
+    /// addition, while the `Poller` exists, you must not call any other
+    /// unsafe method of this `Debouncer`.  Deinitialize it with
+    /// [`Poller::deinit()`](Poller::deinit) instead.
+    #[inline]
+    pub unsafe fn init_split(&self, pin: Pin) -> Result<Split<'_, Pin, Cfg>, InitError<Pin>> {
+        let pin = self.validate_config(pin)?;
+        let debounced = self.init_linted(pin)?;
+
+        Ok((
+            Poller {
+                debouncer: self,
+                pin: PhantomData,
+            },
+            debounced,
+        ))
+    }
+
     #[inline(always)]
-    fn init_linted(&self, pin: Pin) -> Result<Debounced<Cfg>, InitError> {
-        if self.init_flag() {
-            return Err(InitError);
+    fn check_config(&self) {
+        if let Some(message) = Cfg::config_error() {
+            panic!("{}", message);
         }
+    }
 
-        let pin_cell_ptr = self.pin.get();
-        // This is safe because we demand from the caller that this
-        // method completes before any call to `poll()`.
-        let pin_cell = unsafe { &mut *pin_cell_ptr };
+    #[inline(always)]
+    fn validate_config(&self, pin: Pin) -> Result<Pin, InitError<Pin>> {
+        match Cfg::config_error() {
+            Some(message) => Err(bad_config(pin, message)),
+            None => Ok(pin),
+        }
+    }
+
+    // n.b. defined seperately to ensure that we think about unsafety.
+    #[inline(always)]
+    fn init_linted(&self, pin: Pin) -> Result<Debounced<'_, Cfg>, InitError<Pin>> {
+        self.init_seeded(pin, Cfg::INIT_HIGH, Cfg::init_level())
+    }
+
+    // Initialize with the debounced level `high`, and the integrator
+    // (already shifted into place) at `level`.
+    #[inline(always)]
+    fn init_seeded(
+        &self,
+        pin: Pin,
+        high: bool,
+        level: Cfg::Storage,
+    ) -> Result<Debounced<'_, Cfg>, InitError<Pin>> {
+        if self.init_flag() {
+            return Err(InitError::AlreadyInit(pin));
+        }
+
+        let pin_cell_ptr = self.pin.get();
+        // This is safe because we demand from the caller that this
+        // method completes before any call to `poll()`.
+        let pin_cell = unsafe { &mut *pin_cell_ptr };
 
         let pin_ptr = pin_cell.as_mut_ptr();
         // It is always safe to write to a MaybeUninit pointer.
@@ -518,11 +1746,10 @@ impl<Pin: InputPin, Cfg: Debounce> Debouncer<Pin, Cfg> {
         }
 
         // TODO: should this be moved to intepretation side?
-        let mut new_state = if Cfg::INIT_HIGH {
-            Cfg::state_mask() | Cfg::integrator_max()
-        } else {
-            Cfg::zero()
-        };
+        let mut new_state = level;
+        if high {
+            new_state |= Cfg::state_mask();
+        }
         new_state |= Cfg::init_mask();
 
         let state_ptr = self.storage.get();
@@ -532,24 +1759,49 @@ impl<Pin: InputPin, Cfg: Debounce> Debouncer<Pin, Cfg> {
             *state_ptr = new_state;
         }
 
-        Ok(Debounced {
+        #[cfg(feature = "observer")]
+        {
+            let observer_ptr = self.observer.get();
+            // This is safe because we demand from the caller that this
+            // method completes before any call to `poll()`.
+            unsafe {
+                *observer_ptr = None;
+            }
+        }
+
+        #[cfg(feature = "stats")]
+        {
+            let last_sample_ptr = self.last_sample.get();
+            // This is safe because we demand from the caller that this
+            // method completes before any call to `poll()`.
+            unsafe {
+                *last_sample_ptr = high;
+            }
+        }
+
+        Ok(self.debounced())
+    }
+
+    #[inline(always)]
+    fn debounced(&self) -> Debounced<'_, Cfg> {
+        Debounced {
             cfg: PhantomData,
             storage: &self.storage,
-        })
+            #[cfg(feature = "async")]
+            waker: &self.waker,
+        }
     }
 
-    /// Poll the pin debouncer.
+    /// Initialize the pin debouncer, with callbacks for each debounced
+    /// transition.
     ///
-    /// This should be done on a regular basis at roughly the frequency
-    /// used in the calculation of [`MAX_COUNT`](Debounce#associatedconstant.MAX_COUNT).
+    /// Other than registering the [`Observer`](Observer), this is the
+    /// same as [`init()`](#method.init).  A later call to `init()` after
+    /// [`deinit()`](#method.deinit) clears the observer.
     ///
     /// # Safety
     ///
-    /// For this method to be safe, you must ensure that it is not run
-    /// concurrently with a call to any unsafe method of this type,
-    /// including `poll()` itself.  The usual way to do this is to call
-    /// `poll()` from a single interrupt service routine, and not
-    /// enable interrupts until after the call to `init()` returns.
+    /// The same requirements as [`init()`](#method.init) apply.
     ///
     /// # Examples
     ///
@@ -564,56 +1816,37 @@ impl<Pin: InputPin, Cfg: Debounce> Debouncer<Pin, Cfg> {
     /// #         Ok(false)
     /// #     }
     /// # }
-    /// # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
-    /// # static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
-    /// # let input_pin = PinType;
-    /// # let _ = unsafe { DEBOUNCER.init(input_pin) }.unwrap();
-    /// # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
-    /// unsafe {
-    ///     DEBOUNCER.poll()?;
-    /// }
+    /// # fn led_on() {}
+    /// # fn led_off() {}
+    /// use unflappable::{debouncer_uninit, Debouncer, Observer, default::ActiveHigh};
+    /// static DEBOUNCER: Debouncer<PinType, ActiveHigh> = debouncer_uninit!();
+    /// # fn main() -> Result<(), unflappable::InitError<PinType>> {
+    /// #     let input_pin = PinType;
+    ///
+    /// let observer = Observer {
+    ///     on_rise: led_on,
+    ///     on_fall: led_off,
+    /// };
+    /// let debounced_pin = unsafe { DEBOUNCER.init_observed(input_pin, observer) }?;
     /// #     Ok(())
     /// # }
     /// ```
+    #[cfg(feature = "observer")]
     #[inline]
-    pub unsafe fn poll(&self) -> Result<(), PollError<Pin::Error>> {
-        // TODO: can we make this safe with a mutex bit?
-        // is that hair-brained? hare-brained? whatever
-
-        self.poll_linted()
-    }
-
-    // n.b. defined seperately to ensure that we think about unsafety.
-    #[inline(always)]
-    fn poll_linted(&self) -> Result<(), PollError<Pin::Error>> {
-        if !self.init_flag() {
-            return Err(PollError::Init);
-        }
-
-        let pin_cell_ptr = self.pin.get();
-        // This is safe because we only ever mutate in `init()`.
-        let pin_cell = unsafe { &*pin_cell_ptr };
-
-        let pin_ptr = pin_cell.as_ptr();
-        // This is safe because we've checked that init has completed.
-        let pin = unsafe { &*pin_ptr };
-
-        if pin.is_low().map_err(PollError::Pin)? {
-            self.decrement_integrator();
-
-            if self.integrator_is_zero() {
-                self.clear_state_flag();
-            }
-        } else {
-            // TODO: should this check if pin is high?
-            self.increment_integrator();
-
-            if self.integrator_is_max() {
-                self.set_state_flag();
-            }
-        }
+    pub unsafe fn init_observed(
+        &self,
+        pin: Pin,
+        observer: Observer,
+    ) -> Result<Debounced<'_, Cfg>, InitError<Pin>> {
+        let pin = self.validate_config(pin)?;
+        let debounced = self.init_linted(pin)?;
+
+        let observer_ptr = self.observer.get();
+        // This is safe because we demand from the caller that this
+        // method completes before any call to `poll()`.
+        *observer_ptr = Some(observer);
 
-        Ok(())
+        Ok(debounced)
     }
 
     /// Create a new, uninitialized pin debouncer.
@@ -627,6 +1860,19 @@ impl<Pin: InputPin, Cfg: Debounce> Debouncer<Pin, Cfg> {
             cfg: PhantomData,
             pin: UnsafeCell::new(MaybeUninit::uninit()),
             storage: UnsafeCell::new(zero),
+            #[cfg(feature = "async")]
+            waker: atomic_waker::AtomicWaker::new(),
+            #[cfg(all(feature = "cortex-m", target_arch = "arm", debug_assertions))]
+            context: context::ContextCheck::new(),
+            #[cfg(feature = "observer")]
+            observer: UnsafeCell::new(None),
+            #[cfg(feature = "stats")]
+            stats: UnsafeCell::new(Stats {
+                transitions: 0,
+                flips: 0,
+            }),
+            #[cfg(feature = "stats")]
+            last_sample: UnsafeCell::new(false),
         }
     }
 
@@ -672,6 +1918,12 @@ impl<Pin: InputPin, Cfg: Debounce> Debouncer<Pin, Cfg> {
             *state_ptr = Cfg::zero();
         }
 
+        // This is safe for the same reason.
+        #[cfg(all(feature = "cortex-m", target_arch = "arm", debug_assertions))]
+        unsafe {
+            self.context.reset();
+        }
+
         // Ensure no aliasing.
         let pin = {
             let pin_cell_ptr = self.pin.get();
@@ -693,285 +1945,1925 @@ impl<Pin: InputPin, Cfg: Debounce> Debouncer<Pin, Cfg> {
         Ok(pin)
     }
 
+    /// Has this `Debouncer` been initialized?
+    ///
+    /// This is true between a successful call to
+    /// [`init()`](#method.init) and the matching call to
+    /// [`deinit()`](#method.deinit).
     #[inline]
-    fn init_flag(&self) -> bool {
-        let state_ptr = self.storage.get();
-        // This is safe because the read is atomic.
-        let state = unsafe { *state_ptr };
+    pub fn is_initialized(&self) -> bool {
+        self.init_flag()
+    }
 
-        state & Cfg::init_mask() != Cfg::zero()
+    /// The lifetime counters of this `Debouncer`.
+    ///
+    /// The counters keep running across [`deinit()`](#method.deinit)
+    /// and [`init()`](#method.init).  A read racing a `poll()` may see
+    /// a stale count.
+    ///
+    /// ```
+    /// # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
+    /// static DEBOUNCER: Debouncer<(), ActiveLow> = debouncer_uninit!();
+    /// # let debounced = unsafe { DEBOUNCER.init(()) }.unwrap();
+    ///
+    /// // Later, in the application:
+    /// let presses = DEBOUNCER.stats().transitions / 2;
+    /// ```
+    #[cfg(feature = "stats")]
+    #[inline]
+    pub fn stats(&self) -> Stats {
+        let stats_ptr = self.stats.get();
+        // This is safe since the counters are only written by `poll()`,
+        // and we only read them here.
+        unsafe { *stats_ptr }
     }
 
-    #[inline(always)]
-    fn set_state_flag(&self) {
+    /// A snapshot of the current state of this `Debouncer`.
+    ///
+    /// This may be called at any time, even before
+    /// [`init()`](#method.init), though the snapshot may be stale as
+    /// soon as the next `poll()`.
+    #[inline]
+    pub fn status(&self) -> Status<Cfg::Storage> {
         let state_ptr = self.storage.get();
+        // This is safe since the read is atomic.
+        let state = unsafe { *state_ptr };
 
-        // This is safe since we're the only ones allowed to mutate.
-        unsafe {
-            *state_ptr |= Cfg::state_mask();
+        Status {
+            level: Level::from(state & Cfg::state_mask() != Cfg::zero()),
+            integrator: (state & Cfg::integrator_mask()) >> 2,
+            initialized: state & Cfg::init_mask() != Cfg::zero(),
         }
     }
 
-    #[inline(always)]
-    fn clear_state_flag(&self) {
-        let state_ptr = self.storage.get();
+    /// A snapshot of the debounced level and integrator, to
+    /// [`restore()`](#method.restore) later, perhaps after a reset.
+    ///
+    /// See the [`snapshot`](snapshot) module for the wire format.
+    #[inline]
+    pub fn snapshot(&self) -> snapshot::StateSnapshot {
+        let status = self.status();
 
-        // This is safe since we're the only ones allowed to mutate.
-        unsafe {
-            *state_ptr &= !Cfg::state_mask();
+        snapshot::StateSnapshot {
+            level: status.level,
+            integrator: snapshot::widen(status.integrator),
         }
     }
 
-    #[inline(always)]
-    fn integrator_is_zero(&self) -> bool {
+    /// Restore the debounced level and integrator from a snapshot.
+    ///
+    /// The `Debouncer` must already be initialized.  The snapshot is
+    /// checked against the configuration, so a snapshot from a build
+    /// with a longer debounce time is refused rather than leaving the
+    /// integrator beyond its rail.
+    ///
+    /// ```
+    /// # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow, Level};
+    /// # use unflappable::snapshot::StateSnapshot;
+    /// static DEBOUNCER: Debouncer<(), ActiveLow> = debouncer_uninit!();
+    /// # fn load_from_flash() -> [u8; StateSnapshot::LEN] {
+    /// #     StateSnapshot { level: Level::Low, integrator: 0 }.to_bytes()
+    /// # }
+    /// let debounced = unsafe { DEBOUNCER.init(()) }.unwrap();
+    ///
+    /// if let Ok(snapshot) = StateSnapshot::from_bytes(&load_from_flash()) {
+    ///     unsafe { DEBOUNCER.restore(snapshot) }.unwrap();
+    /// }
+    /// assert_eq!(Level::Low, debounced.state());
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`poll()`](#method.poll) apply.
+    #[inline]
+    pub unsafe fn restore(
+        &self,
+        snapshot: snapshot::StateSnapshot,
+    ) -> Result<(), snapshot::RestoreError> {
+        if !self.init_flag() {
+            return Err(snapshot::RestoreError::Init);
+        }
+
+        let integrator: Cfg::Storage =
+            snapshot::narrow(snapshot.integrator).ok_or(snapshot::RestoreError::Range)?;
+        let shifted = integrator << 2;
+        if shifted >> 2 != integrator {
+            return Err(snapshot::RestoreError::Range);
+        }
+
+        let in_range = match Cfg::ALGORITHM {
+            Algorithm::Majority { window, .. } => {
+                shifted & !Cfg::history_mask(window) == Cfg::zero()
+            }
+            _ if snapshot.level.is_high() => !Cfg::less_than(Cfg::fall_max(), shifted),
+            _ => !Cfg::less_than(Cfg::rise_max(), shifted),
+        };
+        if !in_range {
+            return Err(snapshot::RestoreError::Range);
+        }
+
+        let level = if snapshot.level.is_high() {
+            Cfg::state_mask()
+        } else {
+            Cfg::zero()
+        };
+
         let state_ptr = self.storage.get();
+        // This is safe because we demand from the caller that this is
+        // an exclusive call.
+        *state_ptr = Cfg::init_mask() | level | shifted;
+        Ok(())
+    }
 
-        // This is safe since the read is atomic.
-        let state = unsafe { *state_ptr };
-        let integrator = state & Cfg::integrator_mask();
-        integrator == Cfg::zero()
+    /// Poll the debouncer with a sample taken elsewhere.
+    ///
+    /// Use this when the raw level is already in hand, say from a
+    /// timer-capture interrupt, so the debouncer needn't read the pin
+    /// itself.  In that case it doesn't need a pin at all: use `()` as
+    /// the pin type, and initialize it with `init(())`.
+    ///
+    /// Returns the debounced [`Edge`](Edge), if any, just like
+    /// [`poll()`](#method.poll).
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`poll()`](#method.poll) apply.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
+    /// static DEBOUNCER: Debouncer<(), ActiveLow> = debouncer_uninit!();
+    /// # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+    /// let debounced_pin = unsafe { DEBOUNCER.init(()) }.unwrap();
+    ///
+    /// // Later, in the capture interrupt service routine:
+    /// # let captured_level = true;
+    /// unsafe { DEBOUNCER.poll_with(captured_level) }?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub unsafe fn poll_with(&self, sample: bool) -> Result<Edge, PollError<Infallible>> {
+        #[cfg(all(feature = "cortex-m", target_arch = "arm", debug_assertions))]
+        self.context.check(context::current());
+
+        self.poll_with_linted(sample)
     }
 
+    // n.b. defined seperately to ensure that we think about unsafety.
     #[inline(always)]
-    fn integrator_is_max(&self) -> bool {
-        let state_ptr = self.storage.get();
+    fn poll_with_linted(&self, sample: bool) -> Result<Edge, PollError<Infallible>> {
+        if !self.init_flag() {
+            return Err(PollError::Init);
+        }
 
-        // This is safe since the read is atomic.
-        let state = unsafe { *state_ptr };
-        let integrator = state & Cfg::integrator_mask();
-        integrator == Cfg::integrator_max()
+        Ok(self.integrate(sample))
     }
 
     #[inline(always)]
-    fn decrement_integrator(&self) {
+    fn integrate(&self, high: bool) -> Edge {
         let state_ptr = self.storage.get();
 
         // This is safe since we're the only ones allowed to mutate.
-        if !self.integrator_is_zero() {
+        let edge = unsafe {
+            let (state, edge) = Cfg::step(*state_ptr, high);
+            *state_ptr = state;
+            edge
+        };
+
+        #[cfg(feature = "async")]
+        if edge != Edge::NoChange {
+            self.waker.wake();
+        }
+
+        #[cfg(feature = "observer")]
+        {
+            let observer_ptr = self.observer.get();
+            // This is safe because the observer is only written by
+            // `init()`, which may not run concurrently with polling.
+            if let Some(observer) = unsafe { &*observer_ptr } {
+                observer.notify(edge);
+            }
+        }
+
+        #[cfg(feature = "stats")]
+        {
+            let stats_ptr = self.stats.get();
+            let last_sample_ptr = self.last_sample.get();
+            // This is safe since we're the only ones allowed to mutate.
             unsafe {
-                *state_ptr -= Cfg::integrator_one();
+                if high != *last_sample_ptr {
+                    (*stats_ptr).flips = (*stats_ptr).flips.saturating_add(1);
+                    *last_sample_ptr = high;
+                }
+                if edge != Edge::NoChange {
+                    (*stats_ptr).transitions = (*stats_ptr).transitions.saturating_add(1);
+                }
             }
         }
+
+        edge
     }
 
-    #[inline(always)]
-    fn increment_integrator(&self) {
+    #[inline]
+    fn init_flag(&self) -> bool {
         let state_ptr = self.storage.get();
+        // This is safe because the read is atomic.
+        let state = unsafe { *state_ptr };
 
-        // This is safe since we're the only ones allowed to mutate.
-        if !self.integrator_is_max() {
-            unsafe {
-                *state_ptr += Cfg::integrator_one();
-            }
-        }
+        state & Cfg::init_mask() != Cfg::zero()
     }
-}
 
-/// Create a new uninitialized [`Debouncer`](Debouncer).
-///
-/// This is the preferred way to initialize a static `Debouncer`.  Be
-/// sure to initialize it before doing anything else with it, or you'll
-/// get an error `Result`.
-///
-/// # Examples
-///
-/// ```
-/// # struct PinType;
-/// # impl embedded_hal::digital::v2::InputPin for PinType {
-/// #     type Error = core::convert::Infallible;
-/// #     fn is_high(&self) -> Result<bool, Self::Error> {
-/// #         Ok(true)
-/// #     }
-/// #     fn is_low(&self) -> Result<bool, Self::Error> {
-/// #         Ok(false)
-/// #     }
-/// # }
-/// use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
-/// static PIN_DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
-/// ```
-#[macro_export]
-macro_rules! debouncer_uninit {
-    () => {
-        $crate::Debouncer::uninit(0)
-    };
-}
+    #[inline(always)]
+    fn state_flag(&self) -> bool {
+        let state_ptr = self.storage.get();
 
-/// A debounced pin.
-///
-/// This is what you'll use for downstream input processing, leveraging
-/// the methods provided by the trait [`InputPin`](#impl-InputPin).
-pub struct Debounced<'state, Cfg: Debounce> {
-    cfg: PhantomData<Cfg>,
-    storage: &'state UnsafeCell<Cfg::Storage>,
-}
+        // This is safe since the read is atomic.
+        let state = unsafe { *state_ptr };
+        state & Cfg::state_mask() != Cfg::zero()
+    }
 
-impl<'state, Cfg: Debounce> InputPin for Debounced<'state, Cfg> {
-    type Error = Infallible;
+    // Is the integrator (or history) at the rail of the current state?
+    #[inline(always)]
+    fn is_settled(&self) -> bool {
+        if self.state_flag() {
+            self.integrator_is(Cfg::high_rail())
+        } else {
+            self.integrator_is_zero()
+        }
+    }
 
     #[inline(always)]
-    fn is_high(&self) -> Result<bool, Self::Error> {
+    fn integrator_is(&self, level: Cfg::Storage) -> bool {
         let state_ptr = self.storage.get();
+
         // This is safe since the read is atomic.
         let state = unsafe { *state_ptr };
-        let flag = state & Cfg::state_mask();
-        Ok(flag != Cfg::zero())
+        state & Cfg::integrator_mask() == level
     }
 
     #[inline(always)]
-    fn is_low(&self) -> Result<bool, Self::Error> {
+    fn integrator_is_zero(&self) -> bool {
         let state_ptr = self.storage.get();
+
         // This is safe since the read is atomic.
         let state = unsafe { *state_ptr };
-        let flag = state & Cfg::state_mask();
-        Ok(flag == Cfg::zero())
+        let integrator = state & Cfg::integrator_mask();
+        integrator == Cfg::zero()
     }
 }
 
-#[cfg(test)]
-#[allow(clippy::bool_assert_comparison)]
-mod test {
-    use super::*;
-
-    use embedded_hal_mock::pin;
-
-    #[test]
-    fn simple() {
-        struct Cfg;
-        impl Debounce for Cfg {
-            type Storage = u8;
-            const MAX_COUNT: u8 = 3;
-            const INIT_HIGH: bool = false;
-        }
-
-        let expectations = [
-            pin::Transaction::get(pin::State::High),
-            pin::Transaction::get(pin::State::High),
+impl<Pin: Sampler, Cfg: Debounce> Debouncer<Pin, Cfg> {
+    /// Initialize the pin debouncer, starting out at the pin's current
+    /// level rather than at
+    /// [`INIT_HIGH`](Debounce#associatedconstant.INIT_HIGH).
+    ///
+    /// A toggle switch may be in either position at boot, and a fixed
+    /// initial state would report a spurious first edge whenever it
+    /// doesn't match.  This takes a single sample of the pin and starts
+    /// the debounced state there, settled at the rail, whatever the
+    /// [`INIT_COUNT`](Debounce#associatedconstant.INIT_COUNT).  If the
+    /// pin is already initialized, the configuration is invalid, or the
+    /// sample fails, the error returns the pin.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`init()`](#method.init) apply.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # struct PinType;
+    /// # impl embedded_hal::digital::v2::InputPin for PinType {
+    /// #     type Error = core::convert::Infallible;
+    /// #     fn is_high(&self) -> Result<bool, Self::Error> {
+    /// #         Ok(false)
+    /// #     }
+    /// #     fn is_low(&self) -> Result<bool, Self::Error> {
+    /// #         Ok(true)
+    /// #     }
+    /// # }
+    /// # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow, Level};
+    /// # static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+    /// # fn main() -> Result<(), unflappable::SeedError<PinType, core::convert::Infallible>> {
+    /// #     let input_pin = PinType;
+    /// let debounced_pin = unsafe { DEBOUNCER.init_from_pin(input_pin) }?;
+    /// assert_eq!(Level::Low, debounced_pin.state());
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub unsafe fn init_from_pin(
+        &self,
+        pin: Pin,
+    ) -> Result<Debounced<'_, Cfg>, SeedError<Pin, Pin::Error>> {
+        let mut pin = self.validate_config(pin).map_err(SeedError::Init)?;
+
+        if self.init_flag() {
+            return Err(SeedError::Init(InitError::AlreadyInit(pin)));
+        }
+        let high = match pin.sample() {
+            Ok(high) => high,
+            Err(error) => return Err(SeedError::Sample(pin, error)),
+        };
+
+        // Start settled at the rail, whatever the initial count.
+        let level = if high { Cfg::high_rail() } else { Cfg::zero() };
+        self.init_seeded(pin, high, level).map_err(SeedError::Init)
+    }
+
+    /// Poll the pin debouncer.
+    ///
+    /// This should be done on a regular basis at roughly the frequency
+    /// used in the calculation of [`MAX_COUNT`](Debounce#associatedconstant.MAX_COUNT).
+    ///
+    /// With the `cortex-m` feature, debug builds on Cortex-M targets
+    /// panic if this is called from a different exception or interrupt
+    /// than the first call, see the [`context`](context) module.
+    ///
+    /// Returns the debounced [`Edge`](Edge), if any, that resulted from
+    /// this poll, so that you can react to transitions right away
+    /// without keeping track of the previous state yourself.
+    ///
+    /// # Safety
+    ///
+    /// For this method to be safe, you must ensure that it is not run
+    /// concurrently with a call to any unsafe method of this type,
+    /// including `poll()` itself.  The usual way to do this is to call
+    /// `poll()` from a single interrupt service routine, and not
+    /// enable interrupts until after the call to `init()` returns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # struct PinType;
+    /// # impl embedded_hal::digital::v2::InputPin for PinType {
+    /// #     type Error = core::convert::Infallible;
+    /// #     fn is_high(&self) -> Result<bool, Self::Error> {
+    /// #         Ok(true)
+    /// #     }
+    /// #     fn is_low(&self) -> Result<bool, Self::Error> {
+    /// #         Ok(false)
+    /// #     }
+    /// # }
+    /// # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
+    /// # static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+    /// # let input_pin = PinType;
+    /// # let _ = unsafe { DEBOUNCER.init(input_pin) }.unwrap();
+    /// # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+    /// # fn handle_press() {}
+    /// use unflappable::Edge;
+    ///
+    /// if let Edge::Falling = unsafe { DEBOUNCER.poll()? } {
+    ///     handle_press();
+    /// }
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub unsafe fn poll(&self) -> Result<Edge, PollError<Pin::Error>> {
+        // TODO: can we make this safe with a mutex bit?
+        // is that hair-brained? hare-brained? whatever
+
+        #[cfg(all(feature = "cortex-m", target_arch = "arm", debug_assertions))]
+        self.context.check(context::current());
+
+        self.poll_linted()
+    }
+
+    /// Poll the pin debouncer, reporting any transition as an
+    /// [`Activity`](Activity) according to the configured
+    /// [`ACTIVE`](Debounce#associatedconstant.ACTIVE) level.
+    ///
+    /// ```
+    /// # struct PinType;
+    /// # impl embedded_hal::digital::v2::InputPin for PinType {
+    /// #     type Error = core::convert::Infallible;
+    /// #     fn is_high(&self) -> Result<bool, Self::Error> {
+    /// #         Ok(true)
+    /// #     }
+    /// #     fn is_low(&self) -> Result<bool, Self::Error> {
+    /// #         Ok(false)
+    /// #     }
+    /// # }
+    /// # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
+    /// # static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+    /// # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+    /// # let _ = unsafe { DEBOUNCER.init(PinType) }.unwrap();
+    /// # fn handle_press() {}
+    /// use unflappable::Activity;
+    ///
+    /// if let Activity::Activated = unsafe { DEBOUNCER.poll_activity()? } {
+    ///     handle_press();
+    /// }
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`poll()`](#method.poll) apply.
+    #[inline]
+    pub unsafe fn poll_activity(&self) -> Result<Activity, PollError<Pin::Error>> {
+        self.poll().map(|edge| edge.activity(Cfg::ACTIVE))
+    }
+
+    // n.b. defined seperately to ensure that we think about unsafety.
+    #[inline(always)]
+    fn poll_linted(&self) -> Result<Edge, PollError<Pin::Error>> {
+        let high = self.sample_linted()?;
+
+        Ok(self.integrate(high))
+    }
+
+    /// Poll the pin debouncer, catching up on `n` poll intervals.
+    ///
+    /// Use this when the poll timer has run late, such as after a long
+    /// critical section, and `n` intervals have elapsed since the last
+    /// poll.  The pin is read once, and that sample is integrated as if
+    /// it had been seen on each of the missed polls, so the debounce
+    /// delay stays roughly correct.  With an `n` of zero, the pin is
+    /// read but the state is left alone.
+    ///
+    /// Since every step sees the same sample, there is at most one
+    /// edge, which is returned.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`poll()`](#method.poll) apply.
+    #[inline]
+    pub unsafe fn poll_n(&self, n: u16) -> Result<Edge, PollError<Pin::Error>> {
+        #[cfg(all(feature = "cortex-m", target_arch = "arm", debug_assertions))]
+        self.context.check(context::current());
+
+        let high = self.sample_linted()?;
+
+        for _ in 0..n {
+            let edge = self.integrate(high);
+            if edge != Edge::NoChange {
+                return Ok(edge);
+            }
+            if self.is_settled() {
+                break;
+            }
+        }
+
+        Ok(Edge::NoChange)
+    }
+
+    /// Poll the pin debouncer from jittery timing, where `elapsed` ticks
+    /// have passed since the last call.
+    ///
+    /// The elapsed time is converted to whole poll intervals by the
+    /// [`PollInterval`](time::PollInterval), carrying over any remainder,
+    /// and the debouncer advanced by that many steps with
+    /// [`poll_n()`](#method.poll_n).  This keeps the debounce delay
+    /// roughly right when called from a superloop without an accurate
+    /// periodic interrupt.  To keep a long stall from being made up with
+    /// a single sample, give the interval an
+    /// [overrun limit](time::PollInterval#overrun).
+    ///
+    /// ```
+    /// # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
+    /// # use unflappable::time::PollInterval;
+    /// # struct PinType;
+    /// # impl embedded_hal::digital::v2::InputPin for PinType {
+    /// #     type Error = core::convert::Infallible;
+    /// #     fn is_high(&self) -> Result<bool, Self::Error> {
+    /// #         Ok(true)
+    /// #     }
+    /// #     fn is_low(&self) -> Result<bool, Self::Error> {
+    /// #         Ok(false)
+    /// #     }
+    /// # }
+    /// # fn micros_since_last_loop() -> u32 { 9_000 }
+    /// # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+    /// static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+    /// # let input_pin = PinType;
+    /// let debounced_pin = unsafe { DEBOUNCER.init(input_pin) }.unwrap();
+    ///
+    /// // Poll every 10ms, with a microsecond clock.
+    /// let mut interval = PollInterval::new(10_000);
+    ///
+    /// // In the superloop:
+    /// unsafe { DEBOUNCER.tick(&mut interval, micros_since_last_loop()) }?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`poll()`](#method.poll) apply.
+    #[inline]
+    pub unsafe fn tick(
+        &self,
+        interval: &mut time::PollInterval,
+        elapsed: u32,
+    ) -> Result<Edge, PollError<Pin::Error>> {
+        self.poll_n(interval.polls(elapsed))
+    }
+
+    /// Poll the pin debouncer, stamping any edge with the time from
+    /// `clock`.
+    ///
+    /// Returns `Ok(None)` if the debounced state didn't change.  The
+    /// clock is only read when there is an edge.  See the
+    /// [`time`](time) module for an example.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`poll()`](#method.poll) apply.
+    #[inline]
+    pub unsafe fn poll_timestamped<C: time::Clock>(
+        &self,
+        clock: &C,
+    ) -> Result<Option<time::Timestamped>, PollError<Pin::Error>> {
+        let edge = self.poll()?;
+
+        if edge == Edge::NoChange {
+            return Ok(None);
+        }
+
+        Ok(Some(time::Timestamped {
+            edge,
+            at: clock.now(),
+        }))
+    }
+
+    /// Poll the pin debouncer if the poll timer has elapsed.
+    ///
+    /// Start a periodic `CountDown` timer with the poll period, and then
+    /// call this as often as you like, say from the main loop.  The pin
+    /// is only sampled when the timer has elapsed, in which case the
+    /// result of the poll is returned.  Otherwise, this returns
+    /// `Ok(None)` right away.  This saves setting up an interrupt
+    /// service routine just for polling.
+    ///
+    /// If the main loop falls behind by more than a period, the missed
+    /// polls are lost, and the debounce takes that much longer.
+    ///
+    /// ```
+    /// # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
+    /// # struct PinType;
+    /// # impl embedded_hal::digital::v2::InputPin for PinType {
+    /// #     type Error = core::convert::Infallible;
+    /// #     fn is_high(&self) -> Result<bool, Self::Error> {
+    /// #         Ok(true)
+    /// #     }
+    /// #     fn is_low(&self) -> Result<bool, Self::Error> {
+    /// #         Ok(false)
+    /// #     }
+    /// # }
+    /// # struct Timer;
+    /// # impl embedded_hal::timer::CountDown for Timer {
+    /// #     type Time = u32;
+    /// #     fn start<T: Into<u32>>(&mut self, _: T) {}
+    /// #     fn wait(&mut self) -> nb::Result<(), void::Void> { Ok(()) }
+    /// # }
+    /// # impl embedded_hal::timer::Periodic for Timer {}
+    /// # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+    /// use embedded_hal::timer::CountDown;
+    ///
+    /// static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+    /// # let input_pin = PinType;
+    /// # let mut timer = Timer;
+    /// let debounced_pin = unsafe { DEBOUNCER.init(input_pin) }.unwrap();
+    /// timer.start(10u32);
+    ///
+    /// // In the main loop:
+    /// unsafe { DEBOUNCER.poll_if_due(&mut timer) }?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`poll()`](#method.poll) apply.
+    #[inline]
+    pub unsafe fn poll_if_due<Timer>(
+        &self,
+        timer: &mut Timer,
+    ) -> Result<Option<Edge>, PollError<Pin::Error>>
+    where
+        Timer: CountDown + Periodic,
+    {
+        match timer.wait() {
+            Ok(()) => self.poll().map(Some),
+            Err(nb::Error::WouldBlock) => Ok(None),
+            Err(nb::Error::Other(never)) => match never {},
+        }
+    }
+
+    // n.b. only to be called from a `poll()` variant.
+    #[inline(always)]
+    fn sample_linted(&self) -> Result<bool, PollError<Pin::Error>> {
+        if !self.init_flag() {
+            return Err(PollError::Init);
+        }
+
+        let pin_cell_ptr = self.pin.get();
+        // This is safe because we demand from the caller that this is
+        // an exclusive call, and we only otherwise touch the pin in
+        // `init()` and `deinit()`.
+        let pin_cell = unsafe { &mut *pin_cell_ptr };
+
+        let pin_ptr = pin_cell.as_mut_ptr();
+        // This is safe because we've checked that init has completed.
+        let pin = unsafe { &mut *pin_ptr };
+
+        pin.sample().map_err(PollError::Pin)
+    }
+}
+
+/// Create a new uninitialized [`Debouncer`](Debouncer).
+///
+/// This is the preferred way to initialize a static `Debouncer`.  Be
+/// sure to initialize it before doing anything else with it, or you'll
+/// get an error `Result`.
+///
+/// # Examples
+///
+/// ```
+/// # struct PinType;
+/// # impl embedded_hal::digital::v2::InputPin for PinType {
+/// #     type Error = core::convert::Infallible;
+/// #     fn is_high(&self) -> Result<bool, Self::Error> {
+/// #         Ok(true)
+/// #     }
+/// #     fn is_low(&self) -> Result<bool, Self::Error> {
+/// #         Ok(false)
+/// #     }
+/// # }
+/// use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
+/// static PIN_DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+/// ```
+#[macro_export]
+macro_rules! debouncer_uninit {
+    () => {
+        $crate::Debouncer::uninit(0)
+    };
+}
+
+/// The two halves of a split [`Debouncer`](Debouncer), as returned by
+/// [`init_split()`](Debouncer#method.init_split).
+pub type Split<'a, Pin, Cfg> = (Poller<'a, Pin, Cfg>, Debounced<'a, Cfg>);
+
+/// The polling half of a split [`Debouncer`](Debouncer).
+///
+/// Created by [`Debouncer::init_split()`](Debouncer#method.init_split).
+/// Since there is only ever one `Poller`, it can poll safely.  It
+/// reads the pin, so it may only be sent to another thread if the pin
+/// can.
+///
+/// ```compile_fail
+/// use std::rc::Rc;
+/// use unflappable::{debouncer_uninit, default::ActiveLow, Debouncer};
+///
+/// fn assert_send<T: Send>(_: &T) {}
+///
+/// let debouncer: Debouncer<Rc<()>, ActiveLow> = debouncer_uninit!();
+/// let (poller, _debounced) = unsafe { debouncer.init_split(Rc::new(())) }.unwrap();
+/// assert_send(&poller);
+/// ```
+pub struct Poller<'a, Pin, Cfg: Debounce> {
+    debouncer: &'a Debouncer<Pin, Cfg>,
+    // The debouncer is unconditionally `Sync`, so this keeps the
+    // `Poller` from being `Send` unless the pin is.
+    pin: PhantomData<Pin>,
+}
+
+impl<'a, Pin, Cfg: Debounce> Poller<'a, Pin, Cfg> {
+    /// Poll the debouncer with a sample taken elsewhere.
+    ///
+    /// See [`Debouncer::poll_with()`](Debouncer#method.poll_with).
+    #[inline]
+    pub fn poll_with(&mut self, sample: bool) -> Result<Edge, PollError<Infallible>> {
+        self.debouncer.poll_with_linted(sample)
+    }
+
+    /// Destroy the debounced pin and this poller, returning the original
+    /// input pin.
+    ///
+    /// See [`Debouncer::deinit()`](Debouncer#method.deinit).  Holding
+    /// the only `Poller`, this can't race a poll, so it's safe.  If the
+    /// debounced pin belongs to another debouncer, the error returns
+    /// this poller along with it, so the debouncer can still be polled
+    /// and deinitialized with the right pin.
+    #[inline]
+    pub fn deinit<'b>(self, pin: Debounced<'b, Cfg>) -> Result<Pin, (Self, DeinitError<'b, Cfg>)> {
+        match self.debouncer.deinit_linted(pin) {
+            Ok(pin) => Ok(pin),
+            Err(error) => Err((self, error)),
+        }
+    }
+}
+
+impl<'a, Pin, Cfg: Debounce> core::fmt::Debug for Poller<'a, Pin, Cfg> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Poller")
+    }
+}
+
+impl<'a, Pin: Sampler, Cfg: Debounce> Poller<'a, Pin, Cfg> {
+    /// Poll the debouncer.
+    ///
+    /// See [`Debouncer::poll()`](Debouncer#method.poll).
+    #[inline]
+    pub fn poll(&mut self) -> Result<Edge, PollError<Pin::Error>> {
+        self.debouncer.poll_linted()
+    }
+}
+
+/// A debounced pin.
+///
+/// This is what you'll use for downstream input processing, leveraging
+/// the methods provided by the trait [`InputPin`](#impl-InputPin).
+pub struct Debounced<'state, Cfg: Debounce> {
+    cfg: PhantomData<Cfg>,
+    storage: &'state UnsafeCell<Cfg::Storage>,
+    #[cfg(feature = "async")]
+    waker: &'state atomic_waker::AtomicWaker,
+}
+
+impl<'state, Cfg: Debounce> Debounced<'state, Cfg> {
+    /// The current debounced level.
+    ///
+    /// This is the same information as is available through the
+    /// [`InputPin`](#impl-InputPin) methods, but without the
+    /// `Infallible` result to unwrap.
+    #[inline(always)]
+    pub fn state(&self) -> Level {
+        let state_ptr = self.storage.get();
+        // This is safe since the read is atomic.
+        let state = unsafe { *state_ptr };
+        let flag = state & Cfg::state_mask();
+        Level::from(flag != Cfg::zero())
+    }
+
+    /// A predicate that is true while the debounced level is `active`.
+    ///
+    /// This is handy for APIs that take a simple `Fn() -> bool`, such as
+    /// a "should we stay in the bootloader" check, saving you unwrapping
+    /// the `Infallible` result of the `InputPin` methods.
+    ///
+    /// ```
+    /// # use unflappable::{debouncer_uninit, Debouncer, Level, default::ActiveLow};
+    /// # static DEBOUNCER: Debouncer<(), ActiveLow> = debouncer_uninit!();
+    /// # let debounced = unsafe { DEBOUNCER.init(()) }.unwrap();
+    /// fn wait_while(condition: impl Fn() -> bool) {
+    ///     while condition() {}
+    /// }
+    ///
+    /// let pressed = debounced.reader(Level::Low);
+    /// wait_while(pressed);
+    /// ```
+    #[inline]
+    pub fn reader(&self, active: Level) -> impl Fn() -> bool + 'state {
+        let storage = self.storage;
+        move || {
+            // This is safe since the read is atomic.
+            let state = unsafe { *storage.get() };
+            let flag = state & Cfg::state_mask();
+            Level::from(flag != Cfg::zero()) == active
+        }
+    }
+}
+
+impl<'state, Cfg: Debounce> InputPin for Debounced<'state, Cfg> {
+    type Error = Infallible;
+
+    #[inline(always)]
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        let state_ptr = self.storage.get();
+        // This is safe since the read is atomic.
+        let state = unsafe { *state_ptr };
+        let flag = state & Cfg::state_mask();
+        Ok(flag != Cfg::zero())
+    }
+
+    #[inline(always)]
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        let state_ptr = self.storage.get();
+        // This is safe since the read is atomic.
+        let state = unsafe { *state_ptr };
+        let flag = state & Cfg::state_mask();
+        Ok(flag == Cfg::zero())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    use embedded_hal_mock::pin;
+
+    #[test]
+    fn simple() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 3;
+            const INIT_HIGH: bool = false;
+        }
+
+        let expectations = [
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::Low),
+            pin::Transaction::get(pin::State::Low),
+            pin::Transaction::get(pin::State::Low),
+        ];
+
+        let pin = pin::Mock::new(&expectations);
+
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin) }.expect("debounced pin");
+
+        assert_eq!(true, debounced.is_low().unwrap());
+        assert_eq!(false, debounced.is_high().unwrap());
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll() }.unwrap();
+
+        assert_eq!(true, debounced.is_low().unwrap());
+        assert_eq!(false, debounced.is_high().unwrap());
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll() }.unwrap();
+
+        assert_eq!(true, debounced.is_low().unwrap());
+        assert_eq!(false, debounced.is_high().unwrap());
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll() }.unwrap();
+
+        assert_eq!(false, debounced.is_low().unwrap());
+        assert_eq!(true, debounced.is_high().unwrap());
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll() }.unwrap();
+
+        assert_eq!(false, debounced.is_low().unwrap());
+        assert_eq!(true, debounced.is_high().unwrap());
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll() }.unwrap();
+
+        assert_eq!(false, debounced.is_low().unwrap());
+        assert_eq!(true, debounced.is_high().unwrap());
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll() }.unwrap();
+
+        assert_eq!(true, debounced.is_low().unwrap());
+        assert_eq!(false, debounced.is_high().unwrap());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        let mut pin = unsafe { debouncer.deinit(debounced) }.unwrap();
+        pin.done();
+    }
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 3;
+        const INIT_HIGH: bool = false;
+    }
+
+    static SIMPLE_STATIC_TEST: Debouncer<pin::Mock, Cfg> = debouncer_uninit!();
+
+    #[test]
+    fn simple_static() {
+        let expectations = [
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::Low),
+            pin::Transaction::get(pin::State::Low),
+            pin::Transaction::get(pin::State::Low),
+        ];
+
+        let pin = pin::Mock::new(&expectations);
+
+        // This is safe since this is the only test using this Debouncer.
+        let debounced = unsafe { SIMPLE_STATIC_TEST.init(pin) }.expect("debounced pin");
+
+        assert_eq!(true, debounced.is_low().unwrap());
+        assert_eq!(false, debounced.is_high().unwrap());
+
+        // This is safe since this is the only test using this Debouncer.
+        unsafe { SIMPLE_STATIC_TEST.poll() }.unwrap();
+
+        assert_eq!(true, debounced.is_low().unwrap());
+        assert_eq!(false, debounced.is_high().unwrap());
+
+        // This is safe since this is the only test using this Debouncer.
+        unsafe { SIMPLE_STATIC_TEST.poll() }.unwrap();
+
+        assert_eq!(true, debounced.is_low().unwrap());
+        assert_eq!(false, debounced.is_high().unwrap());
+
+        // This is safe since this is the only test using this Debouncer.
+        unsafe { SIMPLE_STATIC_TEST.poll() }.unwrap();
+
+        assert_eq!(false, debounced.is_low().unwrap());
+        assert_eq!(true, debounced.is_high().unwrap());
+
+        // This is safe since this is the only test using this Debouncer.
+        unsafe { SIMPLE_STATIC_TEST.poll() }.unwrap();
+
+        assert_eq!(false, debounced.is_low().unwrap());
+        assert_eq!(true, debounced.is_high().unwrap());
+
+        // This is safe since this is the only test using this Debouncer.
+        unsafe { SIMPLE_STATIC_TEST.poll() }.unwrap();
+
+        assert_eq!(false, debounced.is_low().unwrap());
+        assert_eq!(true, debounced.is_high().unwrap());
+
+        // This is safe since this is the only test using this Debouncer.
+        unsafe { SIMPLE_STATIC_TEST.poll() }.unwrap();
+
+        assert_eq!(true, debounced.is_low().unwrap());
+        assert_eq!(false, debounced.is_high().unwrap());
+
+        // This is safe since this is the only test using this Debouncer.
+        let mut pin = unsafe { SIMPLE_STATIC_TEST.deinit(debounced) }.unwrap();
+        pin.done();
+    }
+
+    #[test]
+    fn edges() {
+        let expectations = [
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::High),
             pin::Transaction::get(pin::State::High),
             pin::Transaction::get(pin::State::Low),
             pin::Transaction::get(pin::State::Low),
             pin::Transaction::get(pin::State::Low),
         ];
 
-        let pin = pin::Mock::new(&expectations);
+        let pin = pin::Mock::new(&expectations);
+
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin) }.expect("debounced pin");
+
+        let edges = [
+            Edge::NoChange,
+            Edge::NoChange,
+            Edge::Rising,
+            Edge::NoChange,
+            Edge::NoChange,
+            Edge::NoChange,
+            Edge::Falling,
+        ];
+        for &edge in edges.iter() {
+            // It is always safe to poll a stack-scoped Debouncer.
+            assert_eq!(edge, unsafe { debouncer.poll() }.unwrap());
+        }
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        let mut pin = unsafe { debouncer.deinit(debounced) }.unwrap();
+        pin.done();
+    }
+
+    #[test]
+    fn external_samples() {
+        let debouncer: Debouncer<(), Cfg> = debouncer_uninit!();
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert!(matches!(
+            unsafe { debouncer.poll_with(true) },
+            Err(PollError::Init)
+        ));
+
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.expect("debounced pin");
+
+        let samples = [true, false, true, true, true];
+        let edges = [
+            Edge::NoChange,
+            Edge::NoChange,
+            Edge::NoChange,
+            Edge::NoChange,
+            Edge::Rising,
+        ];
+        for (&sample, &edge) in samples.iter().zip(edges.iter()) {
+            // It is always safe to poll a stack-scoped Debouncer.
+            assert_eq!(edge, unsafe { debouncer.poll_with(sample) }.unwrap());
+        }
+        assert_eq!(Level::High, debounced.state());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "observer")]
+    fn observer_callbacks() {
+        use core::sync::atomic::{AtomicU8, Ordering};
+
+        static RISES: AtomicU8 = AtomicU8::new(0);
+        static FALLS: AtomicU8 = AtomicU8::new(0);
+
+        let observer = Observer {
+            on_rise: || {
+                RISES.fetch_add(1, Ordering::Relaxed);
+            },
+            on_fall: || {
+                FALLS.fetch_add(1, Ordering::Relaxed);
+            },
+        };
+
+        let debouncer: Debouncer<(), Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init_observed((), observer) }.unwrap();
+
+        for &sample in [true, true, true, true, false, false, false, false].iter() {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_with(sample) }.unwrap();
+        }
+        assert_eq!(1, RISES.load(Ordering::Relaxed));
+        assert_eq!(1, FALLS.load(Ordering::Relaxed));
+
+        // Re-initializing without an observer clears it.
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+        // It is always safe to init a stack-scoped Debouncer.
+        unsafe { debouncer.init(()) }.unwrap();
+        for _ in 0..4 {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_with(true) }.unwrap();
+        }
+        assert_eq!(1, RISES.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn level_conversions() {
+        assert_eq!(Level::High, Level::from(true));
+        assert_eq!(Level::Low, Level::from(false));
+        assert_eq!(true, bool::from(Level::High));
+        assert_eq!(false, bool::from(Level::Low));
+        assert_eq!(Level::High, Level::from(PinState::High));
+        assert_eq!(PinState::Low, PinState::from(Level::Low));
+        assert_eq!(Level::Low, !Level::High);
+    }
+
+    #[test]
+    fn debounced_state() {
+        let expectations = [
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::High),
+            pin::Transaction::get(pin::State::High),
+        ];
+
+        let pin = pin::Mock::new(&expectations);
+
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin) }.expect("debounced pin");
+
+        assert_eq!(Level::Low, debounced.state());
+
+        for _ in 0..3 {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll() }.unwrap();
+        }
+
+        assert_eq!(Level::High, debounced.state());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        let mut pin = unsafe { debouncer.deinit(debounced) }.unwrap();
+        pin.done();
+    }
+
+    #[test]
+    fn asymmetric_counts() {
+        struct Switch;
+        impl Debounce for Switch {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 2;
+            const MAX_COUNT_FALL: u8 = 4;
+            const INIT_HIGH: bool = false;
+        }
+
+        let debouncer: Debouncer<(), Switch> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        // Once high, it takes four more lows than highs to fall.
+        let samples = [true, true, false, false, false, true, false, false];
+        let mut edges = [Edge::NoChange; 8];
+        for (edge, &sample) in edges.iter_mut().zip(samples.iter()) {
+            // It is always safe to poll a stack-scoped Debouncer.
+            *edge = unsafe { debouncer.poll_with(sample) }.unwrap();
+        }
+        assert_eq!(Edge::Rising, edges[1]);
+        assert_eq!(Edge::Falling, edges[7]);
+        assert_eq!(
+            6,
+            edges.iter().filter(|&&edge| edge == Edge::NoChange).count()
+        );
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    fn hysteresis_thresholds() {
+        struct Schmitt;
+        impl Debounce for Schmitt {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 4;
+            const SET_THRESHOLD: Option<u8> = Some(3);
+            const CLEAR_THRESHOLD: Option<u8> = Some(1);
+            const INIT_HIGH: bool = false;
+        }
+
+        let debouncer: Debouncer<(), Schmitt> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        let samples = [
+            true, true, true, true, false, false, false, true, false, true, true,
+        ];
+        let mut edges = [Edge::NoChange; 11];
+        for (edge, &sample) in edges.iter_mut().zip(samples.iter()) {
+            // It is always safe to poll a stack-scoped Debouncer.
+            *edge = unsafe { debouncer.poll_with(sample) }.unwrap();
+        }
+        assert_eq!(Edge::Rising, edges[2]);
+        assert_eq!(Edge::Falling, edges[6]);
+        assert_eq!(Edge::Rising, edges[10]);
+        assert_eq!(
+            8,
+            edges.iter().filter(|&&edge| edge == Edge::NoChange).count()
+        );
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    fn consecutive_samples() {
+        struct Classic;
+        impl Debounce for Classic {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 3;
+            const INIT_HIGH: bool = false;
+            const ALGORITHM: Algorithm = Algorithm::Consecutive;
+        }
+
+        let debouncer: Debouncer<(), Classic> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        // A single disagreeing sample starts the count over.
+        let samples = [
+            true, true, false, true, true, true, false, false, true, false, false, false,
+        ];
+        let mut edges = [Edge::NoChange; 12];
+        for (edge, &sample) in edges.iter_mut().zip(samples.iter()) {
+            // It is always safe to poll a stack-scoped Debouncer.
+            *edge = unsafe { debouncer.poll_with(sample) }.unwrap();
+        }
+        assert_eq!(Edge::Rising, edges[5]);
+        assert_eq!(Edge::Falling, edges[11]);
+        assert_eq!(
+            10,
+            edges.iter().filter(|&&edge| edge == Edge::NoChange).count()
+        );
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    fn majority_vote() {
+        struct Vote;
+        impl Debounce for Vote {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 1;
+            const INIT_HIGH: bool = true;
+            const ALGORITHM: Algorithm = Algorithm::Majority {
+                window: 5,
+                votes: 4,
+            };
+        }
+
+        let debouncer: Debouncer<(), Vote> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        // Three lows in a row aren't enough, but four of the last five are.
+        let samples = [false, false, false, true, false, true, false, false];
+        let mut edges = [Edge::NoChange; 8];
+        for (edge, &sample) in edges.iter_mut().zip(samples.iter()) {
+            // It is always safe to poll a stack-scoped Debouncer.
+            *edge = unsafe { debouncer.poll_with(sample) }.unwrap();
+        }
+        assert_eq!(Edge::Falling, edges[4]);
+        assert_eq!(
+            7,
+            edges.iter().filter(|&&edge| edge == Edge::NoChange).count()
+        );
+        assert_eq!(Level::Low, debounced.state());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    fn reader_predicate() {
+        let debouncer: Debouncer<(), Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        let is_high = debounced.reader(Level::High);
+        let is_low = debounced.reader(Level::Low);
+        assert_eq!(false, is_high());
+        assert_eq!(true, is_low());
+
+        for _ in 0..3 {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_with(true) }.unwrap();
+        }
+        assert_eq!(true, is_high());
+        assert_eq!(false, is_low());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    fn fast_attack() {
+        type Alarm = default::FastAttack<2, 5, false>;
+
+        let debouncer: Debouncer<(), Alarm> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll_with(true) }.unwrap();
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(Edge::Rising, unsafe { debouncer.poll_with(true) }.unwrap());
+
+        for _ in 0..4 {
+            // It is always safe to poll a stack-scoped Debouncer.
+            assert_eq!(
+                Edge::NoChange,
+                unsafe { debouncer.poll_with(false) }.unwrap()
+            );
+        }
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(
+            Edge::Falling,
+            unsafe { debouncer.poll_with(false) }.unwrap()
+        );
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "debug-config",
+        should_panic(expected = "Debounce::SET_THRESHOLD")
+    )]
+    fn thresholds_reversed() {
+        struct Reversed;
+        impl Debounce for Reversed {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 4;
+            const SET_THRESHOLD: Option<u8> = Some(1);
+            const CLEAR_THRESHOLD: Option<u8> = Some(3);
+            const INIT_HIGH: bool = false;
+        }
+
+        let debouncer: Debouncer<(), Reversed> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let result = unsafe { debouncer.init(()) };
+        assert!(matches!(result, Err(InitError::BadConfig(()))));
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "debug-config",
+        should_panic(expected = "Debounce::SET_THRESHOLD")
+    )]
+    fn set_threshold_above_rise() {
+        struct AboveRise;
+        impl Debounce for AboveRise {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 4;
+            const SET_THRESHOLD: Option<u8> = Some(5);
+            const INIT_HIGH: bool = false;
+        }
+
+        let debouncer: Debouncer<(), AboveRise> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let result = unsafe { debouncer.init(()) };
+        assert!(matches!(result, Err(InitError::BadConfig(()))));
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "debug-config",
+        should_panic(expected = "Debounce::CLEAR_THRESHOLD")
+    )]
+    fn clear_threshold_at_fall() {
+        struct AtFall;
+        impl Debounce for AtFall {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 4;
+            const MAX_COUNT_FALL: u8 = 2;
+            const CLEAR_THRESHOLD: Option<u8> = Some(2);
+            const INIT_HIGH: bool = false;
+        }
+
+        let debouncer: Debouncer<(), AtFall> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let result = unsafe { debouncer.init(()) };
+        assert!(matches!(result, Err(InitError::BadConfig(()))));
+    }
+
+    #[test]
+    fn initial_count() {
+        struct MidScale;
+        impl Debounce for MidScale {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 4;
+            const INIT_HIGH: bool = false;
+            const INIT_COUNT: Option<u8> = Some(2);
+        }
+
+        let debouncer: Debouncer<(), MidScale> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+        assert_eq!(false, debouncer.is_settled());
+
+        // A startup glitch is absorbed.
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(
+            Edge::NoChange,
+            unsafe { debouncer.poll_with(true) }.unwrap()
+        );
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(
+            Edge::NoChange,
+            unsafe { debouncer.poll_with(false) }.unwrap()
+        );
+
+        // The first real transition only needs to cover half the scale.
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(
+            Edge::NoChange,
+            unsafe { debouncer.poll_with(true) }.unwrap()
+        );
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(Edge::Rising, unsafe { debouncer.poll_with(true) }.unwrap());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "debug-config",
+        should_panic(expected = "Debounce::INIT_COUNT")
+    )]
+    fn initial_count_out_of_range() {
+        struct TooHigh;
+        impl Debounce for TooHigh {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 4;
+            const INIT_HIGH: bool = false;
+            const INIT_COUNT: Option<u8> = Some(4);
+        }
+
+        let debouncer: Debouncer<(), TooHigh> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let result = unsafe { debouncer.init(()) };
+        assert!(matches!(result, Err(InitError::BadConfig(()))));
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "debug-config",
+        should_panic(expected = "Debounce::INIT_COUNT")
+    )]
+    fn initial_count_above_rail() {
+        struct AboveRail;
+        impl Debounce for AboveRail {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 4;
+            const INIT_HIGH: bool = false;
+            const INIT_COUNT: Option<u8> = Some(10);
+        }
+
+        let debouncer: Debouncer<(), AboveRail> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let result = unsafe { debouncer.init(()) };
+        assert!(matches!(result, Err(InitError::BadConfig(()))));
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "debug-config",
+        should_panic(expected = "Debounce::INIT_COUNT")
+    )]
+    fn initial_count_high_above_rail() {
+        struct AboveRail;
+        impl Debounce for AboveRail {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 4;
+            const INIT_HIGH: bool = true;
+            const INIT_COUNT: Option<u8> = Some(6);
+        }
+
+        let debouncer: Debouncer<(), AboveRail> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let result = unsafe { debouncer.init(()) };
+        assert!(matches!(result, Err(InitError::BadConfig(()))));
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "debug-config",
+        should_panic(expected = "Debounce::INIT_COUNT")
+    )]
+    fn initial_count_high_below_clear() {
+        struct BelowClear;
+        impl Debounce for BelowClear {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 4;
+            const CLEAR_THRESHOLD: Option<u8> = Some(2);
+            const INIT_HIGH: bool = true;
+            const INIT_COUNT: Option<u8> = Some(1);
+        }
+
+        let debouncer: Debouncer<(), BelowClear> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let result = unsafe { debouncer.init(()) };
+        assert!(matches!(result, Err(InitError::BadConfig(()))));
+    }
+
+    #[test]
+    fn init_twice() {
+        let debouncer: Debouncer<(), default::ActiveLow> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        // It is always safe to init a stack-scoped Debouncer.
+        let result = unsafe { debouncer.init(()) };
+        assert!(matches!(result, Err(InitError::AlreadyInit(()))));
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    fn init_from_pin() {
+        let low = pin::Transaction::get(pin::State::Low);
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [low.clone(), low, high];
 
-        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        let debouncer: Debouncer<_, default::ActiveHigh> = debouncer_uninit!();
         // It is always safe to init a stack-scoped Debouncer.
-        let debounced = unsafe { debouncer.init(pin) }.expect("debounced pin");
-
-        assert_eq!(true, debounced.is_low().unwrap());
-        assert_eq!(false, debounced.is_high().unwrap());
+        let debounced = unsafe { debouncer.init_from_pin(pin::Mock::new(&expectations)) }.unwrap();
+        assert_eq!(Level::Low, debounced.state());
+        assert_eq!(true, debouncer.is_settled());
 
+        // No spurious first edge.
         // It is always safe to poll a stack-scoped Debouncer.
-        unsafe { debouncer.poll() }.unwrap();
+        assert_eq!(Edge::NoChange, unsafe { debouncer.poll() }.unwrap());
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(Edge::NoChange, unsafe { debouncer.poll() }.unwrap());
 
-        assert_eq!(true, debounced.is_low().unwrap());
-        assert_eq!(false, debounced.is_high().unwrap());
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
 
-        // It is always safe to poll a stack-scoped Debouncer.
-        unsafe { debouncer.poll() }.unwrap();
+    #[test]
+    fn init_from_pin_ignores_init_count() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 4;
+            const INIT_HIGH: bool = true;
+            const INIT_COUNT: Option<u8> = Some(2);
+        }
 
-        assert_eq!(true, debounced.is_low().unwrap());
-        assert_eq!(false, debounced.is_high().unwrap());
+        let high = pin::Transaction::get(pin::State::High);
+        let low = pin::Transaction::get(pin::State::Low);
+        let expectations = [high, low.clone(), low.clone(), low.clone(), low];
 
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init_from_pin(pin::Mock::new(&expectations)) }.unwrap();
+        assert_eq!(Level::High, debounced.state());
+        assert_eq!(true, debouncer.is_settled());
+
+        // Starting at the rail, it takes the full count to fall.
+        for _ in 0..3 {
+            // It is always safe to poll a stack-scoped Debouncer.
+            assert_eq!(Edge::NoChange, unsafe { debouncer.poll() }.unwrap());
+        }
         // It is always safe to poll a stack-scoped Debouncer.
-        unsafe { debouncer.poll() }.unwrap();
+        assert_eq!(Edge::Falling, unsafe { debouncer.poll() }.unwrap());
 
-        assert_eq!(false, debounced.is_low().unwrap());
-        assert_eq!(true, debounced.is_high().unwrap());
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
 
-        // It is always safe to poll a stack-scoped Debouncer.
-        unsafe { debouncer.poll() }.unwrap();
+    #[test]
+    fn init_from_pin_seeds_high() {
+        let debouncer: Debouncer<_, default::ActiveHigh> = debouncer_uninit!();
+        let expectations = [pin::Transaction::get(pin::State::High)];
 
-        assert_eq!(false, debounced.is_low().unwrap());
-        assert_eq!(true, debounced.is_high().unwrap());
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init_from_pin(pin::Mock::new(&expectations)) }.unwrap();
+        assert_eq!(Level::High, debounced.state());
+        assert_eq!(true, debouncer.is_settled());
 
-        // It is always safe to poll a stack-scoped Debouncer.
-        unsafe { debouncer.poll() }.unwrap();
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
 
-        assert_eq!(false, debounced.is_low().unwrap());
-        assert_eq!(true, debounced.is_high().unwrap());
+    #[test]
+    fn const_debounce() {
+        let debouncer: Debouncer<(), default::ConstDebounce<3, true>> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+        assert_eq!(Level::High, debounced.state());
+
+        for &edge in [Edge::NoChange, Edge::NoChange, Edge::Falling].iter() {
+            // It is always safe to poll a stack-scoped Debouncer.
+            assert_eq!(edge, unsafe { debouncer.poll_with(false) }.unwrap());
+        }
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    fn max_count_from_time() {
+        assert_eq!(4, default::max_count(40, 100));
+        assert_eq!(6, default::max_count(30, 200));
+        assert_eq!(2, default::max_count(15, 100));
+        assert_eq!(63, default::max_count(630, 100));
+
+        type Button = default::ConstDebounce<{ default::max_count(20, 100) }, false>;
+        assert_eq!(2, Button::MAX_COUNT);
+    }
+
+    #[test]
+    #[should_panic(expected = "too long")]
+    fn max_count_too_long() {
+        default::max_count(640, 100);
+    }
+
+    #[test]
+    #[cfg(feature = "fugit")]
+    fn fugit_durations() {
+        use fugit::{MicrosDurationU32, MillisDurationU32};
+
+        let ms = MillisDurationU32::millis;
+        assert_eq!(4, default::max_count_for(ms(40), ms(10)));
+        assert_eq!(3, default::max_count_for(ms(25), ms(10)));
+
+        let us = MicrosDurationU32::micros;
+        assert_eq!(6, default::max_count_for(us(30_000), us(5_000)));
+
+        let timing = Timing::of::<default::ConstDebounce<3, false>>();
+        assert_eq!(ms(15), timing.min_delay(ms(5)));
+    }
+
+    #[test]
+    fn poll_timestamped() {
+        use core::cell::Cell;
+
+        let low = pin::Transaction::get(pin::State::Low);
+        let expectations = [low.clone(), low];
+
+        let debouncer: Debouncer<_, default::ConstDebounce<2, true>> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin::Mock::new(&expectations)) }.unwrap();
+
+        let ticks = Cell::new(0);
+        let clock = || {
+            ticks.set(ticks.get() + 10);
+            ticks.get()
+        };
 
         // It is always safe to poll a stack-scoped Debouncer.
-        unsafe { debouncer.poll() }.unwrap();
+        assert_eq!(None, unsafe { debouncer.poll_timestamped(&clock) }.unwrap());
+        assert_eq!(
+            Some(time::Timestamped {
+                edge: Edge::Falling,
+                at: 10,
+            }),
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_timestamped(&clock) }.unwrap()
+        );
 
-        assert_eq!(true, debounced.is_low().unwrap());
-        assert_eq!(false, debounced.is_high().unwrap());
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
+
+    #[test]
+    fn split_poller() {
+        let low = pin::Transaction::get(pin::State::Low);
+        let expectations = [low.clone(), low];
+
+        let debouncer: Debouncer<_, default::ConstDebounce<2, true>> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let (mut poller, debounced) =
+            unsafe { debouncer.init_split(pin::Mock::new(&expectations)) }.unwrap();
+
+        assert_eq!(Edge::NoChange, poller.poll().unwrap());
+        assert_eq!(Edge::Falling, poller.poll().unwrap());
+        assert_eq!(Level::Low, debounced.state());
+        assert_eq!(Edge::NoChange, poller.poll_with(true).unwrap());
+        assert_eq!(Edge::Rising, poller.poll_with(true).unwrap());
+
+        poller.deinit(debounced).unwrap().done();
+        assert_eq!(false, debouncer.is_initialized());
+    }
+
+    #[test]
+    fn split_poller_deinit_wrong_pin() {
+        let debouncer: Debouncer<(), default::ActiveHigh> = debouncer_uninit!();
+        let other: Debouncer<(), default::ActiveHigh> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let (poller, debounced) = unsafe { debouncer.init_split(()) }.unwrap();
+        // It is always safe to init a stack-scoped Debouncer.
+        let other_debounced = unsafe { other.init(()) }.unwrap();
+
+        // The wrong pin hands back the poller, still able to poll.
+        let (mut poller, error) = poller.deinit(other_debounced).unwrap_err();
+        let other_debounced = match error {
+            DeinitError::Pin(pin) => pin,
+            DeinitError::Init => panic!("expected the pin back"),
+        };
+        assert_eq!(Edge::NoChange, poller.poll_with(true).unwrap());
+
+        poller.deinit(debounced).unwrap();
+        assert_eq!(false, debouncer.is_initialized());
 
         // It is always safe to deinit a stack-scoped Debouncer.
-        let mut pin = unsafe { debouncer.deinit(debounced) }.unwrap();
-        pin.done();
+        unsafe { other.deinit(other_debounced) }.unwrap();
     }
 
-    struct Cfg;
-    impl Debounce for Cfg {
-        type Storage = u8;
-        const MAX_COUNT: u8 = 3;
-        const INIT_HIGH: bool = false;
+    #[test]
+    fn is_initialized() {
+        let debouncer: Debouncer<(), default::ActiveHigh> = debouncer_uninit!();
+        assert_eq!(false, debouncer.is_initialized());
+
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+        assert_eq!(true, debouncer.is_initialized());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+        assert_eq!(false, debouncer.is_initialized());
     }
 
-    static SIMPLE_STATIC_TEST: Debouncer<pin::Mock, Cfg> = debouncer_uninit!();
+    #[test]
+    fn status() {
+        let debouncer: Debouncer<(), default::ActiveLow> = debouncer_uninit!();
+        assert_eq!(
+            Status {
+                level: Level::Low,
+                integrator: 0,
+                initialized: false,
+            },
+            debouncer.status()
+        );
+
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+        let status = debouncer.status();
+        assert_eq!(Level::High, status.level);
+        assert_eq!(default::ActiveLow::MAX_COUNT, status.integrator);
+        assert_eq!(true, status.initialized);
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll_with(false) }.unwrap();
+        assert_eq!(
+            default::ActiveLow::MAX_COUNT - 1,
+            debouncer.status().integrator
+        );
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
 
     #[test]
-    fn simple_static() {
-        let expectations = [
-            pin::Transaction::get(pin::State::High),
-            pin::Transaction::get(pin::State::High),
-            pin::Transaction::get(pin::State::High),
-            pin::Transaction::get(pin::State::Low),
-            pin::Transaction::get(pin::State::Low),
-            pin::Transaction::get(pin::State::Low),
-        ];
+    #[cfg(feature = "stats")]
+    fn stats() {
+        let debouncer: Debouncer<(), default::ActiveHigh> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
 
-        let pin = pin::Mock::new(&expectations);
+        for &sample in [true, false, true, true, true, true, false].iter() {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_with(sample) }.unwrap();
+        }
+        for _ in 0..default::ActiveHigh::MAX_COUNT {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_with(false) }.unwrap();
+        }
 
-        // This is safe since this is the only test using this Debouncer.
-        let debounced = unsafe { SIMPLE_STATIC_TEST.init(pin) }.expect("debounced pin");
+        assert_eq!(
+            Stats {
+                transitions: 2,
+                flips: 4,
+            },
+            debouncer.stats()
+        );
 
-        assert_eq!(true, debounced.is_low().unwrap());
-        assert_eq!(false, debounced.is_high().unwrap());
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
 
-        // This is safe since this is the only test using this Debouncer.
-        unsafe { SIMPLE_STATIC_TEST.poll() }.unwrap();
+    #[test]
+    fn poll_n() {
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [high.clone(), high.clone(), high];
 
-        assert_eq!(true, debounced.is_low().unwrap());
-        assert_eq!(false, debounced.is_high().unwrap());
+        let debouncer: Debouncer<_, default::ActiveHigh> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin::Mock::new(&expectations)) }.unwrap();
 
-        // This is safe since this is the only test using this Debouncer.
-        unsafe { SIMPLE_STATIC_TEST.poll() }.unwrap();
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(Edge::NoChange, unsafe { debouncer.poll_n(0) }.unwrap());
+        assert_eq!(0, debouncer.status().integrator);
 
-        assert_eq!(true, debounced.is_low().unwrap());
-        assert_eq!(false, debounced.is_high().unwrap());
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(Edge::NoChange, unsafe { debouncer.poll_n(3) }.unwrap());
+        assert_eq!(Level::Low, debounced.state());
 
-        // This is safe since this is the only test using this Debouncer.
-        unsafe { SIMPLE_STATIC_TEST.poll() }.unwrap();
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(Edge::Rising, unsafe { debouncer.poll_n(100) }.unwrap());
+        assert_eq!(Level::High, debounced.state());
 
-        assert_eq!(false, debounced.is_low().unwrap());
-        assert_eq!(true, debounced.is_high().unwrap());
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
 
-        // This is safe since this is the only test using this Debouncer.
-        unsafe { SIMPLE_STATIC_TEST.poll() }.unwrap();
+    #[test]
+    fn poll_activity() {
+        let low = pin::Transaction::get(pin::State::Low);
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [
+            low.clone(),
+            low.clone(),
+            low.clone(),
+            low,
+            high.clone(),
+            high.clone(),
+            high.clone(),
+            high,
+        ];
 
-        assert_eq!(false, debounced.is_low().unwrap());
-        assert_eq!(true, debounced.is_high().unwrap());
+        assert_eq!(Level::Low, default::ActiveLow::ACTIVE);
+        let debouncer: Debouncer<_, default::ActiveLow> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin::Mock::new(&expectations)) }.unwrap();
 
-        // This is safe since this is the only test using this Debouncer.
-        unsafe { SIMPLE_STATIC_TEST.poll() }.unwrap();
+        let mut activity = [Activity::NoChange; 8];
+        for slot in activity.iter_mut() {
+            // It is always safe to poll a stack-scoped Debouncer.
+            *slot = unsafe { debouncer.poll_activity() }.unwrap();
+        }
+        assert_eq!(
+            1,
+            activity
+                .iter()
+                .filter(|&&a| a == Activity::Activated)
+                .count()
+        );
+        assert_eq!(
+            1,
+            activity
+                .iter()
+                .filter(|&&a| a == Activity::Deactivated)
+                .count()
+        );
 
-        assert_eq!(false, debounced.is_low().unwrap());
-        assert_eq!(true, debounced.is_high().unwrap());
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
 
-        // This is safe since this is the only test using this Debouncer.
-        unsafe { SIMPLE_STATIC_TEST.poll() }.unwrap();
+    #[test]
+    fn snapshot_restore() {
+        let before: Debouncer<(), default::ActiveLow> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let _ = unsafe { before.init(()) }.unwrap();
+        for &sample in [false, false, false, false, true].iter() {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { before.poll_with(sample) }.unwrap();
+        }
+        let bytes = before.snapshot().to_bytes();
 
-        assert_eq!(true, debounced.is_low().unwrap());
-        assert_eq!(false, debounced.is_high().unwrap());
+        let after: Debouncer<(), default::ActiveLow> = debouncer_uninit!();
+        let snapshot = snapshot::StateSnapshot::from_bytes(&bytes).unwrap();
+        // It is always safe to restore a stack-scoped Debouncer.
+        assert_eq!(Err(snapshot::RestoreError::Init), unsafe {
+            after.restore(snapshot)
+        });
 
-        // This is safe since this is the only test using this Debouncer.
-        let mut pin = unsafe { SIMPLE_STATIC_TEST.deinit(debounced) }.unwrap();
-        pin.done();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { after.init(()) }.unwrap();
+        // It is always safe to restore a stack-scoped Debouncer.
+        unsafe { after.restore(snapshot) }.unwrap();
+        assert_eq!(before.status(), after.status());
+        assert_eq!(Level::Low, debounced.state());
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(Edge::NoChange, unsafe { after.poll_with(true) }.unwrap());
+        assert_eq!(Edge::NoChange, unsafe { after.poll_with(true) }.unwrap());
+        assert_eq!(Edge::Rising, unsafe { after.poll_with(true) }.unwrap());
+    }
+
+    #[test]
+    fn restore_out_of_range() {
+        let debouncer: Debouncer<(), default::ActiveLow> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let _ = unsafe { debouncer.init(()) }.unwrap();
+
+        for &integrator in [5, 64, 256].iter() {
+            let snapshot = snapshot::StateSnapshot {
+                level: Level::High,
+                integrator,
+            };
+            // It is always safe to restore a stack-scoped Debouncer.
+            assert_eq!(Err(snapshot::RestoreError::Range), unsafe {
+                debouncer.restore(snapshot)
+            });
+        }
+        assert_eq!(
+            Status {
+                level: Level::High,
+                integrator: 4,
+                initialized: true,
+            },
+            debouncer.status()
+        );
+    }
+
+    #[test]
+    fn poll_if_due() {
+        struct Timer(u8);
+        impl CountDown for Timer {
+            type Time = u8;
+            fn start<T: Into<u8>>(&mut self, _: T) {}
+            fn wait(&mut self) -> nb::Result<(), void::Void> {
+                if self.0 == 2 {
+                    self.0 = 0;
+                    Ok(())
+                } else {
+                    self.0 += 1;
+                    Err(nb::Error::WouldBlock)
+                }
+            }
+        }
+        impl Periodic for Timer {}
+
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [high.clone(), high];
+
+        let debouncer: Debouncer<_, default::ActiveHigh> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin::Mock::new(&expectations)) }.unwrap();
+        let mut timer = Timer(0);
+
+        for &due in [false, false, true, false, false, true, false].iter() {
+            // It is always safe to poll a stack-scoped Debouncer.
+            let result = unsafe { debouncer.poll_if_due(&mut timer) }.unwrap();
+            assert_eq!(due, result.is_some());
+        }
+        assert_eq!(2, debouncer.status().integrator);
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
     }
 
     #[test]
+    // The waker slot needed for async waits takes up space of its own,
+    // as do the context check, the observer and the statistics.
+    #[cfg(not(any(
+        feature = "async",
+        all(feature = "cortex-m", target_arch = "arm", debug_assertions),
+        feature = "observer",
+        feature = "stats"
+    )))]
     fn zero_sized_pin_type() {
         struct Pin;
         impl InputPin for Pin {
@@ -988,4 +3880,162 @@ mod test {
 
         assert_eq!(1, core::mem::size_of::<MyDebouncer>());
     }
+
+    #[test]
+    fn slow_door_sensor() {
+        // A door sensor sampled at 1Hz, which must hold for a minute.
+        type Door = default::SlowSignal<{ default::slow_max_count(60, 1) }, false>;
+
+        let debouncer: Debouncer<(), Door> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        // Open for 59 seconds, then closed for 10: no change.
+        for _ in 0..59 {
+            // It is always safe to poll a stack-scoped Debouncer.
+            assert_eq!(
+                Edge::NoChange,
+                unsafe { debouncer.poll_with(true) }.unwrap()
+            );
+        }
+        for _ in 0..10 {
+            // It is always safe to poll a stack-scoped Debouncer.
+            assert_eq!(
+                Edge::NoChange,
+                unsafe { debouncer.poll_with(false) }.unwrap()
+            );
+        }
+
+        // It takes another 11 seconds open to make up the difference.
+        for _ in 0..10 {
+            // It is always safe to poll a stack-scoped Debouncer.
+            assert_eq!(
+                Edge::NoChange,
+                unsafe { debouncer.poll_with(true) }.unwrap()
+            );
+        }
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(Edge::Rising, unsafe { debouncer.poll_with(true) }.unwrap());
+        assert_eq!(Level::High, debounced.state());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    fn slow_hour_window() {
+        // Polling at 10Hz, with an hour-long window.
+        type Sensor = default::SlowSignal<{ default::slow_max_count(3600, 10) }, true>;
+
+        let debouncer: Debouncer<(), Sensor> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        let mut polls = 0u32;
+        // It is always safe to poll a stack-scoped Debouncer.
+        while unsafe { debouncer.poll_with(false) }.unwrap() == Edge::NoChange {
+            polls += 1;
+        }
+        assert_eq!(35_999, polls);
+        assert_eq!(Level::Low, debounced.state());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    fn slow_largest_window_does_not_overflow() {
+        const MAX: u32 = (1 << 30) - 1;
+        type Widest = default::SlowSignal<{ default::slow_max_count(MAX, 1) }, false>;
+
+        let debouncer: Debouncer<(), Widest> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        // Skip ahead to just short of the top of the integrator.
+        // It is always safe to write to a stack-scoped Debouncer.
+        unsafe {
+            *debouncer.storage.get() |= (MAX - 1) << 2;
+        }
+
+        for &edge in [Edge::Rising, Edge::NoChange, Edge::NoChange].iter() {
+            // It is always safe to poll a stack-scoped Debouncer.
+            assert_eq!(edge, unsafe { debouncer.poll_with(true) }.unwrap());
+        }
+        // It is always safe to read a stack-scoped Debouncer.
+        let state = unsafe { *debouncer.storage.get() };
+        assert_eq!(u32::MAX, state);
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        assert_eq!(
+            Edge::NoChange,
+            unsafe { debouncer.poll_with(false) }.unwrap()
+        );
+        assert_eq!(Level::High, debounced.state());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "too long")]
+    fn slow_window_too_long() {
+        default::slow_max_count(1 << 20, 1 << 10);
+    }
+
+    #[test]
+    fn config_timing() {
+        const TIMING: Timing = Timing::of::<default::ActiveHigh>();
+        assert_eq!(4, TIMING.polls());
+        assert_eq!(40, TIMING.min_delay_ms(100));
+        assert_eq!(13, TIMING.min_delay_ms(300));
+        assert_eq!(13_333, TIMING.min_delay_us(300));
+
+        const KUHN: Timing = Timing::of::<default::OriginalKuhn>();
+        assert_eq!(300, KUHN.min_delay_ms(10));
+
+        type Door = default::SlowSignal<{ default::slow_max_count(600, 1) }, false>;
+        const DOOR: Timing = Timing::new(<Door as Debounce>::MAX_COUNT);
+        assert_eq!(600_000, DOOR.min_delay_ms(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "poll rate")]
+    fn config_timing_zero_rate() {
+        Timing::of::<default::ActiveHigh>().min_delay_ms(0);
+    }
+
+    #[test]
+    fn config_timing_per_edge() {
+        const ALARM: Timing = Timing::of::<default::FastAttack<2, 50, false>>();
+        assert_eq!(2, ALARM.polls());
+        assert_eq!(2, ALARM.rise().polls());
+        assert_eq!(50, ALARM.fall().polls());
+        assert_eq!(20, ALARM.min_delay_ms(100));
+        assert_eq!(500, ALARM.fall().min_delay_ms(100));
+
+        struct Schmitt;
+        impl Debounce for Schmitt {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 8;
+            const SET_THRESHOLD: Option<u8> = Some(6);
+            const CLEAR_THRESHOLD: Option<u8> = Some(3);
+            const INIT_HIGH: bool = false;
+        }
+        const SCHMITT: Timing = Timing::of::<Schmitt>();
+        assert_eq!(Timing::from_polls(6, 5), SCHMITT);
+
+        struct Vote;
+        impl Debounce for Vote {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 8;
+            const INIT_HIGH: bool = false;
+            const ALGORITHM: Algorithm = Algorithm::Majority {
+                window: 5,
+                votes: 3,
+            };
+        }
+        const VOTE: Timing = Timing::of::<Vote>();
+        assert_eq!(Timing::from_polls(3, 3), VOTE);
+    }
 }
@@ -0,0 +1,126 @@
+//! Dump the state of the debouncers from a panic handler.
+//!
+//! When a bug only shows up after some sequence of button presses, the
+//! state of the inputs at the time of the crash is the first thing to
+//! ask for.  Register the set of debouncers once at startup with
+//! [`register()`](register), and call [`panic_dump()`](panic_dump) from
+//! the panic handler to emit a [`StateSnapshot`](StateSnapshot) of each,
+//! through whatever channel the handler has to hand.
+//!
+//! ```
+//! use unflappable::{debouncer_uninit, default::{ActiveHigh, ActiveLow}, Debouncer};
+//! use unflappable::panic_dump::{self, DumpSet};
+//!
+//! static BUTTON: Debouncer<(), ActiveLow> = debouncer_uninit!();
+//! static SWITCH: Debouncer<(), ActiveHigh> = debouncer_uninit!();
+//! static INPUTS: &DumpSet = &[&BUTTON, &SWITCH];
+//! # fn write_to_uart(_: &[u8]) {}
+//!
+//! // At startup, before enabling interrupts:
+//! unsafe { panic_dump::register(INPUTS) };
+//!
+//! // In the panic handler:
+//! panic_dump::panic_dump(|index, snapshot| {
+//!     write_to_uart(&[index as u8]);
+//!     write_to_uart(&snapshot.to_bytes());
+//! });
+//! ```
+
+use core::cell::UnsafeCell;
+
+use crate::snapshot::{Snapshot, StateSnapshot};
+
+/// A set of debouncers to dump, in the order they are reported.
+pub type DumpSet = [&'static (dyn Snapshot + Sync)];
+
+struct Registry {
+    set: UnsafeCell<&'static DumpSet>,
+}
+
+// We demand particular mutex requirements as documented on the methods
+// marked as unsafe.  They are expected to be enforced statically by
+// the user, outside of the type system.
+unsafe impl Sync for Registry {}
+
+static REGISTRY: Registry = Registry {
+    set: UnsafeCell::new(&[]),
+};
+
+/// Register the set of debouncers for [`panic_dump()`](panic_dump),
+/// replacing any set registered before.
+///
+/// # Safety
+///
+/// For this call to be safe, you must ensure that it is not run
+/// concurrently with itself or with `panic_dump()`.  The usual way to
+/// do this is to register once at startup, before enabling interrupts.
+#[inline]
+pub unsafe fn register(set: &'static DumpSet) {
+    *REGISTRY.set.get() = set;
+}
+
+/// Call `emit` with the index and a snapshot of each registered
+/// debouncer.
+///
+/// This doesn't lock or allocate, so it may be called from a panic
+/// handler, even if the panic interrupted a poll.  In that case the
+/// snapshot of the debouncer being polled may be from before or after
+/// that poll.
+pub fn panic_dump<F: FnMut(usize, StateSnapshot)>(mut emit: F) {
+    // This is safe since we demand that registration doesn't race.
+    let set = unsafe { *REGISTRY.set.get() };
+
+    for (index, debouncer) in set.iter().enumerate() {
+        emit(index, debouncer.snapshot());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{debouncer_uninit, default, Debouncer, Level};
+
+    static LOW: Debouncer<(), default::ActiveLow> = debouncer_uninit!();
+    static HIGH: Debouncer<(), default::ActiveLow> = debouncer_uninit!();
+    static SET: &DumpSet = &[&LOW, &HIGH];
+
+    #[test]
+    fn dumps_registered_set() {
+        extern crate std;
+        use std::vec::Vec;
+
+        let mut dumps = Vec::new();
+        panic_dump(|index, snapshot| dumps.push((index, snapshot)));
+        assert!(dumps.is_empty());
+
+        // This test is the only one to register, and init the statics.
+        let _ = unsafe { LOW.init(()) }.unwrap();
+        let _ = unsafe { HIGH.init(()) }.unwrap();
+        for _ in 0..4 {
+            let _ = unsafe { LOW.poll_with(false) }.unwrap();
+        }
+        unsafe { register(SET) };
+
+        panic_dump(|index, snapshot| dumps.push((index, snapshot)));
+        assert_eq!(
+            [
+                (
+                    0,
+                    StateSnapshot {
+                        level: Level::Low,
+                        integrator: 0,
+                    }
+                ),
+                (
+                    1,
+                    StateSnapshot {
+                        level: Level::High,
+                        integrator: 4,
+                    }
+                ),
+            ],
+            dumps[..]
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Queue up debounced edges for the application.
+//!
+//! The application usually learns about a press by checking the
+//! [`Debounced`](crate::Debounced) pin from its main loop.  If the loop
+//! is slow, a short press can come and go between two checks and be
+//! missed entirely.  Instead, the poll routine can push each edge into
+//! a [`heapless::spsc::Queue`][0], with the interrupt service routine
+//! holding the producer half and the application draining the consumer
+//! half at its leisure.
+//!
+//! ```
+//! # struct PinType;
+//! # impl embedded_hal::digital::v2::InputPin for PinType {
+//! #     type Error = core::convert::Infallible;
+//! #     fn is_high(&self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
+//! use unflappable::queue::EdgeQueue;
+//!
+//! static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+//!
+//! # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+//! # let input_pin = PinType;
+//! let mut queue: EdgeQueue<8> = EdgeQueue::new();
+//! let (mut producer, mut consumer) = queue.split();
+//! let debounced_pin = unsafe { DEBOUNCER.init(input_pin) }.unwrap();
+//!
+//! // In the interrupt service routine, with the producer:
+//! unsafe { DEBOUNCER.poll_into(&mut producer) }?;
+//!
+//! // In the main loop, with the consumer:
+//! while let Some(edge) = consumer.dequeue() {
+//!     // handle the edge
+//! }
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! Draining everything at once is fine until someone mashes every
+//! button on the panel, and the main loop spends its whole iteration
+//! on input.  The [`Drain`](Drain) trait adds
+//! [`drain_events()`](Drain::drain_events) to the consumer, handling at
+//! most a given number of events per call and leaving the rest for the
+//! next iteration.  It works for any queued event, such as the
+//! [`BankEdges`](crate::bank::BankEdges) of a
+//! [`DebouncerBank`](crate::bank::DebouncerBank), too.
+//!
+//! ```
+//! use unflappable::{queue::{Drain, EdgeQueue}, Edge};
+//!
+//! let mut queue: EdgeQueue<8> = EdgeQueue::new();
+//! let (mut producer, mut consumer) = queue.split();
+//! # producer.enqueue(Edge::Falling).unwrap();
+//!
+//! // In the main loop, handle at most four edges per iteration:
+//! consumer.drain_events(4, |edge| {
+//!     // handle the edge
+//! });
+//! ```
+//!
+//! [0]: https://docs.rs/heapless/0.8/heapless/spsc/struct.Queue.html
+
+use heapless::spsc::{Consumer, Producer, Queue};
+
+use crate::{Debounce, Debouncer, Edge, PollError, Sampler};
+
+/// A queue of debounced edges.
+///
+/// As with any `heapless::spsc::Queue`, this holds up to `N - 1` edges.
+pub type EdgeQueue<const N: usize> = Queue<Edge, N>;
+
+impl<Pin: Sampler, Cfg: Debounce> Debouncer<Pin, Cfg> {
+    /// Poll the pin debouncer, queueing any resulting edge.
+    ///
+    /// Other than pushing the edge onto the queue, this is the same as
+    /// [`poll()`](#method.poll), and returns the edge too.  If the queue
+    /// is full, the new edge is dropped, so make sure there's room for
+    /// as many edges as can happen between drains.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`poll()`](#method.poll) apply.
+    #[inline]
+    pub unsafe fn poll_into<const N: usize>(
+        &self,
+        events: &mut Producer<'_, Edge, N>,
+    ) -> Result<Edge, PollError<Pin::Error>> {
+        let edge = self.poll()?;
+
+        if edge != Edge::NoChange {
+            let _ = events.enqueue(edge);
+        }
+
+        Ok(edge)
+    }
+}
+
+/// Handle queued events a bounded number at a time.
+pub trait Drain {
+    /// The type of event in the queue.
+    type Event;
+
+    /// Dequeue and handle at most `max` events, returning the number
+    /// handled.
+    fn drain_events<F: FnMut(Self::Event)>(&mut self, max: usize, handle: F) -> usize;
+}
+
+impl<T, const N: usize> Drain for Consumer<'_, T, N> {
+    type Event = T;
+
+    fn drain_events<F: FnMut(T)>(&mut self, max: usize, mut handle: F) -> usize {
+        let mut handled = 0;
+
+        while handled < max {
+            match self.dequeue() {
+                Some(event) => handle(event),
+                None => break,
+            }
+            handled += 1;
+        }
+
+        handled
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use embedded_hal_mock::pin;
+
+    use crate::debouncer_uninit;
+
+    #[test]
+    fn short_press_queued() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 1;
+            const INIT_HIGH: bool = true;
+        }
+
+        let low = pin::Transaction::get(pin::State::Low);
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [low.clone(), high.clone(), low, high];
+
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin::Mock::new(&expectations)) }.unwrap();
+
+        let mut queue: EdgeQueue<4> = EdgeQueue::new();
+        let (mut producer, mut consumer) = queue.split();
+
+        for _ in 0..expectations.len() {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_into(&mut producer) }.unwrap();
+        }
+
+        // Only three edges fit, so the last is dropped.
+        assert_eq!(Some(Edge::Falling), consumer.dequeue());
+        assert_eq!(Some(Edge::Rising), consumer.dequeue());
+        assert_eq!(Some(Edge::Falling), consumer.dequeue());
+        assert_eq!(None, consumer.dequeue());
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
+
+    #[test]
+    fn drain_bounded() {
+        let mut queue: EdgeQueue<8> = EdgeQueue::new();
+        let (mut producer, mut consumer) = queue.split();
+
+        for _ in 0..5 {
+            producer.enqueue(Edge::Falling).unwrap();
+        }
+
+        let mut handled = 0;
+        assert_eq!(3, consumer.drain_events(3, |_| handled += 1));
+        assert_eq!(3, handled);
+        assert_eq!(2, consumer.drain_events(3, |_| handled += 1));
+        assert_eq!(0, consumer.drain_events(3, |_| handled += 1));
+        assert_eq!(5, handled);
+    }
+}
@@ -0,0 +1,140 @@
+//! Remap input channels to logical functions at runtime.
+//!
+//! Products often let the user rearrange their buttons: swapping up and
+//! down, or moving a function to a more convenient key.  Rather than
+//! checking the settings in every handler, a [`Remap`](Remap) holds the
+//! logical function of each input channel, loaded from the stored user
+//! configuration, and translates channels into functions before the
+//! events reach the application.
+//!
+//! ```
+//! use unflappable::{bank::DebouncerBank, remap::Remap};
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+//! enum Function {
+//!     Up,
+//!     Down,
+//!     Select,
+//! }
+//!
+//! static BANK: DebouncerBank<3> = DebouncerBank::new(0b111);
+//! let mut remap = Remap::new([Function::Up, Function::Down, Function::Select]);
+//!
+//! // The user swapped the up and down buttons.
+//! remap.swap(0, 1);
+//!
+//! // In the poll routine:
+//! # let sample = 0b111;
+//! let edges = unsafe { BANK.update(sample) };
+//! remap.dispatch(edges, |function, edge| {
+//!     // handle the function
+//! });
+//! ```
+
+use crate::bank::BankEdges;
+use crate::Edge;
+
+/// A map from input channels to logical functions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remap<Id, const N: usize> {
+    map: [Id; N],
+}
+
+impl<Id: Copy, const N: usize> Remap<Id, N> {
+    /// Create a remap, where channel `n` has the function `map[n]`.
+    #[inline]
+    pub const fn new(map: [Id; N]) -> Self {
+        Remap { map }
+    }
+
+    /// The function of a channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such channel.
+    #[inline]
+    pub fn function(&self, channel: usize) -> Id {
+        self.map[channel]
+    }
+
+    /// Give a channel a new function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such channel.
+    #[inline]
+    pub fn set(&mut self, channel: usize, function: Id) {
+        self.map[channel] = function;
+    }
+
+    /// Swap the functions of two channels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either channel doesn't exist.
+    #[inline]
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.map.swap(a, b);
+    }
+
+    /// Replace the whole map, such as with one loaded from storage.
+    #[inline]
+    pub fn load(&mut self, map: [Id; N]) {
+        self.map = map;
+    }
+
+    /// The whole map, such as to save it to storage.
+    #[inline]
+    pub fn map(&self) -> &[Id; N] {
+        &self.map
+    }
+
+    /// Call `on_edge` with the function and edge of each channel in the
+    /// edges of a [`DebouncerBank`](crate::bank::DebouncerBank) poll.
+    ///
+    /// Channels beyond the map are ignored.
+    pub fn dispatch<F: FnMut(Id, Edge)>(&self, edges: BankEdges, mut on_edge: F) {
+        for (channel, &function) in self.map.iter().enumerate().take(32) {
+            let bit = 1 << channel;
+            if edges.rising & bit != 0 {
+                on_edge(function, Edge::Rising);
+            } else if edges.falling & bit != 0 {
+                on_edge(function, Edge::Falling);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn swapped_buttons() {
+        let mut remap = Remap::new(['u', 'd', 's']);
+        remap.swap(0, 1);
+        assert_eq!('d', remap.function(0));
+        assert_eq!(&['d', 'u', 's'], remap.map());
+
+        let mut seen = [None; 2];
+        let mut count = 0;
+        remap.dispatch(
+            BankEdges {
+                rising: 0b001,
+                falling: 0b100,
+            },
+            |function, edge| {
+                seen[count] = Some((function, edge));
+                count += 1;
+            },
+        );
+        assert_eq!(
+            [Some(('d', Edge::Rising)), Some(('s', Edge::Falling))],
+            seen
+        );
+
+        remap.load(['a', 'b', 'c']);
+        remap.set(2, 'z');
+        assert_eq!(&['a', 'b', 'z'], remap.map());
+    }
+}
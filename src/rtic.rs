@@ -0,0 +1,187 @@
+//! Keep the debouncer as an RTIC shared resource.
+//!
+//! The usual [`Debouncer`](crate::Debouncer) lives in a `static`, with
+//! `unsafe` methods whose exclusivity requirements you must uphold by
+//! hand.  In an RTIC application, the framework already does that job
+//! for shared resources: every access goes through `lock()`, which
+//! grants exclusive access.  A [`ResourceDebouncer`](ResourceDebouncer)
+//! is a debouncer to be kept as such a resource, with safe methods
+//! taking `&mut self`.
+//!
+//! Within a task, the [`DebouncerMutex`](DebouncerMutex) trait adds
+//! `poll()` and `state()` directly to the resource proxy, each of which
+//! takes the lock for you.
+//!
+//! ```ignore
+//! use unflappable::{default::ActiveLow, rtic::{DebouncerMutex, ResourceDebouncer}};
+//!
+//! #[rtic::app(device = pac, dispatchers = [EXTI0])]
+//! mod app {
+//!     use super::*;
+//!
+//!     #[shared]
+//!     struct Shared {
+//!         button: ResourceDebouncer<ButtonPin, ActiveLow>,
+//!     }
+//!
+//!     #[local]
+//!     struct Local {}
+//!
+//!     #[init]
+//!     fn init(cx: init::Context) -> (Shared, Local) {
+//!         let button = ResourceDebouncer::new(button_pin(cx.device));
+//!         (Shared { button }, Local {})
+//!     }
+//!
+//!     // Poll at 100Hz from a timer interrupt.
+//!     #[task(binds = TIM2, shared = [button], priority = 2)]
+//!     fn poll(mut cx: poll::Context) {
+//!         if let Ok(unflappable::Edge::Falling) = cx.shared.button.poll() {
+//!             // the button was just pressed
+//!         }
+//!     }
+//!
+//!     #[task(shared = [button], priority = 1)]
+//!     async fn ui(mut cx: ui::Context) {
+//!         let level = cx.shared.button.state();
+//!         // ...
+//!     }
+//! }
+//! ```
+
+use rtic_core::Mutex;
+
+use crate::{Debounce, DebounceExt, Debouncer, Edge, Level, PollError, Sampler};
+
+/// A debouncer to be kept as an RTIC shared resource.
+pub struct ResourceDebouncer<Pin, Cfg: Debounce> {
+    debouncer: Debouncer<Pin, Cfg>,
+}
+
+impl<Pin, Cfg: Debounce> ResourceDebouncer<Pin, Cfg> {
+    /// Create a debouncer for the given input pin.
+    ///
+    /// This is typically done in the RTIC `init` task.
+    pub fn new(pin: Pin) -> Self {
+        let debouncer = Debouncer::uninit(Cfg::zero());
+        debouncer.check_config();
+        // A new debouncer is never already initialized.
+        let _ = debouncer.init_linted(pin);
+
+        ResourceDebouncer { debouncer }
+    }
+
+    /// Poll the debouncer with a sample taken elsewhere.
+    ///
+    /// As with [`Debouncer::poll_with()`](crate::Debouncer#method.poll_with),
+    /// use `()` as the pin type in this case.
+    #[inline]
+    pub fn poll_with(&mut self, sample: bool) -> Edge {
+        self.debouncer.integrate(sample)
+    }
+
+    /// The current debounced level.
+    #[inline]
+    pub fn state(&self) -> Level {
+        self.debouncer.debounced().state()
+    }
+
+    /// Destroy the debouncer, returning the original input pin.
+    pub fn release(self) -> Pin {
+        match self.debouncer.deinit_linted(self.debouncer.debounced()) {
+            Ok(pin) => pin,
+            Err(_) => unreachable!("a ResourceDebouncer is always initialized"),
+        }
+    }
+}
+
+impl<Pin: Sampler, Cfg: Debounce> ResourceDebouncer<Pin, Cfg> {
+    /// Poll the debouncer.
+    ///
+    /// This should be done on a regular basis at roughly the frequency
+    /// used in the calculation of
+    /// [`MAX_COUNT`](crate::Debounce#associatedconstant.MAX_COUNT).
+    /// Returns the debounced edge, if any.
+    ///
+    /// Since the debouncer is always initialized, the only possible
+    /// error is from reading the pin.
+    #[inline]
+    pub fn poll(&mut self) -> Result<Edge, PollError<Pin::Error>> {
+        self.debouncer.poll_linted()
+    }
+}
+
+/// Poll and read a [`ResourceDebouncer`](ResourceDebouncer) through
+/// its RTIC resource proxy.
+pub trait DebouncerMutex {
+    /// The error type of the input pin.
+    type Error;
+
+    /// Lock the resource and poll the debouncer.
+    fn poll(&mut self) -> Result<Edge, PollError<Self::Error>>;
+
+    /// Lock the resource and read the debounced level.
+    fn state(&mut self) -> Level;
+}
+
+impl<M, Pin, Cfg> DebouncerMutex for M
+where
+    M: Mutex<T = ResourceDebouncer<Pin, Cfg>>,
+    Pin: Sampler,
+    Cfg: Debounce,
+{
+    type Error = Pin::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Result<Edge, PollError<Pin::Error>> {
+        self.lock(|debouncer| debouncer.poll())
+    }
+
+    #[inline]
+    fn state(&mut self) -> Level {
+        self.lock(|debouncer| debouncer.state())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use embedded_hal_mock::pin;
+    use rtic_core::Exclusive;
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 2;
+        const INIT_HIGH: bool = false;
+    }
+
+    #[test]
+    fn poll_through_lock() {
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [high.clone(), high];
+
+        let mut debouncer: ResourceDebouncer<_, Cfg> =
+            ResourceDebouncer::new(pin::Mock::new(&expectations));
+
+        {
+            let mut resource = Exclusive(&mut debouncer);
+            assert_eq!(Level::Low, resource.state());
+            assert_eq!(Edge::NoChange, resource.poll().unwrap());
+            assert_eq!(Edge::Rising, resource.poll().unwrap());
+            assert_eq!(Level::High, resource.state());
+        }
+
+        debouncer.release().done();
+    }
+
+    #[test]
+    fn external_samples() {
+        let mut debouncer: ResourceDebouncer<(), Cfg> = ResourceDebouncer::new(());
+
+        assert_eq!(Edge::NoChange, debouncer.poll_with(true));
+        assert_eq!(Edge::Rising, debouncer.poll_with(true));
+        assert_eq!(Level::High, debouncer.state());
+    }
+}
@@ -0,0 +1,208 @@
+//! Debounce with settings chosen at runtime.
+//!
+//! A [`Debouncer`](crate::Debouncer) takes its settings from the
+//! constants of a [`Debounce`](crate::Debounce) configuration, so every
+//! distinct debounce time is a distinct type.  That's the right
+//! trade-off for most firmware, but not when the settings are only
+//! known at runtime, say loaded from EEPROM or set over a service port.
+//!
+//! A [`RuntimeDebouncer`](RuntimeDebouncer) owns its pin and keeps its
+//! [`RuntimeConfig`](RuntimeConfig) alongside the integrator, so the
+//! settings can be chosen, and changed, on the fly.
+//!
+//! ```
+//! # struct PinType;
+//! # impl embedded_hal::digital::v2::InputPin for PinType {
+//! #     type Error = core::convert::Infallible;
+//! #     fn is_high(&self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! # let input_pin = PinType;
+//! # fn load_from_eeprom() -> u8 { 6 }
+//! use unflappable::runtime::{RuntimeConfig, RuntimeDebouncer};
+//!
+//! let config = RuntimeConfig {
+//!     max_count: load_from_eeprom(),
+//!     init_high: true,
+//! };
+//! let mut debouncer = RuntimeDebouncer::with_config(input_pin, config).unwrap();
+//!
+//! // In the poll routine:
+//! let edge = debouncer.poll()?;
+//! # Ok::<(), core::convert::Infallible>(())
+//! ```
+
+use crate::transition::{self, Params, STATE};
+use crate::{Edge, Level, Sampler};
+
+/// The settings of a [`RuntimeDebouncer`](RuntimeDebouncer).
+///
+/// These have the same meaning as the constants of the same names on
+/// [`Debounce`](crate::Debounce).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuntimeConfig {
+    /// The number of polls a clean transition takes.  Must be nonzero.
+    pub max_count: u8,
+
+    /// The initial state of the pin.
+    pub init_high: bool,
+}
+
+/// An error indicating that a [`RuntimeConfig`](RuntimeConfig) is
+/// invalid.
+#[derive(Debug)]
+pub struct ConfigError;
+
+/// A pin debouncer with runtime settings.
+///
+/// Unlike a [`Debouncer`](crate::Debouncer), this owns its pin, and is
+/// polled through a mutable reference.  Put it in whatever mutex your
+/// application uses to share it with the poll routine.
+#[derive(Debug)]
+pub struct RuntimeDebouncer<Pin> {
+    pin: Pin,
+    config: RuntimeConfig,
+    // The state word of the shared transition function, wide enough
+    // for any `max_count`.
+    state: u16,
+}
+
+impl<Pin> RuntimeDebouncer<Pin> {
+    /// Debounce the pin with the given settings.
+    ///
+    /// Returns an error if `max_count` is zero.
+    pub fn with_config(pin: Pin, config: RuntimeConfig) -> Result<Self, ConfigError> {
+        if config.max_count == 0 {
+            return Err(ConfigError);
+        }
+
+        Ok(RuntimeDebouncer {
+            pin,
+            config,
+            state: settled(config, config.init_high),
+        })
+    }
+
+    /// The current settings.
+    #[inline]
+    pub fn config(&self) -> RuntimeConfig {
+        self.config
+    }
+
+    /// Change the settings.
+    ///
+    /// The debounced state is kept, and settled at the new
+    /// `max_count`.  The new `init_high` has no effect until the pin is
+    /// debounced afresh.  Returns an error, leaving the settings as
+    /// they were, if `max_count` is zero.
+    pub fn set_config(&mut self, config: RuntimeConfig) -> Result<(), ConfigError> {
+        if config.max_count == 0 {
+            return Err(ConfigError);
+        }
+
+        self.config = config;
+        self.state = settled(config, self.is_high());
+        Ok(())
+    }
+
+    /// Poll with a sample taken elsewhere, returning the debounced
+    /// edge, if any.
+    pub fn poll_with(&mut self, sample: bool) -> Edge {
+        let max = u16::from(self.config.max_count) << 2;
+        let (state, edge) = transition::advance(&Params::integrator(max), self.state, sample);
+        self.state = state;
+        edge
+    }
+
+    /// The current debounced level.
+    #[inline]
+    pub fn state(&self) -> Level {
+        Level::from(self.is_high())
+    }
+
+    /// Destroy the debouncer, returning the original pin.
+    #[inline]
+    pub fn release(self) -> Pin {
+        self.pin
+    }
+
+    #[inline]
+    fn is_high(&self) -> bool {
+        self.state & u16::from(STATE) != 0
+    }
+}
+
+// The state word of a debouncer settled at the given level.
+#[inline]
+fn settled(config: RuntimeConfig, high: bool) -> u16 {
+    if high {
+        (u16::from(config.max_count) << 2) | u16::from(STATE)
+    } else {
+        0
+    }
+}
+
+impl<Pin: Sampler> RuntimeDebouncer<Pin> {
+    /// Poll the pin, returning the debounced edge, if any.
+    pub fn poll(&mut self) -> Result<Edge, Pin::Error> {
+        let sample = self.pin.sample()?;
+        Ok(self.poll_with(sample))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn change_config() {
+        let config = RuntimeConfig {
+            max_count: 2,
+            init_high: false,
+        };
+        let mut debouncer = RuntimeDebouncer::with_config((), config).unwrap();
+
+        assert_eq!(Edge::NoChange, debouncer.poll_with(true));
+        assert_eq!(Edge::Rising, debouncer.poll_with(true));
+        assert_eq!(Level::High, debouncer.state());
+
+        debouncer
+            .set_config(RuntimeConfig {
+                max_count: 4,
+                ..config
+            })
+            .unwrap();
+        assert_eq!(Level::High, debouncer.state());
+        for _ in 0..3 {
+            assert_eq!(Edge::NoChange, debouncer.poll_with(false));
+        }
+        assert_eq!(Edge::Falling, debouncer.poll_with(false));
+
+        assert!(debouncer
+            .set_config(RuntimeConfig {
+                max_count: 0,
+                ..config
+            })
+            .is_err());
+        assert_eq!(4, debouncer.config().max_count);
+    }
+
+    #[test]
+    fn full_range_max_count() {
+        let config = RuntimeConfig {
+            max_count: u8::MAX,
+            init_high: false,
+        };
+        let mut debouncer = RuntimeDebouncer::with_config((), config).unwrap();
+
+        for _ in 1..u8::MAX {
+            assert_eq!(Edge::NoChange, debouncer.poll_with(true));
+        }
+        assert_eq!(Edge::Rising, debouncer.poll_with(true));
+        assert_eq!(Level::High, debouncer.state());
+    }
+}
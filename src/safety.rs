@@ -0,0 +1,232 @@
+//! React to safety inputs without waiting for the debounce.
+//!
+//! Debouncing delays every transition by at least `MAX_COUNT` polls,
+//! which is exactly what you want for a user interface, and exactly
+//! what you don't want for an emergency stop.  Safety standards often
+//! require reacting to the very first sign of the stop being pressed.
+//!
+//! A [`SafetyInput`](SafetyInput) calls a trip callback from `poll()` as
+//! soon as a single raw sample is at the active level, bypassing the
+//! debounce.  The debounced state is still tracked as usual, and is the
+//! one to use for deciding when the stop has really been released, or
+//! for latching logic.  Once tripped, the callback isn't called again
+//! until the input has settled back at the inactive level, so that
+//! bouncing while the stop is pressed or released doesn't trip it over
+//! and over.
+//!
+//! ```
+//! # struct PinType;
+//! # impl embedded_hal::digital::v2::InputPin for PinType {
+//! #     type Error = core::convert::Infallible;
+//! #     fn is_high(&self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! # fn disable_motor_drivers() {}
+//! use unflappable::{default::ActiveLow, safety::SafetyInput, Level};
+//!
+//! // The stop button pulls the line low.
+//! static ESTOP: SafetyInput<PinType, ActiveLow> = SafetyInput::uninit(0, Level::Low);
+//!
+//! # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+//! # let input_pin = PinType;
+//! let debounced = unsafe { ESTOP.init(input_pin, disable_motor_drivers) }.unwrap();
+//!
+//! // In the interrupt service routine:
+//! unsafe { ESTOP.poll() }?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use core::cell::UnsafeCell;
+
+use crate::{
+    Debounce, Debounced, Debouncer, DeinitError, Edge, InitError, Level, PollError, Sampler,
+};
+
+/// A debounced input that trips on the first raw active sample.
+pub struct SafetyInput<Pin, Cfg: Debounce> {
+    debouncer: Debouncer<Pin, Cfg>,
+    active: Level,
+    on_trip: UnsafeCell<Option<fn()>>,
+    armed: UnsafeCell<bool>,
+}
+
+// We demand particular mutex requirements as documented on the methods
+// marked as unsafe.  They are expected to be enforced statically by
+// the user, outside of the type system.
+unsafe impl<Pin, Cfg: Debounce> Sync for SafetyInput<Pin, Cfg> {}
+
+impl<Pin, Cfg: Debounce> SafetyInput<Pin, Cfg> {
+    /// Create a new, uninitialized safety input, which is active at the
+    /// `active` level.
+    ///
+    /// As for [`Debouncer::uninit()`](crate::Debouncer#method.uninit),
+    /// you must pass in the zero value of the storage type.
+    ///
+    /// The debounce configuration should start out inactive, so with an
+    /// active-low input, use one with `INIT_HIGH` set.
+    #[inline]
+    pub const fn uninit(zero: Cfg::Storage, active: Level) -> Self {
+        SafetyInput {
+            debouncer: Debouncer::uninit(zero),
+            active,
+            on_trip: UnsafeCell::new(None),
+            armed: UnsafeCell::new(false),
+        }
+    }
+
+    /// Initialize the safety input for a given input pin, with the
+    /// callback to call when it trips.
+    ///
+    /// Returns an error if it has already been initialized.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as
+    /// [`Debouncer::init()`](crate::Debouncer#method.init) apply.
+    pub unsafe fn init(
+        &self,
+        pin: Pin,
+        on_trip: fn(),
+    ) -> Result<Debounced<'_, Cfg>, InitError<Pin>> {
+        let pin = self.debouncer.validate_config(pin)?;
+        let debounced = self.debouncer.init_linted(pin)?;
+
+        // This is safe because we demand from the caller that this
+        // method completes before any call to `poll()`.
+        *self.on_trip.get() = Some(on_trip);
+        *self.armed.get() = true;
+
+        Ok(debounced)
+    }
+
+    /// Destroy the debounced pin, returning the original input pin.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as
+    /// [`Debouncer::deinit()`](crate::Debouncer#method.deinit) apply.
+    pub unsafe fn deinit<'a>(&self, pin: Debounced<'a, Cfg>) -> Result<Pin, DeinitError<'a, Cfg>> {
+        let pin = self.debouncer.deinit_linted(pin)?;
+
+        // This is safe because we demand from the caller that this is
+        // an exclusive call.
+        *self.on_trip.get() = None;
+        *self.armed.get() = false;
+
+        Ok(pin)
+    }
+
+    #[inline(always)]
+    fn settled_inactive(&self) -> bool {
+        let inactive = self.active == Level::Low;
+        self.debouncer.state_flag() == inactive && self.debouncer.is_settled()
+    }
+}
+
+impl<Pin: Sampler, Cfg: Debounce> SafetyInput<Pin, Cfg> {
+    /// Poll the safety input.
+    ///
+    /// If the raw sample is at the active level, and the input hasn't
+    /// tripped since it last settled at the inactive level, the trip
+    /// callback is called before this returns.  Returns the debounced
+    /// edge, if any, just like
+    /// [`Debouncer::poll()`](crate::Debouncer#method.poll).
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as
+    /// [`Debouncer::poll()`](crate::Debouncer#method.poll) apply.
+    pub unsafe fn poll(&self) -> Result<Edge, PollError<Pin::Error>> {
+        let high = self.debouncer.sample_linted()?;
+
+        // This is safe because we demand from the caller that this is
+        // an exclusive call.
+        let armed = &mut *self.armed.get();
+
+        if Level::from(high) == self.active && *armed {
+            *armed = false;
+            if let Some(on_trip) = *self.on_trip.get() {
+                on_trip();
+            }
+        }
+
+        let edge = self.debouncer.integrate(high);
+
+        if self.settled_inactive() {
+            *armed = true;
+        }
+
+        Ok(edge)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    use embedded_hal_mock::pin;
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 2;
+        const INIT_HIGH: bool = true;
+    }
+
+    static TRIPS: AtomicU8 = AtomicU8::new(0);
+
+    fn trip() {
+        TRIPS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn trips_on_first_sample() {
+        let low = pin::Transaction::get(pin::State::Low);
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [
+            low.clone(),
+            low.clone(),
+            high.clone(),
+            low.clone(),
+            high.clone(),
+            high,
+            low,
+        ];
+
+        let estop: SafetyInput<_, Cfg> = SafetyInput::uninit(0, Level::Low);
+        // It is always safe to init a stack-scoped SafetyInput.
+        let debounced = unsafe { estop.init(pin::Mock::new(&expectations), trip) }.unwrap();
+
+        // The very first active sample trips, before the debounce.
+        // It is always safe to poll a stack-scoped SafetyInput.
+        assert_eq!(Edge::NoChange, unsafe { estop.poll() }.unwrap());
+        assert_eq!(1, TRIPS.load(Ordering::SeqCst));
+        assert_eq!(Level::High, debounced.state());
+
+        // It is always safe to poll a stack-scoped SafetyInput.
+        assert_eq!(Edge::Falling, unsafe { estop.poll() }.unwrap());
+
+        // Bouncing on release doesn't trip it again, and the release
+        // itself is debounced.
+        for &edge in [Edge::NoChange, Edge::NoChange, Edge::NoChange, Edge::Rising].iter() {
+            // It is always safe to poll a stack-scoped SafetyInput.
+            assert_eq!(edge, unsafe { estop.poll() }.unwrap());
+        }
+        assert_eq!(1, TRIPS.load(Ordering::SeqCst));
+
+        // Once settled, it's ready to trip again.
+        // It is always safe to poll a stack-scoped SafetyInput.
+        unsafe { estop.poll() }.unwrap();
+        assert_eq!(2, TRIPS.load(Ordering::SeqCst));
+
+        // It is always safe to deinit a stack-scoped SafetyInput.
+        unsafe { estop.deinit(debounced) }.unwrap().done();
+    }
+}
@@ -0,0 +1,277 @@
+//! Poll from a cooperative scheduler.
+//!
+//! Super-loop firmware without a timer interrupt often runs a simple
+//! cooperative scheduler, calling each task in turn with the current
+//! tick count.  The [`PollTask`](PollTask) fits into such a task list:
+//! call its [`run()`](PollTask::run) as often as you like, and it polls
+//! the [`Debouncer`](crate::Debouncer) whenever a poll period has gone
+//! by.  The tick counter is allowed to wrap around, see the
+//! [`time`](crate::time) module.
+//!
+//! The timing is handled by a [`Periodic`](Periodic), which can be used
+//! on its own to drive other periodic work, like a
+//! [`DebouncerBank`](crate::bank::DebouncerBank).
+//!
+//! With hundreds of inputs behind I/O expanders, reading every one of
+//! them on every tick may be more bus traffic than a tick allows.  A
+//! [`TimeSlicer`](TimeSlicer) splits the inputs into slices polled
+//! round-robin, one slice per tick.  Each input is then only sampled
+//! every few ticks, so its `MAX_COUNT` should be scaled down to match,
+//! with [`sliced_max_count()`](sliced_max_count).
+//!
+//! ```
+//! use unflappable::{Debounce, scheduler::{sliced_max_count, TimeSlicer}};
+//!
+//! // 200 inputs, 50 of them read each tick.
+//! const SLICES: usize = TimeSlicer::new(200, 50).slices();
+//!
+//! struct Sliced;
+//! impl Debounce for Sliced {
+//!     type Storage = u8;
+//!     // The same debounce time as a MAX_COUNT of 20 polled every tick.
+//!     const MAX_COUNT: u8 = sliced_max_count(20, SLICES) as u8;
+//!     const INIT_HIGH: bool = true;
+//! }
+//!
+//! let mut slicer = TimeSlicer::new(200, 50);
+//! assert_eq!(0..50, slicer.next_slice());
+//! assert_eq!(50..100, slicer.next_slice());
+//! ```
+
+use core::ops::Range;
+
+use crate::time::{deadline, deadline_passed};
+use crate::{Debounce, Debouncer, Edge, PollError, Sampler};
+
+/// Decides when periodic work is due, given a wrapping tick count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Periodic {
+    period: u32,
+    next: Option<u32>,
+}
+
+impl Periodic {
+    /// Do something every `period` ticks.
+    ///
+    /// The first call to [`due()`](#method.due) is always due.
+    #[inline]
+    pub const fn new(period: u32) -> Self {
+        Periodic { period, next: None }
+    }
+
+    /// The period, in ticks.
+    #[inline]
+    pub fn period(&self) -> u32 {
+        self.period
+    }
+
+    /// Is the work due at time `now`?
+    ///
+    /// Returns `true` at most once per period.  If more than a whole
+    /// period was missed, the missed work is skipped rather than done
+    /// in a burst.
+    pub fn due(&mut self, now: u32) -> bool {
+        let next = match self.next {
+            Some(next) if !deadline_passed(now, next) => return false,
+            Some(next) => deadline(next, self.period),
+            None => deadline(now, self.period),
+        };
+
+        self.next = Some(if deadline_passed(now, next) {
+            deadline(now, self.period)
+        } else {
+            next
+        });
+        true
+    }
+}
+
+/// A cooperative task that polls a debouncer.
+pub struct PollTask<'a, Pin, Cfg: Debounce> {
+    debouncer: &'a Debouncer<Pin, Cfg>,
+    periodic: Periodic,
+}
+
+impl<'a, Pin: Sampler, Cfg: Debounce> PollTask<'a, Pin, Cfg> {
+    /// Create a task that polls `debouncer` every `period` ticks.
+    ///
+    /// # Safety
+    ///
+    /// For this call to be safe, you must ensure that the task is the
+    /// only thing that calls any unsafe method of the debouncer for as
+    /// long as the task lives.  This is the usual situation in a super
+    /// loop: initialize the debouncer, create the task, and only then
+    /// start the loop.
+    #[inline]
+    pub unsafe fn new(debouncer: &'a Debouncer<Pin, Cfg>, period: u32) -> Self {
+        PollTask {
+            debouncer,
+            periodic: Periodic::new(period),
+        }
+    }
+
+    /// Run the task at time `now`.
+    ///
+    /// Returns `None` if it isn't time to poll yet, or else the result
+    /// of the poll.
+    pub fn run(&mut self, now: u32) -> Option<Result<Edge, PollError<Pin::Error>>> {
+        if self.periodic.due(now) {
+            Some(self.debouncer.poll_linted())
+        } else {
+            None
+        }
+    }
+}
+
+/// Polls a large set of inputs a slice at a time, round-robin.
+///
+/// The inputs are identified by index, and each tick polls the next
+/// `stride` of them, wrapping back to the start once all have been
+/// polled.  The last slice may be short.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeSlicer {
+    channels: usize,
+    stride: usize,
+    next: usize,
+}
+
+impl TimeSlicer {
+    /// Poll `channels` inputs, `stride` of them on each tick.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stride` is zero.
+    #[inline]
+    pub const fn new(channels: usize, stride: usize) -> Self {
+        assert!(stride != 0, "the stride of a TimeSlicer cannot be zero");
+
+        TimeSlicer {
+            channels,
+            stride,
+            next: 0,
+        }
+    }
+
+    /// The number of slices, which is the number of ticks between
+    /// successive polls of each input.
+    #[inline]
+    pub const fn slices(&self) -> usize {
+        let slices = self.channels / self.stride;
+        if slices * self.stride < self.channels {
+            slices + 1
+        } else {
+            slices
+        }
+    }
+
+    /// The inputs to poll on this tick.
+    pub fn next_slice(&mut self) -> Range<usize> {
+        let start = self.next;
+        let end = core::cmp::min(start + self.stride, self.channels);
+
+        self.next = if end >= self.channels { 0 } else { end };
+        start..end
+    }
+}
+
+/// The `MAX_COUNT` giving the same debounce time as `max_count` when
+/// each input is only polled once every `slices` ticks.
+///
+/// Rounds up, so the debounce time is never shorter, and is never less
+/// than one.
+pub const fn sliced_max_count(max_count: u32, slices: usize) -> u32 {
+    let slices = if slices == 0 { 1 } else { slices as u32 };
+    let count = max_count / slices;
+    if count == 0 || count * slices < max_count {
+        count + 1
+    } else {
+        count
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    use embedded_hal_mock::pin;
+
+    use crate::debouncer_uninit;
+
+    #[test]
+    fn periodic() {
+        let mut periodic = Periodic::new(10);
+
+        assert_eq!(true, periodic.due(5));
+        assert_eq!(false, periodic.due(6));
+        assert_eq!(false, periodic.due(14));
+        assert_eq!(true, periodic.due(15));
+        assert_eq!(false, periodic.due(15));
+
+        // Running late doesn't drift the schedule...
+        assert_eq!(true, periodic.due(27));
+        assert_eq!(true, periodic.due(35));
+
+        // ...but falling a whole period behind skips ahead.
+        assert_eq!(true, periodic.due(100));
+        assert_eq!(false, periodic.due(105));
+        assert_eq!(true, periodic.due(110));
+    }
+
+    #[test]
+    fn periodic_wraps_around() {
+        let mut periodic = Periodic::new(10);
+
+        assert_eq!(true, periodic.due(u32::MAX - 3));
+        assert_eq!(false, periodic.due(u32::MAX));
+        assert_eq!(false, periodic.due(5));
+        assert_eq!(true, periodic.due(6));
+        assert_eq!(false, periodic.due(15));
+        assert_eq!(true, periodic.due(16));
+    }
+
+    #[test]
+    fn time_slicer() {
+        let mut slicer = TimeSlicer::new(10, 4);
+        assert_eq!(3, slicer.slices());
+
+        assert_eq!(0..4, slicer.next_slice());
+        assert_eq!(4..8, slicer.next_slice());
+        assert_eq!(8..10, slicer.next_slice());
+        assert_eq!(0..4, slicer.next_slice());
+
+        assert_eq!(3, sliced_max_count(8, 3));
+        assert_eq!(2, sliced_max_count(8, 4));
+        assert_eq!(1, sliced_max_count(2, 8));
+    }
+
+    #[test]
+    fn poll_task() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 2;
+            const INIT_HIGH: bool = false;
+        }
+
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [high.clone(), high];
+
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin::Mock::new(&expectations)) }.unwrap();
+
+        {
+            // The task is the only thing polling the debouncer.
+            let mut task = unsafe { PollTask::new(&debouncer, 4) };
+
+            assert_eq!(Some(Edge::NoChange), task.run(0).map(Result::unwrap));
+            assert_eq!(None, task.run(1).map(Result::unwrap));
+            assert_eq!(None, task.run(3).map(Result::unwrap));
+            assert_eq!(Some(Edge::Rising), task.run(4).map(Result::unwrap));
+        }
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
+}
@@ -0,0 +1,155 @@
+//! A safe debouncer guarded by critical sections.
+//!
+//! The methods of [`Debouncer`](crate::Debouncer) that touch its shared
+//! state are `unsafe`, leaving it to you to make sure they never run
+//! concurrently.  That costs nothing, but it can be awkward to uphold
+//! in a larger project.  The [`SharedDebouncer`](SharedDebouncer) runs
+//! each of them inside a [`critical_section`][0] instead, so they can
+//! be called safely from anywhere, at the cost of briefly masking
+//! interrupts.
+//!
+//! Your platform must provide a critical section implementation; see
+//! the `critical-section` crate for details.
+//!
+//! [0]: https://docs.rs/critical-section
+
+use crate::{Debounce, Debounced, Debouncer, DeinitError, Edge, InitError, PollError, Sampler};
+
+/// A pin debouncer that is safe to share.
+///
+/// Create one in static storage with the macro
+/// [`shared_debouncer_uninit!`](crate::shared_debouncer_uninit).
+///
+/// ```
+/// # struct PinType;
+/// # impl embedded_hal::digital::v2::InputPin for PinType {
+/// #     type Error = core::convert::Infallible;
+/// #     fn is_high(&self) -> Result<bool, Self::Error> {
+/// #         Ok(true)
+/// #     }
+/// #     fn is_low(&self) -> Result<bool, Self::Error> {
+/// #         Ok(false)
+/// #     }
+/// # }
+/// use unflappable::{shared_debouncer_uninit, default::ActiveLow};
+/// use unflappable::shared::SharedDebouncer;
+/// static DEBOUNCER: SharedDebouncer<PinType, ActiveLow> = shared_debouncer_uninit!();
+///
+/// # fn main() -> Result<(), unflappable::InitError<PinType>> {
+/// #     let input_pin = PinType;
+/// let debounced_pin = DEBOUNCER.init(input_pin)?;
+/// #     Ok(())
+/// # }
+/// ```
+pub struct SharedDebouncer<Pin, Cfg: Debounce> {
+    inner: Debouncer<Pin, Cfg>,
+}
+
+impl<Pin: Sampler, Cfg: Debounce> SharedDebouncer<Pin, Cfg> {
+    /// Create a new, uninitialized shared pin debouncer.
+    ///
+    /// For technical reasons, you must pass in the zero value of the
+    /// storage type [`Debounce::Storage`](crate::Debounce#associatedtype.Storage),
+    /// so prefer the macro
+    /// [`shared_debouncer_uninit!`](crate::shared_debouncer_uninit).
+    #[inline]
+    pub const fn uninit(zero: Cfg::Storage) -> Self {
+        SharedDebouncer {
+            inner: Debouncer::uninit(zero),
+        }
+    }
+
+    /// Initialize the pin debouncer for a given input pin.
+    ///
+    /// Returns an error if the debouncer has already be initialized.
+    /// See [`Debouncer::init()`](crate::Debouncer#method.init).
+    #[inline]
+    pub fn init(&self, pin: Pin) -> Result<Debounced<'_, Cfg>, InitError<Pin>> {
+        let pin = self.inner.validate_config(pin)?;
+        critical_section::with(|_| self.inner.init_linted(pin))
+    }
+
+    /// Poll the pin debouncer.
+    ///
+    /// See [`Debouncer::poll()`](crate::Debouncer#method.poll).
+    #[inline]
+    pub fn poll(&self) -> Result<Edge, PollError<Pin::Error>> {
+        critical_section::with(|_| self.inner.poll_linted())
+    }
+
+    /// Destroy the debounced pin, returning the original input pin.
+    ///
+    /// See [`Debouncer::deinit()`](crate::Debouncer#method.deinit).
+    #[inline]
+    pub fn deinit<'a>(&self, pin: Debounced<'a, Cfg>) -> Result<Pin, DeinitError<'a, Cfg>> {
+        critical_section::with(|_| self.inner.deinit_linted(pin))
+    }
+
+    /// Has this debouncer been initialized?
+    ///
+    /// See [`Debouncer::is_initialized()`](crate::Debouncer#method.is_initialized).
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.inner.is_initialized()
+    }
+}
+
+/// Create a new uninitialized [`SharedDebouncer`](crate::shared::SharedDebouncer).
+///
+/// This is the preferred way to initialize a static `SharedDebouncer`.
+///
+/// # Examples
+///
+/// ```
+/// # struct PinType;
+/// # impl embedded_hal::digital::v2::InputPin for PinType {
+/// #     type Error = core::convert::Infallible;
+/// #     fn is_high(&self) -> Result<bool, Self::Error> {
+/// #         Ok(true)
+/// #     }
+/// #     fn is_low(&self) -> Result<bool, Self::Error> {
+/// #         Ok(false)
+/// #     }
+/// # }
+/// use unflappable::{shared_debouncer_uninit, default::ActiveLow};
+/// use unflappable::shared::SharedDebouncer;
+/// static PIN_DEBOUNCER: SharedDebouncer<PinType, ActiveLow> = shared_debouncer_uninit!();
+/// ```
+#[macro_export]
+macro_rules! shared_debouncer_uninit {
+    () => {
+        $crate::shared::SharedDebouncer::uninit(0)
+    };
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    use embedded_hal::digital::v2::InputPin;
+    use embedded_hal_mock::pin;
+
+    #[test]
+    fn safe_round_trip() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 2;
+            const INIT_HIGH: bool = false;
+        }
+
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [high.clone(), high];
+
+        let debouncer: SharedDebouncer<_, Cfg> = shared_debouncer_uninit!();
+        assert!(matches!(debouncer.poll(), Err(PollError::Init)));
+
+        let debounced = debouncer.init(pin::Mock::new(&expectations)).unwrap();
+        assert_eq!(Edge::NoChange, debouncer.poll().unwrap());
+        assert_eq!(Edge::Rising, debouncer.poll().unwrap());
+        assert_eq!(true, debounced.is_high().unwrap());
+
+        debouncer.deinit(debounced).unwrap().done();
+    }
+}
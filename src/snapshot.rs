@@ -0,0 +1,226 @@
+//! Save and restore the state of a debouncer.
+//!
+//! A [`StateSnapshot`](StateSnapshot) holds what a
+//! [`Debouncer`](crate::Debouncer) knows about its input: the debounced
+//! level and the integrator.  Taken with
+//! [`snapshot()`](crate::Debouncer#method.snapshot) before a reset,
+//! such as for an OTA update, and put back with
+//! [`restore()`](crate::Debouncer#method.restore) afterwards, it keeps a
+//! held button from being seen as pressed a second time.
+//!
+//! # Wire format
+//!
+//! The encoding is [`LEN`](StateSnapshot::LEN) bytes, and is stable: a
+//! snapshot written by one version of this crate decodes in any later
+//! version, which will bump [`VERSION`](StateSnapshot::VERSION) rather
+//! than change the meaning of a layout already in the field.
+//!
+//! | Offset | Length | Contents                                     |
+//! |--------|--------|----------------------------------------------|
+//! | 0      | 1      | The format version, currently 1              |
+//! | 1      | 1      | Flags: bit 0 is the level, the rest are zero |
+//! | 2      | 8      | The integrator, little-endian                |
+//!
+//! ```
+//! use unflappable::{snapshot::StateSnapshot, Level};
+//!
+//! let snapshot = StateSnapshot { level: Level::High, integrator: 3 };
+//! let bytes = snapshot.to_bytes();
+//! assert_eq!([1, 1, 3, 0, 0, 0, 0, 0, 0, 0], bytes);
+//! assert_eq!(Ok(snapshot), StateSnapshot::from_bytes(&bytes));
+//! ```
+
+use crate::transition::Word;
+use crate::{Debounce, Debouncer, Level};
+
+/// The state of a debouncer, as of one poll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StateSnapshot {
+    /// The debounced level.
+    pub level: Level,
+
+    /// The integrator, as in [`Status`](crate::Status).
+    pub integrator: u64,
+}
+
+/// Something that can take a [`StateSnapshot`](StateSnapshot) of its
+/// state.
+///
+/// Implemented by [`Debouncer`](crate::Debouncer), so that debouncers of
+/// different configurations can be collected together, such as for a
+/// [panic dump](crate::panic_dump).
+pub trait Snapshot {
+    /// A snapshot of the current state.
+    fn snapshot(&self) -> StateSnapshot;
+}
+
+impl<Pin, Cfg: Debounce> Snapshot for Debouncer<Pin, Cfg> {
+    #[inline]
+    fn snapshot(&self) -> StateSnapshot {
+        Debouncer::snapshot(self)
+    }
+}
+
+/// An error decoding a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecodeError {
+    /// The snapshot was not [`LEN`](StateSnapshot::LEN) bytes.
+    Length,
+
+    /// The snapshot was written in an unknown version of the format.
+    Version(u8),
+
+    /// The snapshot had reserved flags set.
+    Flags,
+}
+
+/// An error restoring a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RestoreError {
+    /// The `Debouncer` was not initialized.
+    Init,
+
+    /// The integrator is beyond the rails of the configuration.
+    Range,
+}
+
+impl StateSnapshot {
+    /// The version of the wire format written by this crate.
+    pub const VERSION: u8 = 1;
+
+    /// The length of an encoded snapshot.
+    pub const LEN: usize = 10;
+
+    /// Encode the snapshot.
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut bytes = [0; Self::LEN];
+        bytes[0] = Self::VERSION;
+        bytes[1] = self.level.is_high() as u8;
+        bytes[2..].copy_from_slice(&self.integrator.to_le_bytes());
+        bytes
+    }
+
+    /// Decode a snapshot.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() != Self::LEN {
+            return Err(DecodeError::Length);
+        }
+        if bytes[0] != Self::VERSION {
+            return Err(DecodeError::Version(bytes[0]));
+        }
+        if bytes[1] & !1 != 0 {
+            return Err(DecodeError::Flags);
+        }
+
+        let mut integrator = [0; 8];
+        integrator.copy_from_slice(&bytes[2..]);
+
+        Ok(StateSnapshot {
+            level: Level::from(bytes[1] & 1 != 0),
+            integrator: u64::from_le_bytes(integrator),
+        })
+    }
+}
+
+// The storage type needn't convert to or from a `u64`, so go a bit at a
+// time.
+pub(crate) fn widen<S: Word>(value: S) -> u64 {
+    let one = S::from(1);
+    let bits = core::mem::size_of::<S>().min(8) * 8;
+    (0..bits as u8)
+        .filter(|&bit| (value >> bit) & one == one)
+        .fold(0, |wide, bit| wide | 1 << bit)
+}
+
+pub(crate) fn narrow<S: Word>(wide: u64) -> Option<S> {
+    let bits = core::mem::size_of::<S>().min(8) * 8;
+    if bits < 64 && wide >> bits != 0 {
+        return None;
+    }
+    Some(
+        (0..bits as u8)
+            .filter(|&bit| wide & 1 << bit != 0)
+            .fold(S::from(0), |value, bit| value | S::from(1) << bit),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for &snapshot in [
+            StateSnapshot {
+                level: Level::Low,
+                integrator: 0,
+            },
+            StateSnapshot {
+                level: Level::High,
+                integrator: u64::MAX,
+            },
+            StateSnapshot {
+                level: Level::Low,
+                integrator: 0x0123_4567_89ab_cdef,
+            },
+        ]
+        .iter()
+        {
+            assert_eq!(
+                Ok(snapshot),
+                StateSnapshot::from_bytes(&snapshot.to_bytes())
+            );
+        }
+    }
+
+    // The layout is a promise to firmware in the field: this must never
+    // change for version 1.
+    #[test]
+    fn version_1_layout() {
+        let bytes = [1, 0, 0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0x23, 0x01];
+        assert_eq!(
+            Ok(StateSnapshot {
+                level: Level::Low,
+                integrator: 0x0123_4567_89ab_cdef,
+            }),
+            StateSnapshot::from_bytes(&bytes)
+        );
+    }
+
+    #[test]
+    fn bad_snapshots() {
+        let bytes = StateSnapshot {
+            level: Level::High,
+            integrator: 2,
+        }
+        .to_bytes();
+
+        assert_eq!(
+            Err(DecodeError::Length),
+            StateSnapshot::from_bytes(&bytes[..9])
+        );
+
+        let mut future = bytes;
+        future[0] = 2;
+        assert_eq!(
+            Err(DecodeError::Version(2)),
+            StateSnapshot::from_bytes(&future)
+        );
+
+        let mut flagged = bytes;
+        flagged[1] |= 0x80;
+        assert_eq!(Err(DecodeError::Flags), StateSnapshot::from_bytes(&flagged));
+    }
+
+    #[test]
+    fn widen_narrow() {
+        assert_eq!(0xa5, widen(0xa5u8));
+        assert_eq!(u64::MAX, widen(u64::MAX));
+        assert_eq!(Some(0xa5u8), narrow(0xa5));
+        assert_eq!(None::<u8>, narrow(0x100));
+        assert_eq!(Some(u64::MAX), narrow(u64::MAX));
+    }
+}
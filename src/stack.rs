@@ -0,0 +1,153 @@
+//! Debounce from the main loop, without `unsafe`.
+//!
+//! The usual [`Debouncer`](crate::Debouncer) lives in a `static`, to be
+//! shared between an interrupt service routine and the application,
+//! with `unsafe` methods whose exclusivity requirements you must uphold
+//! by hand.  If the pin is only ever polled and read from the main
+//! loop, none of that is needed.  A [`StackDebouncer`](StackDebouncer)
+//! owns its pin, and is polled and read with ordinary safe methods,
+//! borrowing checked as usual.
+//!
+//! ```
+//! # struct PinType;
+//! # impl embedded_hal::digital::v2::InputPin for PinType {
+//! #     type Error = core::convert::Infallible;
+//! #     fn is_high(&self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+//! # let input_pin = PinType;
+//! # fn handle_press() {}
+//! use unflappable::{default::ActiveLow, stack::StackDebouncer};
+//!
+//! let mut button: StackDebouncer<_, ActiveLow> = StackDebouncer::new(input_pin);
+//!
+//! // In the main loop, at roughly the poll rate:
+//! button.poll()?;
+//! if button.is_low() {
+//!     handle_press();
+//! }
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::{Debounce, DebounceExt, Debouncer, Edge, Level, PollError, Sampler};
+
+/// A debouncer that owns its pin, with safe `&mut self` methods.
+pub struct StackDebouncer<Pin, Cfg: Debounce> {
+    debouncer: Debouncer<Pin, Cfg>,
+}
+
+impl<Pin, Cfg: Debounce> StackDebouncer<Pin, Cfg> {
+    /// Create a debouncer for the given input pin.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the [`Debounce`](crate::Debounce) configuration is
+    /// invalid.
+    pub fn new(pin: Pin) -> Self {
+        let debouncer = Debouncer::uninit(Cfg::zero());
+        debouncer.check_config();
+        // A new debouncer is never already initialized.
+        let _ = debouncer.init_linted(pin);
+
+        StackDebouncer { debouncer }
+    }
+
+    /// Poll the debouncer with a sample taken elsewhere.
+    ///
+    /// As with [`Debouncer::poll_with()`](crate::Debouncer#method.poll_with),
+    /// use `()` as the pin type in this case.
+    #[inline]
+    pub fn poll_with(&mut self, sample: bool) -> Edge {
+        self.debouncer.integrate(sample)
+    }
+
+    /// The current debounced level.
+    #[inline]
+    pub fn state(&self) -> Level {
+        self.debouncer.debounced().state()
+    }
+
+    /// Is the debounced level high?
+    #[inline]
+    pub fn is_high(&self) -> bool {
+        self.state() == Level::High
+    }
+
+    /// Is the debounced level low?
+    #[inline]
+    pub fn is_low(&self) -> bool {
+        self.state() == Level::Low
+    }
+
+    /// Destroy the debouncer, returning the original input pin.
+    pub fn release(self) -> Pin {
+        match self.debouncer.deinit_linted(self.debouncer.debounced()) {
+            Ok(pin) => pin,
+            Err(_) => unreachable!("a StackDebouncer is always initialized"),
+        }
+    }
+}
+
+impl<Pin: Sampler, Cfg: Debounce> StackDebouncer<Pin, Cfg> {
+    /// Poll the debouncer.
+    ///
+    /// This should be done on a regular basis at roughly the frequency
+    /// used in the calculation of
+    /// [`MAX_COUNT`](crate::Debounce#associatedconstant.MAX_COUNT).
+    /// Returns the debounced edge, if any.
+    ///
+    /// Since the debouncer is always initialized, the only possible
+    /// error is from reading the pin.
+    #[inline]
+    pub fn poll(&mut self) -> Result<Edge, PollError<Pin::Error>> {
+        self.debouncer.poll_linted()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    use embedded_hal_mock::pin;
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 2;
+        const INIT_HIGH: bool = true;
+    }
+
+    #[test]
+    fn owned_pin() {
+        let low = pin::Transaction::get(pin::State::Low);
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [low.clone(), high, low.clone(), low];
+
+        let mut button: StackDebouncer<_, Cfg> = StackDebouncer::new(pin::Mock::new(&expectations));
+        assert_eq!(true, button.is_high());
+
+        for &edge in [
+            Edge::NoChange,
+            Edge::NoChange,
+            Edge::NoChange,
+            Edge::Falling,
+        ]
+        .iter()
+        {
+            assert_eq!(edge, button.poll().unwrap());
+        }
+        assert_eq!(true, button.is_low());
+
+        assert_eq!(Edge::NoChange, button.poll_with(true));
+        assert_eq!(Level::Low, button.state());
+
+        button.release().done();
+    }
+}
@@ -0,0 +1,156 @@
+//! A stream of debounced edges.
+//!
+//! Async applications often want to handle each transition as it
+//! arrives, in a loop like `while let Some(edge) = edges.next().await`.
+//! The [`Edges`](Edges) stream, created by
+//! [`Debounced::edges()`](crate::Debounced#method.edges), does just
+//! that.  Like the `Wait` implementation, it registers its waker with
+//! the `Debouncer`, which wakes it from `poll()` on each transition.
+//!
+//! The stream compares the debounced level with the one it last saw,
+//! so the task must get a chance to run between transitions, or the
+//! two edges of a short pulse may be missed.  Since a transition takes
+//! at least `MAX_COUNT` polls, that is rarely a concern.  Only one task
+//! can wait on a debounced pin at a time, whether through the stream or
+//! through `Wait`.
+
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures_core::Stream;
+
+use crate::{Debounce, Debounced, Edge, Level};
+
+/// A never-ending stream of debounced edges.
+pub struct Edges<'a, 'state, Cfg: Debounce> {
+    pin: &'a Debounced<'state, Cfg>,
+    last: Level,
+}
+
+impl<'state, Cfg: Debounce> Debounced<'state, Cfg> {
+    /// A stream of the edges of this debounced pin, starting from its
+    /// current level.
+    ///
+    /// ```
+    /// # struct PinType;
+    /// # impl embedded_hal::digital::v2::InputPin for PinType {
+    /// #     type Error = core::convert::Infallible;
+    /// #     fn is_high(&self) -> Result<bool, Self::Error> { Ok(true) }
+    /// #     fn is_low(&self) -> Result<bool, Self::Error> { Ok(false) }
+    /// # }
+    /// # use futures_core::Stream;
+    /// # async fn next<S: Stream + Unpin>(s: &mut S) -> Option<S::Item> {
+    /// #     core::future::poll_fn(|cx| core::pin::Pin::new(&mut *s).poll_next(cx)).await
+    /// # }
+    /// use unflappable::{debouncer_uninit, Debouncer, Edge, default::ActiveLow};
+    /// static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+    ///
+    /// async fn button_task() {
+    ///     # let input_pin = PinType;
+    ///     let debounced = unsafe { DEBOUNCER.init(input_pin) }.unwrap();
+    ///     let mut edges = debounced.edges();
+    ///
+    ///     while let Some(edge) = next(&mut edges).await {
+    ///         if edge == Edge::Falling {
+    ///             // the button was pressed
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[inline]
+    pub fn edges(&self) -> Edges<'_, 'state, Cfg> {
+        Edges {
+            pin: self,
+            last: self.state(),
+        }
+    }
+}
+
+impl<'a, 'state, Cfg: Debounce> Stream for Edges<'a, 'state, Cfg> {
+    type Item = Edge;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Edge>> {
+        // Register before checking, so that a transition completing in
+        // between still wakes us.
+        self.pin.waker.register(cx.waker());
+
+        let level = self.pin.state();
+        if level == self.last {
+            return Poll::Pending;
+        }
+
+        self.last = level;
+        Poll::Ready(Some(match level {
+            Level::High => Edge::Rising,
+            Level::Low => Edge::Falling,
+        }))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    extern crate std;
+
+    use super::*;
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    use crate::{debouncer_uninit, Debouncer};
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 2;
+        const INIT_HIGH: bool = false;
+    }
+
+    #[derive(Default)]
+    struct Counter(AtomicUsize);
+
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn stream_of_edges() {
+        let debouncer: Debouncer<(), Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+        let counter = Arc::new(Counter::default());
+        let waker = Arc::clone(&counter).into();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut edges = debounced.edges();
+        assert_eq!(true, Pin::new(&mut edges).poll_next(&mut cx).is_pending());
+
+        for _ in 0..2 {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_with(true) }.unwrap();
+        }
+        assert_eq!(1, counter.0.load(Ordering::SeqCst));
+        assert_eq!(
+            Poll::Ready(Some(Edge::Rising)),
+            Pin::new(&mut edges).poll_next(&mut cx)
+        );
+        assert_eq!(true, Pin::new(&mut edges).poll_next(&mut cx).is_pending());
+
+        for _ in 0..2 {
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll_with(false) }.unwrap();
+        }
+        assert_eq!(2, counter.0.load(Ordering::SeqCst));
+        assert_eq!(
+            Poll::Ready(Some(Edge::Falling)),
+            Pin::new(&mut edges).poll_next(&mut cx)
+        );
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+}
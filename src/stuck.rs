@@ -0,0 +1,244 @@
+//! Detect inputs that are stuck.
+//!
+//! Debouncing hides the faults that matter most in an industrial
+//! setting.  A shorted or disconnected switch simply reads as a switch
+//! that nobody is pressing, and a contact that's failing intermittently
+//! looks like a bit of extra bounce.
+//!
+//! A [`StuckWatchdog`](StuckWatchdog) watches the raw samples as well
+//! as the debounced state, and flags the input as [`Stuck`](Stuck) once
+//! it has gone the configured number of polls either without a single
+//! change of the raw level, or without the debounced state settling.
+//! The flag clears itself as soon as the input shows signs of life.
+//! The second of these is just what a
+//! [`ChatterDetector`](crate::chatter::ChatterDetector) reports, and the
+//! watchdog is built on one.
+//!
+//! Choose the limit according to how the input is used.  A limit switch
+//! that is expected to be hit every machine cycle can use a limit of a
+//! few cycles, while for an emergency stop that is rarely pressed, only
+//! [`Stuck::MidTransition`](Stuck::MidTransition) is meaningful.
+//!
+//! ```
+//! # struct PinType;
+//! # impl embedded_hal::digital::v2::InputPin for PinType {
+//! #     type Error = core::convert::Infallible;
+//! #     fn is_high(&self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! # fn report_fault() {}
+//! use unflappable::{default::ActiveLow, stuck::StuckWatchdog};
+//!
+//! // Polling at 100Hz, the carriage should pass every few seconds.
+//! static HOME: StuckWatchdog<PinType, ActiveLow> = StuckWatchdog::uninit(0, 1000);
+//!
+//! # fn main() -> Result<(), unflappable::PollError<core::convert::Infallible>> {
+//! # let input_pin = PinType;
+//! let debounced = unsafe { HOME.init(input_pin) }.unwrap();
+//!
+//! // In the interrupt service routine:
+//! unsafe { HOME.poll() }?;
+//!
+//! // In the application:
+//! if HOME.stuck().is_some() {
+//!     report_fault();
+//! }
+//! #     Ok(())
+//! # }
+//! ```
+
+use core::cell::UnsafeCell;
+
+use crate::chatter::ChatterDetector;
+use crate::{Debounce, Debounced, DeinitError, Edge, InitError, PollError, Sampler};
+
+/// The way in which an input is stuck.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Stuck {
+    /// The raw level hasn't changed at all, as with a shorted or
+    /// disconnected switch.
+    Idle,
+
+    /// The raw level keeps changing, but the debounced state never
+    /// settles, as with a failing contact.  This is
+    /// [`is_chattering()`](crate::chatter::ChatterDetector#method.is_chattering).
+    MidTransition,
+}
+
+/// A debouncer that flags inputs that are stuck.
+pub struct StuckWatchdog<Pin, Cfg: Debounce> {
+    chatter: ChatterDetector<Pin, Cfg>,
+    idle: UnsafeCell<u32>,
+    last_sample: UnsafeCell<bool>,
+}
+
+// We demand particular mutex requirements as documented on the methods
+// marked as unsafe.  They are expected to be enforced statically by
+// the user, outside of the type system.
+unsafe impl<Pin, Cfg: Debounce> Sync for StuckWatchdog<Pin, Cfg> {}
+
+impl<Pin, Cfg: Debounce> StuckWatchdog<Pin, Cfg> {
+    /// Create a new, uninitialized watchdog, which flags the input once
+    /// it has been stuck for `limit` polls.
+    ///
+    /// As for [`Debouncer::uninit()`](crate::Debouncer#method.uninit),
+    /// you must pass in the zero value of the storage type.
+    #[inline]
+    pub const fn uninit(zero: Cfg::Storage, limit: u32) -> Self {
+        StuckWatchdog {
+            chatter: ChatterDetector::uninit(zero, limit),
+            idle: UnsafeCell::new(0),
+            last_sample: UnsafeCell::new(false),
+        }
+    }
+
+    /// Initialize the watchdog for a given input pin.
+    ///
+    /// Returns an error if it has already been initialized.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as
+    /// [`Debouncer::init()`](crate::Debouncer#method.init) apply.
+    pub unsafe fn init(&self, pin: Pin) -> Result<Debounced<'_, Cfg>, InitError<Pin>> {
+        let debounced = self.chatter.init(pin)?;
+
+        // This is safe because we demand from the caller that this
+        // method completes before any call to `poll()`.
+        *self.idle.get() = 0;
+        *self.last_sample.get() = Cfg::INIT_HIGH;
+
+        Ok(debounced)
+    }
+
+    /// Destroy the debounced pin, returning the original input pin.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as
+    /// [`Debouncer::deinit()`](crate::Debouncer#method.deinit) apply.
+    pub unsafe fn deinit<'a>(&self, pin: Debounced<'a, Cfg>) -> Result<Pin, DeinitError<'a, Cfg>> {
+        let pin = self.chatter.deinit(pin)?;
+
+        // This is safe because we demand from the caller that this is
+        // an exclusive call.
+        *self.idle.get() = 0;
+
+        Ok(pin)
+    }
+
+    /// Is the input stuck, and if so, how?
+    ///
+    /// This is cleared as soon as the raw level changes, or the
+    /// debounced state settles, as the case may be.
+    #[inline]
+    pub fn stuck(&self) -> Option<Stuck> {
+        // This is safe since the read is atomic.
+        let idle = unsafe { *self.idle.get() };
+
+        if self.chatter.is_chattering() {
+            Some(Stuck::MidTransition)
+        } else if idle >= self.chatter.limit() {
+            Some(Stuck::Idle)
+        } else {
+            None
+        }
+    }
+}
+
+impl<Pin: Sampler, Cfg: Debounce> StuckWatchdog<Pin, Cfg> {
+    /// Poll the watchdog.
+    ///
+    /// Returns the debounced edge, if any, just like
+    /// [`Debouncer::poll()`](crate::Debouncer#method.poll), and updates
+    /// the counts of polls the input has been stuck.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as
+    /// [`Debouncer::poll()`](crate::Debouncer#method.poll) apply.
+    pub unsafe fn poll(&self) -> Result<Edge, PollError<Pin::Error>> {
+        let (high, edge) = self.chatter.poll_sample()?;
+
+        // This is safe because we demand from the caller that this is
+        // an exclusive call.
+        let idle = &mut *self.idle.get();
+        let last_sample = &mut *self.last_sample.get();
+
+        if high != *last_sample {
+            *last_sample = high;
+            *idle = 0;
+        } else {
+            *idle = idle.saturating_add(1);
+        }
+
+        Ok(edge)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use embedded_hal_mock::pin;
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 4;
+        const INIT_HIGH: bool = false;
+    }
+
+    #[test]
+    fn flags_idle_input() {
+        let low = pin::Transaction::get(pin::State::Low);
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [low.clone(), low.clone(), low, high];
+
+        let watchdog: StuckWatchdog<_, Cfg> = StuckWatchdog::uninit(0, 3);
+        // It is always safe to init a stack-scoped StuckWatchdog.
+        let debounced = unsafe { watchdog.init(pin::Mock::new(&expectations)) }.unwrap();
+
+        for &stuck in [None, None, Some(Stuck::Idle), None].iter() {
+            // It is always safe to poll a stack-scoped StuckWatchdog.
+            assert_eq!(Edge::NoChange, unsafe { watchdog.poll() }.unwrap());
+            assert_eq!(stuck, watchdog.stuck());
+        }
+
+        // It is always safe to deinit a stack-scoped StuckWatchdog.
+        unsafe { watchdog.deinit(debounced) }.unwrap().done();
+    }
+
+    #[test]
+    fn flags_input_stuck_mid_transition() {
+        let low = pin::Transaction::get(pin::State::Low);
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [high.clone(), high.clone(), low.clone(), high, low];
+
+        let watchdog: StuckWatchdog<_, Cfg> = StuckWatchdog::uninit(0, 4);
+        // It is always safe to init a stack-scoped StuckWatchdog.
+        let debounced = unsafe { watchdog.init(pin::Mock::new(&expectations)) }.unwrap();
+
+        for &stuck in [
+            None,
+            None,
+            None,
+            Some(Stuck::MidTransition),
+            Some(Stuck::MidTransition),
+        ]
+        .iter()
+        {
+            // It is always safe to poll a stack-scoped StuckWatchdog.
+            assert_eq!(Edge::NoChange, unsafe { watchdog.poll() }.unwrap());
+            assert_eq!(stuck, watchdog.stuck());
+        }
+
+        // It is always safe to deinit a stack-scoped StuckWatchdog.
+        unsafe { watchdog.deinit(debounced) }.unwrap().done();
+    }
+}
@@ -0,0 +1,115 @@
+//! Helpers for testing debounced code on the host.
+//!
+//! The [`VirtualClock`](VirtualClock) is a [`Clock`](crate::time::Clock)
+//! that only moves when the test tells it to, so that anything timed,
+//! such as [`poll_timestamped()`](crate::Debouncer#method.poll_timestamped)
+//! or an [`EdgeTimer`](crate::time::EdgeTimer), can be tested
+//! deterministically.
+//!
+//! ```
+//! use unflappable::{test_util::VirtualClock, time::{Clock, EdgeTimer, Timestamped}, Edge};
+//!
+//! let clock = VirtualClock::new(0);
+//! let mut timer = EdgeTimer::new();
+//!
+//! timer.record(Timestamped { edge: Edge::Falling, at: clock.now() });
+//! clock.advance(750);
+//! let release = timer.record(Timestamped { edge: Edge::Rising, at: clock.now() });
+//!
+//! assert_eq!(Some(750), release.since_opposite);
+//! ```
+
+use core::cell::Cell;
+
+use crate::time::Clock;
+
+/// A clock that is advanced by hand.
+///
+/// Like the tick counters it stands in for, it wraps around to zero
+/// when it overflows.
+#[derive(Debug, Default, Clone)]
+pub struct VirtualClock {
+    now: Cell<u32>,
+}
+
+impl VirtualClock {
+    /// Create a new clock, starting at tick `start`.
+    #[inline]
+    pub const fn new(start: u32) -> Self {
+        VirtualClock {
+            now: Cell::new(start),
+        }
+    }
+
+    /// Move the clock forward by `ticks`.
+    #[inline]
+    pub fn advance(&self, ticks: u32) {
+        self.now.set(self.now.get().wrapping_add(ticks));
+    }
+
+    /// Set the clock to tick `now`.
+    #[inline]
+    pub fn set(&self, now: u32) {
+        self.now.set(now);
+    }
+}
+
+impl Clock for VirtualClock {
+    #[inline]
+    fn now(&self) -> u32 {
+        self.now.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{debouncer_uninit, time::Timestamped, Debounce, Debouncer, Edge};
+    use embedded_hal_mock::pin;
+
+    #[test]
+    fn advance_wraps() {
+        let clock = VirtualClock::new(u32::MAX - 1);
+        clock.advance(3);
+        assert_eq!(1, clock.now());
+
+        clock.set(100);
+        assert_eq!(100, clock.now());
+    }
+
+    #[test]
+    fn timestamps_polls() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 2;
+            const INIT_HIGH: bool = false;
+        }
+
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [high.clone(), high];
+
+        let clock = VirtualClock::new(1_000);
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin::Mock::new(&expectations)) }.unwrap();
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        let poll = || unsafe { debouncer.poll_timestamped(&clock) }.unwrap();
+
+        clock.advance(10);
+        assert_eq!(None, poll());
+        clock.advance(10);
+        assert_eq!(
+            Some(Timestamped {
+                edge: Edge::Rising,
+                at: 1_020,
+            }),
+            poll()
+        );
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
+}
@@ -0,0 +1,517 @@
+//! Wrapping tick arithmetic.
+//!
+//! Time in this crate is measured in ticks of a free-running `u32`
+//! counter, which wraps around to zero when it overflows.  Comparing
+//! ticks naively (`now >= deadline`) breaks at the wrap, so every
+//! time-based feature goes through these helpers instead.  You may find
+//! them handy for your own timing code too.
+//!
+//! All of these assume that the ticks being compared are within half
+//! the range of the counter (a little over two billion ticks) of each
+//! other.
+//!
+//! To have the debouncer read the counter itself, implement
+//! [`Clock`](Clock), or just pass a closure.  Then
+//! [`Debouncer::poll_timestamped()`](crate::Debouncer#method.poll_timestamped)
+//! stamps each edge with the time it was seen, and an
+//! [`EdgeTimer`](EdgeTimer) turns the stamped edges into press
+//! durations and the intervals between presses.
+//!
+//! ```
+//! use unflappable::{time::EdgeTimer, Edge};
+//! # use unflappable::{debouncer_uninit, Debouncer, default::ActiveLow};
+//! # struct PinType;
+//! # impl embedded_hal::digital::v2::InputPin for PinType {
+//! #     type Error = core::convert::Infallible;
+//! #     fn is_high(&self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! # static DEBOUNCER: Debouncer<PinType, ActiveLow> = debouncer_uninit!();
+//! # let _ = unsafe { DEBOUNCER.init(PinType) }.unwrap();
+//! # struct Monotonic;
+//! # impl Monotonic { fn ticks(&self) -> u32 { 0 } }
+//! # let monotonic = Monotonic;
+//!
+//! let clock = || monotonic.ticks();
+//! let mut timer = EdgeTimer::new();
+//!
+//! // In the poll routine:
+//! if let Some(event) = unsafe { DEBOUNCER.poll_timestamped(&clock) }.unwrap() {
+//!     let timing = timer.record(event);
+//!     if event.edge == Edge::Rising {
+//!         if let Some(held) = timing.since_opposite {
+//!             // the button was held for `held` ticks
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::Edge;
+
+/// The number of ticks elapsed from `then` to `now`.
+///
+/// ```
+/// use unflappable::time::ticks_since;
+/// assert_eq!(5, ticks_since(105, 100));
+/// assert_eq!(5, ticks_since(2, u32::MAX - 2));
+/// ```
+#[inline]
+pub const fn ticks_since(now: u32, then: u32) -> u32 {
+    now.wrapping_sub(then)
+}
+
+/// The tick `ticks` after `now`.
+///
+/// ```
+/// use unflappable::time::deadline;
+/// assert_eq!(110, deadline(100, 10));
+/// assert_eq!(6, deadline(u32::MAX - 3, 10));
+/// ```
+#[inline]
+pub const fn deadline(now: u32, ticks: u32) -> u32 {
+    now.wrapping_add(ticks)
+}
+
+/// Has time `now` reached `deadline`?
+///
+/// This is `true` from the deadline itself onward.
+///
+/// ```
+/// use unflappable::time::deadline_passed;
+/// assert!(!deadline_passed(u32::MAX, 6));
+/// assert!(deadline_passed(6, 6));
+/// assert!(deadline_passed(7, 6));
+/// ```
+#[inline]
+pub const fn deadline_passed(now: u32, deadline: u32) -> bool {
+    (now.wrapping_sub(deadline) as i32) >= 0
+}
+
+/// Convert elapsed ticks into a number of poll intervals.
+///
+/// For polling from a superloop with jittery timing, rather than from a
+/// periodic interrupt.  Pass the ticks elapsed since the last call, and
+/// get back the number of whole poll intervals to advance the debouncer
+/// by.  The remainder is carried over to the next call, so that no time
+/// is lost to rounding.  See
+/// [`Debouncer::tick()`](crate::Debouncer#method.tick).
+///
+/// ```
+/// use unflappable::time::PollInterval;
+///
+/// // Poll every 10ms, with a microsecond clock.
+/// let mut interval = PollInterval::new(10_000);
+///
+/// assert_eq!(0, interval.polls(6_000));
+/// assert_eq!(1, interval.polls(6_000));
+/// assert_eq!(3, interval.polls(28_000));
+/// ```
+///
+/// # Overrun
+///
+/// Normally every missed interval is made up, by integrating the one
+/// late sample as if it had been seen on each of them.  That keeps the
+/// debounce delay right after a brief hiccup, but if the loop stalls
+/// for a long time, a single sample may be enough to change state, and
+/// the debouncer turns twitchy.  With an overrun limit, a call that
+/// finds more than that many intervals elapsed counts as just one
+/// interval instead, so the debouncer needs its full count of fresh
+/// samples, and the overrun is kept for
+/// [`take_overrun()`](#method.take_overrun) to report.
+///
+/// ```
+/// use unflappable::time::PollInterval;
+///
+/// let mut interval = PollInterval::new(10_000).with_overrun_limit(4);
+///
+/// assert_eq!(3, interval.polls(30_000));
+/// assert_eq!(None, interval.take_overrun());
+///
+/// // The loop stalled for 100ms.
+/// assert_eq!(1, interval.polls(100_000));
+/// assert_eq!(Some(10), interval.take_overrun());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollInterval {
+    period: u32,
+    carry: u32,
+    overrun_limit: Option<u16>,
+    overrun: Option<u32>,
+}
+
+impl PollInterval {
+    /// A poll interval of `period` ticks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    #[inline]
+    pub const fn new(period: u32) -> Self {
+        assert!(period != 0, "the poll interval cannot be zero");
+        PollInterval {
+            period,
+            carry: 0,
+            overrun_limit: None,
+            overrun: None,
+        }
+    }
+
+    /// Treat more than `limit` intervals elapsed in one call as an
+    /// overrun.  See [Overrun](#overrun).
+    #[inline]
+    pub const fn with_overrun_limit(mut self, limit: u16) -> Self {
+        self.overrun_limit = Some(limit);
+        self
+    }
+
+    /// A poll interval of `period`, for elapsed times measured in the
+    /// same units.
+    ///
+    /// ```
+    /// use fugit::MicrosDurationU32;
+    /// use unflappable::time::PollInterval;
+    ///
+    /// let mut interval = PollInterval::from_period(MicrosDurationU32::micros(10_000));
+    /// assert_eq!(2, interval.polls(25_000));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    #[cfg(feature = "fugit")]
+    #[inline]
+    pub const fn from_period<const NOM: u32, const DENOM: u32>(
+        period: fugit::Duration<u32, NOM, DENOM>,
+    ) -> Self {
+        PollInterval::new(period.ticks())
+    }
+
+    /// The length of the interval in ticks.
+    #[inline]
+    pub const fn period(&self) -> u32 {
+        self.period
+    }
+
+    /// Note that `elapsed` ticks have passed, returning the number of
+    /// whole poll intervals now complete.
+    ///
+    /// If more than `u16::MAX` intervals have passed, the excess is
+    /// dropped, which the debouncer doesn't mind since it reaches a
+    /// rail long before that.  If more than the overrun limit have
+    /// passed, this returns one, and forgets any remainder.
+    pub fn polls(&mut self, elapsed: u32) -> u16 {
+        let total = self.carry as u64 + elapsed as u64;
+        let polls = total / self.period as u64;
+        self.carry = (total % self.period as u64) as u32;
+
+        if let Some(limit) = self.overrun_limit {
+            if polls > limit as u64 {
+                self.overrun = Some(polls.min(u32::MAX as u64) as u32);
+                self.carry = 0;
+                return 1;
+            }
+        }
+
+        if polls > u16::MAX as u64 {
+            u16::MAX
+        } else {
+            polls as u16
+        }
+    }
+
+    /// The number of intervals elapsed in the last overrun, if there
+    /// has been one since the last call.
+    #[inline]
+    pub fn take_overrun(&mut self) -> Option<u32> {
+        self.overrun.take()
+    }
+
+    /// Forget any partial interval carried over, and any overrun.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.carry = 0;
+        self.overrun = None;
+    }
+}
+
+/// A source of the current time, in ticks of a free-running `u32`
+/// counter.
+///
+/// This is implemented for closures returning the ticks, so that any
+/// monotonic timer can be plugged in, such as an RTIC monotonic with
+/// `|| monotonic.now().ticks()`.
+pub trait Clock {
+    /// The current time.
+    fn now(&self) -> u32;
+}
+
+impl<F: Fn() -> u32> Clock for F {
+    #[inline]
+    fn now(&self) -> u32 {
+        self()
+    }
+}
+
+/// A debounced edge, with the time it was seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Timestamped {
+    /// The debounced edge.
+    pub edge: Edge,
+
+    /// The time of the poll that saw the edge, in ticks of the clock.
+    pub at: u32,
+}
+
+#[cfg(feature = "fugit")]
+impl Timestamped {
+    /// The time of the poll that saw the edge, as a `fugit` instant of a
+    /// clock ticking at `NOM / DENOM` seconds per tick.
+    ///
+    /// ```
+    /// use fugit::TimerInstantU32;
+    /// use unflappable::{time::Timestamped, Edge};
+    ///
+    /// let event = Timestamped { edge: Edge::Falling, at: 5_000 };
+    /// let at: TimerInstantU32<1_000_000> = event.instant();
+    /// assert_eq!(5, at.duration_since_epoch().to_millis());
+    /// ```
+    #[inline]
+    pub const fn instant<const NOM: u32, const DENOM: u32>(
+        &self,
+    ) -> fugit::Instant<u32, NOM, DENOM> {
+        fugit::Instant::<u32, NOM, DENOM>::from_ticks(self.at)
+    }
+}
+
+/// The time since earlier edges, as measured by an
+/// [`EdgeTimer`](EdgeTimer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeTiming {
+    /// Ticks since the last edge the opposite way.  On the release of a
+    /// button, this is how long it was held.
+    pub since_opposite: Option<u32>,
+
+    /// Ticks since the last edge the same way.  On the press of a
+    /// button, this is the interval since the previous press.
+    pub since_same: Option<u32>,
+}
+
+#[cfg(feature = "fugit")]
+impl EdgeTiming {
+    /// The time since the last edge the opposite way, as a `fugit`
+    /// duration of a clock ticking at `NOM / DENOM` seconds per tick.
+    ///
+    /// ```
+    /// use fugit::MicrosDurationU32;
+    /// use unflappable::time::EdgeTiming;
+    ///
+    /// let timing = EdgeTiming { since_opposite: Some(250_000), since_same: None };
+    /// let held: Option<MicrosDurationU32> = timing.since_opposite_duration();
+    /// assert_eq!(Some(250), held.map(|held| held.to_millis()));
+    /// ```
+    #[inline]
+    pub fn since_opposite_duration<const NOM: u32, const DENOM: u32>(
+        &self,
+    ) -> Option<fugit::Duration<u32, NOM, DENOM>> {
+        self.since_opposite
+            .map(fugit::Duration::<u32, NOM, DENOM>::from_ticks)
+    }
+
+    /// The time since the last edge the same way, as a `fugit` duration
+    /// of a clock ticking at `NOM / DENOM` seconds per tick.
+    #[inline]
+    pub fn since_same_duration<const NOM: u32, const DENOM: u32>(
+        &self,
+    ) -> Option<fugit::Duration<u32, NOM, DENOM>> {
+        self.since_same
+            .map(fugit::Duration::<u32, NOM, DENOM>::from_ticks)
+    }
+}
+
+/// Measure the time between timestamped edges.
+#[derive(Debug, Clone, Default)]
+pub struct EdgeTimer {
+    last_rise: Option<u32>,
+    last_fall: Option<u32>,
+}
+
+impl EdgeTimer {
+    /// Create a new edge timer, which hasn't seen any edges yet.
+    #[inline]
+    pub const fn new() -> Self {
+        EdgeTimer {
+            last_rise: None,
+            last_fall: None,
+        }
+    }
+
+    /// Record an edge, returning the time since earlier edges.
+    ///
+    /// [`Edge::NoChange`](crate::Edge::NoChange) isn't recorded, and
+    /// has no timing.
+    pub fn record(&mut self, event: Timestamped) -> EdgeTiming {
+        let (same, opposite) = match event.edge {
+            Edge::Rising => (&mut self.last_rise, self.last_fall),
+            Edge::Falling => (&mut self.last_fall, self.last_rise),
+            Edge::NoChange => {
+                return EdgeTiming {
+                    since_opposite: None,
+                    since_same: None,
+                }
+            }
+        };
+
+        let timing = EdgeTiming {
+            since_opposite: opposite.map(|then| ticks_since(event.at, then)),
+            since_same: same.map(|then| ticks_since(event.at, then)),
+        };
+        *same = Some(event.at);
+        timing
+    }
+
+    /// Forget all the edges seen.
+    #[inline]
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    // Interesting places for the counter to be: both sides of the wrap,
+    // and both sides of the halfway point.
+    const ORIGINS: [u32; 6] = [0, 1, u32::MAX, i32::MAX as u32, i32::MAX as u32 + 1, 12345];
+
+    #[test]
+    fn since_across_wrap() {
+        for origin in ORIGINS {
+            for elapsed in 0..1000 {
+                let now = origin.wrapping_add(elapsed);
+                assert_eq!(elapsed, ticks_since(now, origin));
+            }
+        }
+    }
+
+    #[test]
+    fn deadline_across_wrap() {
+        for origin in ORIGINS {
+            for ticks in 0..100 {
+                let due = deadline(origin, ticks);
+                assert_eq!(ticks, ticks_since(due, origin));
+
+                for offset in 0..200u32 {
+                    let now = origin.wrapping_add(offset);
+                    assert_eq!(
+                        offset >= ticks,
+                        deadline_passed(now, due),
+                        "origin {}, ticks {}, offset {}",
+                        origin,
+                        ticks,
+                        offset
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn half_range_limit() {
+        let half = 1 << 31;
+
+        assert_eq!(true, deadline_passed(half - 1, 0));
+        assert_eq!(false, deadline_passed(half, 0));
+        assert_eq!(true, deadline_passed(0, half + 1));
+    }
+
+    #[test]
+    fn edge_timer() {
+        let mut timer = EdgeTimer::new();
+        let at = |edge, at| Timestamped { edge, at };
+
+        let first = timer.record(at(Edge::Falling, u32::MAX - 10));
+        assert_eq!(None, first.since_opposite);
+        assert_eq!(None, first.since_same);
+
+        // Held across the wrap.
+        let release = timer.record(at(Edge::Rising, 20));
+        assert_eq!(Some(31), release.since_opposite);
+        assert_eq!(None, release.since_same);
+
+        let second = timer.record(at(Edge::Falling, 100));
+        assert_eq!(Some(80), second.since_opposite);
+        assert_eq!(Some(111), second.since_same);
+
+        let none = timer.record(at(Edge::NoChange, 200));
+        assert_eq!(None, none.since_same);
+
+        timer.reset();
+        assert_eq!(None, timer.record(at(Edge::Rising, 300)).since_opposite);
+    }
+
+    #[test]
+    #[cfg(feature = "fugit")]
+    fn edge_timer_durations() {
+        use fugit::MillisDurationU32;
+
+        let mut timer = EdgeTimer::new();
+        let at = |edge, at| Timestamped { edge, at };
+
+        // A 32.768kHz clock, counting across the wrap.
+        timer.record(at(Edge::Falling, u32::MAX - 16_383));
+        let release = timer.record(at(Edge::Rising, 16_384));
+
+        let held: MillisDurationU32 = release
+            .since_opposite_duration::<1, 32_768>()
+            .unwrap()
+            .convert();
+        assert_eq!(1_000, held.ticks());
+        assert_eq!(None, release.since_same_duration::<1, 32_768>());
+        assert_eq!(
+            16_384,
+            at(Edge::Rising, 16_384).instant::<1, 32_768>().ticks()
+        );
+    }
+
+    #[test]
+    fn poll_interval_carries_remainder() {
+        let mut interval = PollInterval::new(3);
+
+        let polls: u32 = [1, 1, 2, 5, 0, 1]
+            .iter()
+            .map(|&elapsed| interval.polls(elapsed) as u32)
+            .sum();
+        assert_eq!(3, polls);
+
+        assert_eq!(1, interval.polls(2));
+        assert_eq!(u16::MAX, interval.polls(u32::MAX));
+
+        interval.reset();
+        assert_eq!(0, interval.polls(2));
+    }
+
+    #[test]
+    fn poll_interval_overrun() {
+        let mut interval = PollInterval::new(10).with_overrun_limit(3);
+
+        assert_eq!(3, interval.polls(35));
+        assert_eq!(None, interval.take_overrun());
+
+        // The carry of 5 tips this one over the limit, and is dropped.
+        assert_eq!(1, interval.polls(35));
+        assert_eq!(Some(4), interval.take_overrun());
+        assert_eq!(None, interval.take_overrun());
+        assert_eq!(0, interval.polls(5));
+
+        assert_eq!(1, interval.polls(u32::MAX));
+        interval.reset();
+        assert_eq!(None, interval.take_overrun());
+    }
+}
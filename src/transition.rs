@@ -0,0 +1,295 @@
+//! The debounce algorithm as a pure function.
+//!
+//! A [`Debouncer`](crate::Debouncer) keeps its state in storage of its
+//! own and dictates how it may be shared.  Some systems need the state
+//! somewhere else entirely: in a buffer filled by DMA, in a service of
+//! a custom RTOS, or packed alongside other data.  For them, this
+//! module exposes the transition function of the integration algorithm
+//! on a bare `u8`.  It is the same function every `Debouncer` runs, so
+//! it behaves exactly as a `Debouncer` with `u8` storage, the given
+//! `MAX_COUNT`, and the default algorithm and thresholds.
+//!
+//! The state is opaque.  Start it with [`init_state()`](init_state),
+//! advance it with [`step()`](step) on each sample, and read it with
+//! [`level()`](level).
+//!
+//! ```
+//! use unflappable::{transition, Edge};
+//!
+//! const MAX_COUNT: u8 = 4;
+//!
+//! let mut state = transition::init_state(MAX_COUNT, true);
+//! # let sample = false;
+//!
+//! // On each sample:
+//! let (next, edge) = transition::step(state, sample, MAX_COUNT);
+//! state = next;
+//! if edge == Edge::Falling {
+//!     // the pin went low
+//! }
+//! ```
+
+use core::ops::{AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, Not, Shl, Shr, SubAssign};
+
+use crate::{Algorithm, Edge, Level};
+
+pub(crate) const STATE: u8 = 0b01;
+const INIT: u8 = 0b10;
+const ONE: u8 = 1 << 2;
+
+// The operations the state machine needs of its state word, which any
+// `Debounce::Storage` provides.
+pub(crate) trait Word:
+    From<u8>
+    + BitAnd<Output = Self>
+    + BitAndAssign
+    + BitOr<Output = Self>
+    + BitOrAssign
+    + Not<Output = Self>
+    + Shl<u8, Output = Self>
+    + Shr<u8, Output = Self>
+    + AddAssign
+    + SubAssign
+    + Eq
+    + Copy
+{
+}
+
+impl<T> Word for T where
+    T: From<u8>
+        + BitAnd<Output = T>
+        + BitAndAssign
+        + BitOr<Output = T>
+        + BitOrAssign
+        + Not<Output = T>
+        + Shl<u8, Output = T>
+        + Shr<u8, Output = T>
+        + AddAssign
+        + SubAssign
+        + Eq
+        + Copy
+{
+}
+
+// The settings of the state machine, with the levels already shifted
+// into place above the flags.
+#[derive(Clone, Copy)]
+pub(crate) struct Params<S> {
+    pub(crate) rise_max: S,
+    pub(crate) fall_max: S,
+    pub(crate) set_level: S,
+    pub(crate) clear_level: S,
+    pub(crate) algorithm: Algorithm,
+}
+
+impl<S: Word> Params<S> {
+    // The default integrator, counting to `max` in both directions.
+    #[inline(always)]
+    pub(crate) fn integrator(max: S) -> Self {
+        Params {
+            rise_max: max,
+            fall_max: max,
+            set_level: max,
+            clear_level: S::from(0),
+            algorithm: Algorithm::Integrator,
+        }
+    }
+}
+
+// The transition function behind every debouncer in the crate.  The
+// flags and the integrator share the state word: the debounced level
+// in the lowest bit, the init flag above it, and the integrator (or the
+// majority-vote history) in the rest.
+#[inline(always)]
+pub(crate) fn advance<S: Word>(params: &Params<S>, state: S, high: bool) -> (S, Edge) {
+    match params.algorithm {
+        Algorithm::Majority { window, votes } => vote(state, high, window, votes),
+        algorithm => count(params, state, high, algorithm == Algorithm::Consecutive),
+    }
+}
+
+#[inline(always)]
+fn count<S: Word>(params: &Params<S>, state: S, high: bool, consecutive: bool) -> (S, Edge) {
+    let zero = S::from(0);
+    let one = S::from(ONE);
+    let flag = S::from(STATE);
+    let mask = !S::from(STATE | INIT);
+
+    let is_high = state & flag != zero;
+    let mut integrator = state & mask;
+    let others = state & !mask;
+
+    if !high {
+        if consecutive && !is_high {
+            integrator = zero;
+        } else if integrator != zero {
+            integrator -= one;
+        }
+
+        if integrator == params.clear_level && is_high {
+            ((others & !flag) | integrator, Edge::Falling)
+        } else {
+            (others | integrator, Edge::NoChange)
+        }
+    } else {
+        // The integrator counts up to the rise count while low, and
+        // down from the fall count while high.
+        let ceiling = if is_high {
+            params.fall_max
+        } else {
+            params.rise_max
+        };
+        if consecutive && is_high {
+            integrator = params.fall_max;
+        } else if integrator != ceiling {
+            integrator += one;
+        }
+
+        if integrator == params.set_level && !is_high {
+            // Having reached the rail, move to the rail for falling.
+            if integrator == ceiling {
+                integrator = params.fall_max;
+            }
+            (others | flag | integrator, Edge::Rising)
+        } else {
+            (others | integrator, Edge::NoChange)
+        }
+    }
+}
+
+#[inline(always)]
+fn vote<S: Word>(state: S, high: bool, window: u8, votes: u8) -> (S, Edge) {
+    let zero = S::from(0);
+    let one = S::from(ONE);
+    let flag = S::from(STATE);
+    let mask = !S::from(STATE | INIT);
+
+    let mut history = ((state & mask) << 1) & history_mask(window);
+    if high {
+        history |= one;
+    }
+    let new_state = (state & !mask) | history;
+
+    let highs = (0..window)
+        .filter(|&bit| history & (one << bit) != zero)
+        .count() as u8;
+
+    if state & flag == zero && highs >= votes {
+        (new_state | flag, Edge::Rising)
+    } else if state & flag != zero && window - highs >= votes {
+        (new_state & !flag, Edge::Falling)
+    } else {
+        (new_state, Edge::NoChange)
+    }
+}
+
+// The bits of the state word holding a majority-vote history.
+#[inline(always)]
+pub(crate) fn history_mask<S: Word>(window: u8) -> S {
+    let mut mask = S::from(1) << window;
+    mask -= S::from(1);
+    mask << 2
+}
+
+/// The initial state for a pin starting out at `init_high`.
+///
+/// # Panics
+///
+/// Panics if `max_count` is zero, or too big for the two bits taken by
+/// the flags, that is, greater than 63.
+#[inline]
+pub const fn init_state(max_count: u8, init_high: bool) -> u8 {
+    assert!(max_count != 0, "max_count cannot be zero");
+    assert!(max_count < 1 << 6, "max_count must be less than 64");
+
+    if init_high {
+        (max_count << 2) | INIT | STATE
+    } else {
+        INIT
+    }
+}
+
+/// Advance the state with a new sample, returning the new state and
+/// the debounced edge, if any.
+///
+/// This is the integrator with the default thresholds, counting to
+/// `max_count` in both directions.  It knows nothing of a `Debounce`
+/// configuration, so it ignores any other
+/// [`ALGORITHM`](crate::Debounce#associatedconstant.ALGORITHM),
+/// thresholds, or rise and fall counts.
+///
+/// `max_count` must be the same as was passed to
+/// [`init_state()`](init_state).
+///
+/// # Panics
+///
+/// Panics if `max_count` is zero or greater than 63, as for
+/// [`init_state()`](init_state).
+#[inline]
+pub fn step(state: u8, sample: bool, max_count: u8) -> (u8, Edge) {
+    assert!(max_count != 0, "max_count cannot be zero");
+    assert!(max_count < 1 << 6, "max_count must be less than 64");
+
+    advance(&Params::integrator(max_count << 2), state, sample)
+}
+
+/// The debounced level of the state.
+#[inline]
+pub const fn level(state: u8) -> Level {
+    if state & STATE != 0 {
+        Level::High
+    } else {
+        Level::Low
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{debouncer_uninit, Debounce, Debouncer};
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 3;
+        const INIT_HIGH: bool = false;
+    }
+
+    #[test]
+    fn matches_debouncer() {
+        let samples = [
+            true, false, true, true, true, true, false, true, false, false, false, true, false,
+            false, true, true, true,
+        ];
+
+        let debouncer: Debouncer<(), Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(()) }.unwrap();
+        let mut state = init_state(Cfg::MAX_COUNT, Cfg::INIT_HIGH);
+
+        for &sample in samples.iter() {
+            let (next, edge) = step(state, sample, Cfg::MAX_COUNT);
+            state = next;
+
+            // It is always safe to poll a stack-scoped Debouncer.
+            assert_eq!(unsafe { debouncer.poll_with(sample) }.unwrap(), edge);
+            assert_eq!(debounced.state(), level(state));
+        }
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "max_count cannot be zero")]
+    fn step_zero_max_count() {
+        let _ = step(INIT, true, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "max_count must be less than 64")]
+    fn step_max_count_overflow() {
+        let _ = step(INIT, true, 64);
+    }
+}
@@ -0,0 +1,535 @@
+//! A framed protocol for tuning debounce settings on hardware.
+//!
+//! Picking a good [`MAX_COUNT`](crate::Debounce::MAX_COUNT) is easiest
+//! while watching the real input bounce.  This module defines a tiny
+//! wire format for streaming what the debouncer sees over a serial port
+//! to a host tool: the raw samples, the integrator value after each
+//! poll, and the resulting debounced edges.
+//!
+//! Each [`Record`](Record) is encoded as a fixed seven-byte payload:
+//!
+//! | byte | contents                                           |
+//! |------|----------------------------------------------------|
+//! | 0    | record kind: `0` sample, `1` integrator, `2` event |
+//! |      | or `3` config                                      |
+//! | 1    | channel, or for config the parameter               |
+//! | 2-5  | value, little-endian                               |
+//! | 6    | checksum: the wrapping sum of bytes 0 to 5         |
+//!
+//! A sample's value is `1` for high and `0` for low.  An event's value
+//! is `1` for a rising edge and `2` for a falling one.
+//!
+//! Config records travel the other way, from the host tool to the
+//! firmware, to adjust a runtime parameter during bring-up without
+//! reflashing.  The firmware decodes them with a [`Decoder`](Decoder)
+//! and hands them to [`Parameter::apply()`].  The parameters are
+//! numbered as in [`Parameter`](Parameter).  The debounce threshold
+//! itself, `MAX_COUNT`, is fixed at compile time and can't be adjusted.
+//!
+//! The payload is framed with [SLIP][0]: each frame begins and ends with
+//! `0xC0`, and any `0xC0` or `0xDB` in the payload is escaped.  This lets
+//! the host resynchronize after a dropped byte by waiting for the next
+//! frame boundary.  The firmware side uses [`Record::encode()`], and the
+//! host side a [`Decoder`](Decoder), which is also `no_std`.
+//!
+//! [0]: https://datatracker.ietf.org/doc/html/rfc1055
+
+use crate::gesture::GestureTiming;
+use crate::{Debounce, DebounceExt, Debounced, Edge, Level};
+
+const END: u8 = 0xC0;
+const ESC: u8 = 0xDB;
+const ESC_END: u8 = 0xDC;
+const ESC_ESC: u8 = 0xDD;
+
+const PAYLOAD_LEN: usize = 7;
+
+/// The longest possible encoded frame, in bytes.
+pub const MAX_FRAME_LEN: usize = 2 + 2 * PAYLOAD_LEN;
+
+/// A single observation of a debounced channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Record {
+    /// A raw sample read from the input pin.
+    Sample {
+        /// The channel sampled.
+        channel: u8,
+        /// The level read.
+        level: Level,
+    },
+
+    /// The integrator value after a poll.
+    Integrator {
+        /// The channel polled.
+        channel: u8,
+        /// The integrator value, between zero and `MAX_COUNT`.
+        value: u32,
+    },
+
+    /// A debounced edge.
+    Event {
+        /// The channel polled.
+        channel: u8,
+        /// The edge reported by the poll.
+        edge: Edge,
+    },
+
+    /// A request from the host to adjust a parameter.
+    Config {
+        /// The parameter to adjust.
+        parameter: Parameter,
+        /// The new value.
+        value: u32,
+    },
+}
+
+/// A runtime-adjustable parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parameter {
+    /// [`GestureTiming::long_press`](crate::gesture::GestureTiming#structfield.long_press).
+    LongPress,
+
+    /// [`GestureTiming::multi_click`](crate::gesture::GestureTiming#structfield.multi_click).
+    MultiClick,
+
+    /// [`GestureTiming::repeat_delay`](crate::gesture::GestureTiming#structfield.repeat_delay).
+    RepeatDelay,
+
+    /// [`GestureTiming::repeat_interval`](crate::gesture::GestureTiming#structfield.repeat_interval).
+    RepeatInterval,
+}
+
+impl Parameter {
+    /// Set this parameter in `timing` to `value`, saturating at the
+    /// largest value the parameter can hold.
+    ///
+    /// Recognizers take their timing when they're created, so pass the
+    /// updated timing along to them afterwards, for instance with
+    /// [`LongPress::set_timing()`](crate::gesture::LongPress::set_timing).
+    pub fn apply(self, timing: &mut GestureTiming, value: u32) {
+        let value = if value > u16::MAX as u32 {
+            u16::MAX
+        } else {
+            value as u16
+        };
+
+        match self {
+            Parameter::LongPress => timing.long_press = value,
+            Parameter::MultiClick => timing.multi_click = value,
+            Parameter::RepeatDelay => timing.repeat_delay = value,
+            Parameter::RepeatInterval => timing.repeat_interval = value,
+        }
+    }
+
+    fn id(self) -> u8 {
+        match self {
+            Parameter::LongPress => 0,
+            Parameter::MultiClick => 1,
+            Parameter::RepeatDelay => 2,
+            Parameter::RepeatInterval => 3,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Parameter::LongPress),
+            1 => Some(Parameter::MultiClick),
+            2 => Some(Parameter::RepeatDelay),
+            3 => Some(Parameter::RepeatInterval),
+            _ => None,
+        }
+    }
+}
+
+/// An error encoding a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferTooSmall;
+
+/// An error decoding a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The frame was not the length of a payload.
+    Length,
+
+    /// The frame contained an invalid escape sequence.
+    Escape,
+
+    /// The checksum did not match.
+    Checksum,
+
+    /// The record kind or value was not recognized.
+    Invalid,
+}
+
+impl Record {
+    /// Record the current integrator value of a debounced pin.
+    #[inline]
+    pub fn integrator<Cfg>(channel: u8, pin: &Debounced<'_, Cfg>) -> Self
+    where
+        Cfg: Debounce,
+        Cfg::Storage: Into<u32>,
+    {
+        let state_ptr = pin.storage.get();
+        // This is safe since the read is atomic.
+        let state = unsafe { *state_ptr };
+        let value = (state & Cfg::integrator_mask()) >> 2;
+
+        Record::Integrator {
+            channel,
+            value: value.into(),
+        }
+    }
+
+    /// Encode the record as a frame into `buf`, returning the number of
+    /// bytes written.
+    ///
+    /// A buffer of [`MAX_FRAME_LEN`](MAX_FRAME_LEN) bytes is always big
+    /// enough.
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, BufferTooSmall> {
+        let payload = self.payload();
+        let mut len = 0;
+
+        let mut put = |byte| {
+            let slot = buf.get_mut(len).ok_or(BufferTooSmall)?;
+            *slot = byte;
+            len += 1;
+            Ok(())
+        };
+
+        put(END)?;
+        for byte in payload {
+            match byte {
+                END => {
+                    put(ESC)?;
+                    put(ESC_END)?;
+                }
+                ESC => {
+                    put(ESC)?;
+                    put(ESC_ESC)?;
+                }
+                _ => put(byte)?,
+            }
+        }
+        put(END)?;
+
+        Ok(len)
+    }
+
+    fn payload(&self) -> [u8; PAYLOAD_LEN] {
+        let (kind, channel, value) = match *self {
+            Record::Sample { channel, level } => (0, channel, level.is_high() as u32),
+            Record::Integrator { channel, value } => (1, channel, value),
+            Record::Event { channel, edge } => {
+                let value = match edge {
+                    Edge::NoChange => 0,
+                    Edge::Rising => 1,
+                    Edge::Falling => 2,
+                };
+                (2, channel, value)
+            }
+            Record::Config { parameter, value } => (3, parameter.id(), value),
+        };
+
+        let value = value.to_le_bytes();
+        let mut payload = [kind, channel, value[0], value[1], value[2], value[3], 0];
+        payload[6] = checksum(&payload[..6]);
+        payload
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        if payload.len() != PAYLOAD_LEN {
+            return Err(DecodeError::Length);
+        }
+        if checksum(&payload[..6]) != payload[6] {
+            return Err(DecodeError::Checksum);
+        }
+
+        let channel = payload[1];
+        let value = u32::from_le_bytes([payload[2], payload[3], payload[4], payload[5]]);
+
+        match (payload[0], value) {
+            (0, 0) => Ok(Record::Sample {
+                channel,
+                level: Level::Low,
+            }),
+            (0, 1) => Ok(Record::Sample {
+                channel,
+                level: Level::High,
+            }),
+            (1, value) => Ok(Record::Integrator { channel, value }),
+            (2, 0) => Ok(Record::Event {
+                channel,
+                edge: Edge::NoChange,
+            }),
+            (2, 1) => Ok(Record::Event {
+                channel,
+                edge: Edge::Rising,
+            }),
+            (2, 2) => Ok(Record::Event {
+                channel,
+                edge: Edge::Falling,
+            }),
+            (3, value) => match Parameter::from_id(channel) {
+                Some(parameter) => Ok(Record::Config { parameter, value }),
+                None => Err(DecodeError::Invalid),
+            },
+            _ => Err(DecodeError::Invalid),
+        }
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// A streaming decoder for tuning frames.
+///
+/// Feed it the received bytes one at a time; it reports each complete
+/// frame as it arrives.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    buf: [u8; PAYLOAD_LEN],
+    len: usize,
+    escaped: bool,
+    error: Option<DecodeError>,
+}
+
+impl Decoder {
+    /// Create a new decoder, waiting for the start of a frame.
+    #[inline]
+    pub const fn new() -> Self {
+        Decoder {
+            buf: [0; PAYLOAD_LEN],
+            len: 0,
+            escaped: false,
+            error: None,
+        }
+    }
+
+    /// Handle the next received byte.
+    ///
+    /// Returns the decoded record (or the problem with it) when `byte`
+    /// ends a frame, otherwise `None`.  Empty frames, as found between
+    /// back-to-back frames, are skipped.
+    pub fn push(&mut self, byte: u8) -> Option<Result<Record, DecodeError>> {
+        if byte == END {
+            let result = match self.error.take() {
+                Some(error) => Some(Err(error)),
+                None if self.len == 0 && !self.escaped => None,
+                None if self.escaped => Some(Err(DecodeError::Escape)),
+                None => Some(Record::from_payload(&self.buf[..self.len])),
+            };
+            self.len = 0;
+            self.escaped = false;
+            return result;
+        }
+
+        if self.error.is_some() {
+            return None;
+        }
+
+        let byte = if self.escaped {
+            self.escaped = false;
+            match byte {
+                ESC_END => END,
+                ESC_ESC => ESC,
+                _ => {
+                    self.error = Some(DecodeError::Escape);
+                    return None;
+                }
+            }
+        } else if byte == ESC {
+            self.escaped = true;
+            return None;
+        } else {
+            byte
+        };
+
+        match self.buf.get_mut(self.len) {
+            Some(slot) => {
+                *slot = byte;
+                self.len += 1;
+            }
+            None => self.error = Some(DecodeError::Length),
+        }
+
+        None
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder::new()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    use embedded_hal_mock::pin;
+
+    use crate::{debouncer_uninit, Debouncer};
+
+    fn decode(frame: &[u8]) -> Option<Result<Record, DecodeError>> {
+        let mut decoder = Decoder::new();
+        let mut result = None;
+        for &byte in frame {
+            if let Some(decoded) = decoder.push(byte) {
+                assert!(result.is_none(), "more than one frame decoded");
+                result = Some(decoded);
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn round_trip() {
+        let records = [
+            Record::Sample {
+                channel: 3,
+                level: Level::High,
+            },
+            Record::Integrator {
+                channel: 0,
+                value: 0xC0DB,
+            },
+            Record::Event {
+                channel: 0xC0,
+                edge: Edge::Falling,
+            },
+            Record::Config {
+                parameter: Parameter::RepeatInterval,
+                value: 25,
+            },
+        ];
+
+        for record in records {
+            let mut buf = [0; MAX_FRAME_LEN];
+            let len = record.encode(&mut buf).unwrap();
+            assert_eq!(Some(Ok(record)), decode(&buf[..len]));
+        }
+    }
+
+    #[test]
+    fn escaping() {
+        let record = Record::Integrator {
+            channel: 0xDB,
+            value: 0xC0,
+        };
+
+        let mut buf = [0; MAX_FRAME_LEN];
+        let len = record.encode(&mut buf).unwrap();
+        assert_eq!(
+            [END, 1, ESC, ESC_ESC, ESC, ESC_END, 0, 0, 0, 0x9C, END],
+            buf[..len]
+        );
+
+        assert_eq!(Err(BufferTooSmall), record.encode(&mut buf[..10]));
+    }
+
+    #[test]
+    fn bad_frames() {
+        assert_eq!(None, decode(&[END, END, END]));
+        assert_eq!(Some(Err(DecodeError::Length)), decode(&[END, 0, 0, END]));
+        assert_eq!(
+            Some(Err(DecodeError::Length)),
+            decode(&[END, 0, 0, 0, 0, 0, 0, 0, 0, END])
+        );
+        assert_eq!(
+            Some(Err(DecodeError::Checksum)),
+            decode(&[END, 0, 0, 1, 0, 0, 0, 0, END])
+        );
+        assert_eq!(
+            Some(Err(DecodeError::Invalid)),
+            decode(&[END, 0, 0, 2, 0, 0, 0, 2, END])
+        );
+        assert_eq!(
+            Some(Err(DecodeError::Escape)),
+            decode(&[END, 0, ESC, 0, END])
+        );
+    }
+
+    #[test]
+    fn resynchronize() {
+        let mut decoder = Decoder::new();
+
+        // The tail end of a frame, followed by a complete one.
+        let mut results = [0, 1, 0, END, END, 0, 1, 0, 0, 0, 0, 1, END]
+            .iter()
+            .filter_map(|&byte| decoder.push(byte));
+
+        assert_eq!(Some(Err(DecodeError::Length)), results.next());
+        assert_eq!(
+            Some(Ok(Record::Sample {
+                channel: 1,
+                level: Level::Low,
+            })),
+            results.next()
+        );
+        assert_eq!(None, results.next());
+    }
+
+    #[test]
+    fn apply_config() {
+        let mut decoder = Decoder::new();
+        let mut timing = GestureTiming::default();
+
+        let frames = [
+            [END, 3, 0, 0x2C, 0x01, 0, 0, 0x30, END],
+            [END, 3, 2, 0, 0, 1, 0, 0x06, END],
+        ];
+        for byte in frames.iter().flatten() {
+            if let Some(Ok(Record::Config { parameter, value })) = decoder.push(*byte) {
+                parameter.apply(&mut timing, value);
+            }
+        }
+
+        assert_eq!(300, timing.long_press);
+        assert_eq!(u16::MAX, timing.repeat_delay);
+        assert_eq!(GestureTiming::default().multi_click, timing.multi_click);
+
+        assert_eq!(
+            Some(Err(DecodeError::Invalid)),
+            decode(&[END, 3, 4, 0, 0, 0, 0, 7, END])
+        );
+    }
+
+    #[test]
+    fn integrator_value() {
+        struct Cfg;
+        impl Debounce for Cfg {
+            type Storage = u8;
+            const MAX_COUNT: u8 = 3;
+            const INIT_HIGH: bool = true;
+        }
+
+        let expectations = [pin::Transaction::get(pin::State::Low)];
+
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let debounced = unsafe { debouncer.init(pin::Mock::new(&expectations)) }.unwrap();
+
+        assert_eq!(
+            Record::Integrator {
+                channel: 7,
+                value: 3
+            },
+            Record::integrator(7, &debounced)
+        );
+
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll() }.unwrap();
+        assert_eq!(
+            Record::Integrator {
+                channel: 7,
+                value: 2
+            },
+            Record::integrator(7, &debounced)
+        );
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
+}
@@ -0,0 +1,171 @@
+//! Track initialization in the type system.
+//!
+//! A [`Debouncer`](crate::Debouncer) finds out at runtime whether it
+//! has been initialized, so every poll checks, and may report
+//! [`PollError::Init`](crate::PollError::Init).  That's unavoidable for
+//! a `static`, but when the debouncer is an ordinary value, the type
+//! system can keep track instead.
+//!
+//! A [`TypedDebouncer`](TypedDebouncer) starts out
+//! [`Uninit`](Uninit), with no pin and no way to poll.  Initializing it
+//! consumes it, and returns one that is [`Ready`](Ready), holding the
+//! pin.  Only a ready debouncer can be polled, so polling before
+//! initializing is a compile error, and the only error a poll can
+//! report is from reading the pin.
+//!
+//! ```
+//! # struct PinType;
+//! # impl embedded_hal::digital::v2::InputPin for PinType {
+//! #     type Error = core::convert::Infallible;
+//! #     fn is_high(&self) -> Result<bool, Self::Error> {
+//! #         Ok(true)
+//! #     }
+//! #     fn is_low(&self) -> Result<bool, Self::Error> {
+//! #         Ok(false)
+//! #     }
+//! # }
+//! # fn main() -> Result<(), core::convert::Infallible> {
+//! # let input_pin = PinType;
+//! use unflappable::{default::ActiveLow, typestate::TypedDebouncer};
+//!
+//! let debouncer = TypedDebouncer::<ActiveLow>::new();
+//! let mut debouncer = debouncer.init(input_pin);
+//!
+//! // In the main loop:
+//! debouncer.poll()?;
+//!
+//! // Later, to get the pin back:
+//! let (debouncer, input_pin) = debouncer.deinit();
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::{Debounce, DebounceExt, Debouncer, Edge, Level, Sampler};
+
+/// The state of a [`TypedDebouncer`](TypedDebouncer) that hasn't been
+/// initialized.
+#[derive(Debug, Default)]
+pub struct Uninit;
+
+/// The state of a [`TypedDebouncer`](TypedDebouncer) that is ready to
+/// poll, holding the input pin.
+#[derive(Debug)]
+pub struct Ready<Pin>(Pin);
+
+/// A debouncer whose initialization is tracked in its type.
+pub struct TypedDebouncer<Cfg: Debounce, State = Uninit> {
+    debouncer: Debouncer<(), Cfg>,
+    state: State,
+}
+
+impl<Cfg: Debounce> TypedDebouncer<Cfg, Uninit> {
+    /// Create a new, uninitialized debouncer.
+    #[inline]
+    pub fn new() -> Self {
+        TypedDebouncer {
+            debouncer: Debouncer::uninit(Cfg::zero()),
+            state: Uninit,
+        }
+    }
+
+    /// Initialize the debouncer for a given input pin.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the [`Debounce`](crate::Debounce) configuration is
+    /// invalid.
+    pub fn init<Pin>(self, pin: Pin) -> TypedDebouncer<Cfg, Ready<Pin>> {
+        self.debouncer.check_config();
+        // An uninitialized debouncer is never already initialized.
+        let _ = self.debouncer.init_linted(());
+
+        TypedDebouncer {
+            debouncer: self.debouncer,
+            state: Ready(pin),
+        }
+    }
+}
+
+impl<Cfg: Debounce> Default for TypedDebouncer<Cfg, Uninit> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Cfg: Debounce, Pin> TypedDebouncer<Cfg, Ready<Pin>> {
+    /// Poll the debouncer with a sample taken elsewhere, rather than
+    /// reading the pin.
+    #[inline]
+    pub fn poll_with(&mut self, sample: bool) -> Edge {
+        self.debouncer.integrate(sample)
+    }
+
+    /// The current debounced level.
+    #[inline]
+    pub fn state(&self) -> Level {
+        self.debouncer.debounced().state()
+    }
+
+    /// Deinitialize the debouncer, returning it along with the original
+    /// input pin.
+    pub fn deinit(self) -> (TypedDebouncer<Cfg, Uninit>, Pin) {
+        let _ = self.debouncer.deinit_linted(self.debouncer.debounced());
+
+        let debouncer = TypedDebouncer {
+            debouncer: self.debouncer,
+            state: Uninit,
+        };
+        (debouncer, self.state.0)
+    }
+}
+
+impl<Cfg: Debounce, Pin: Sampler> TypedDebouncer<Cfg, Ready<Pin>> {
+    /// Poll the debouncer.
+    ///
+    /// This should be done on a regular basis at roughly the frequency
+    /// used in the calculation of
+    /// [`MAX_COUNT`](crate::Debounce#associatedconstant.MAX_COUNT).
+    /// Returns the debounced edge, if any.  The only possible error is
+    /// from reading the pin.
+    #[inline]
+    pub fn poll(&mut self) -> Result<Edge, Pin::Error> {
+        let high = self.state.0.sample()?;
+        Ok(self.debouncer.integrate(high))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use embedded_hal_mock::pin;
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 2;
+        const INIT_HIGH: bool = false;
+    }
+
+    #[test]
+    fn init_poll_deinit() {
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [high.clone(), high];
+
+        let debouncer = TypedDebouncer::<Cfg>::new();
+        let mut debouncer = debouncer.init(pin::Mock::new(&expectations));
+        assert_eq!(Level::Low, debouncer.state());
+
+        assert_eq!(Edge::NoChange, debouncer.poll().unwrap());
+        assert_eq!(Edge::Rising, debouncer.poll().unwrap());
+        assert_eq!(Level::High, debouncer.state());
+
+        let (debouncer, mut pin) = debouncer.deinit();
+        pin.done();
+
+        // Deinitializing resets the state for the next pin.
+        let debouncer = debouncer.init(());
+        assert_eq!(Level::Low, debouncer.state());
+    }
+}
@@ -0,0 +1,128 @@
+//! Awaiting the debounced signal.
+//!
+//! The waiting task registers its waker with the `Debouncer`, which
+//! wakes it from `poll()` whenever a transition completes.  Edges are
+//! recognized by waiting for the opposite level and then the level
+//! itself, so the task must get a chance to run at least once between
+//! transitions.  Since a transition takes at least `MAX_COUNT` polls,
+//! that is rarely a concern.
+
+use core::future::poll_fn;
+use core::task::Poll;
+
+use embedded_hal_async::digital::Wait;
+
+use crate::{Debounce, Debounced, Level};
+
+impl<'state, Cfg: Debounce> Debounced<'state, Cfg> {
+    async fn wait_for(&self, level: Level) {
+        poll_fn(|cx| {
+            // Register before checking, so that a transition completing
+            // in between still wakes us.
+            self.waker.register(cx.waker());
+
+            if self.state() == level {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl<'state, Cfg: Debounce> Wait for Debounced<'state, Cfg> {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Level::High).await;
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Level::Low).await;
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Level::Low).await;
+        self.wait_for(Level::High).await;
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Level::High).await;
+        self.wait_for(Level::Low).await;
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(!self.state()).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    extern crate std;
+
+    use super::*;
+
+    use core::future::Future;
+    use core::pin::pin;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use core::task::Context;
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    use embedded_hal_mock::pin;
+
+    use crate::{debouncer_uninit, Debouncer};
+
+    struct Cfg;
+    impl Debounce for Cfg {
+        type Storage = u8;
+        const MAX_COUNT: u8 = 2;
+        const INIT_HIGH: bool = false;
+    }
+
+    #[derive(Default)]
+    struct Counter(AtomicUsize);
+
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn wait_for_rising_edge() {
+        let high = pin::Transaction::get(pin::State::High);
+        let expectations = [high.clone(), high];
+
+        let debouncer: Debouncer<_, Cfg> = debouncer_uninit!();
+        // It is always safe to init a stack-scoped Debouncer.
+        let mut debounced = unsafe { debouncer.init(pin::Mock::new(&expectations)) }.unwrap();
+
+        let counter = Arc::new(Counter::default());
+        let waker = Arc::clone(&counter).into();
+        let mut cx = Context::from_waker(&waker);
+
+        {
+            let mut edge = pin!(debounced.wait_for_rising_edge());
+            assert_eq!(true, edge.as_mut().poll(&mut cx).is_pending());
+
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll() }.unwrap();
+            assert_eq!(0, counter.0.load(Ordering::SeqCst));
+            assert_eq!(true, edge.as_mut().poll(&mut cx).is_pending());
+
+            // It is always safe to poll a stack-scoped Debouncer.
+            unsafe { debouncer.poll() }.unwrap();
+            assert_eq!(1, counter.0.load(Ordering::SeqCst));
+            assert_eq!(true, edge.as_mut().poll(&mut cx).is_ready());
+        }
+
+        // It is always safe to deinit a stack-scoped Debouncer.
+        unsafe { debouncer.deinit(debounced) }.unwrap().done();
+    }
+}
@@ -0,0 +1,329 @@
+//! Tie watchdog servicing to the poll routine.
+//!
+//! The periodic input poll is often the most dependable heartbeat a
+//! system has, so it makes a good place to service a watchdog timer:
+//! as long as inputs are being polled successfully, the system is
+//! alive.  A [`Heartbeat`](Heartbeat) counts successful polls, and
+//! calls your kick callback once every so many of them.  If polling
+//! stops, or keeps failing, the kicks stop and the watchdog fires.
+//!
+//! ```
+//! # struct Watchdog;
+//! # impl Watchdog { fn feed(&mut self) {} }
+//! # let mut watchdog = Watchdog;
+//! # fn poll() -> Result<(), ()> { Ok(()) }
+//! use unflappable::watchdog::Heartbeat;
+//!
+//! // Polling at 1kHz, kick the watchdog ten times a second.
+//! let mut heartbeat = Heartbeat::new(100, || watchdog.feed());
+//!
+//! // In the poll routine:
+//! heartbeat.track(poll())?;
+//! # Ok::<(), ()>(())
+//! ```
+//!
+//! A watchdog catches a system that has stopped entirely, but not one
+//! where the timer driving the polls has died while everything else
+//! keeps running.  If you have two independent periodic interrupts,
+//! say SysTick and a hardware timer, a [`CrossCheck`](CrossCheck) lets
+//! each vouch for the other: both call
+//! [`heartbeat()`](CrossCheck::heartbeat), and each one checks that the
+//! other has beaten about as often as expected since it last looked.
+//!
+//! ```
+//! use unflappable::watchdog::{CrossCheck, Side};
+//!
+//! // SysTick at 1kHz, and the poll timer at 100Hz, so in a common
+//! // window of 100ms we expect 100 beats of one and ten of the other.
+//! static CROSS_CHECK: CrossCheck = CrossCheck::new(100, 10, 1);
+//!
+//! // In the SysTick handler:
+//! if unsafe { CROSS_CHECK.heartbeat(Side::A) }.is_err() {
+//!     // the poll timer has stalled
+//! }
+//!
+//! // In the poll timer handler:
+//! if unsafe { CROSS_CHECK.heartbeat(Side::B) }.is_err() {
+//!     // SysTick has stalled
+//! }
+//! ```
+
+use core::cell::UnsafeCell;
+
+/// Kick a watchdog every so many successful polls.
+pub struct Heartbeat<Kick> {
+    every: u16,
+    count: u16,
+    kick: Kick,
+}
+
+impl<Kick: FnMut()> Heartbeat<Kick> {
+    /// Call `kick` once every `every` successful polls.
+    ///
+    /// An `every` of zero is treated as one, kicking on every poll.
+    #[inline]
+    pub const fn new(every: u16, kick: Kick) -> Self {
+        Heartbeat {
+            every,
+            count: 0,
+            kick,
+        }
+    }
+
+    /// Note a successful poll, kicking if it's time.
+    ///
+    /// Returns `true` if the kick callback was called.
+    pub fn beat(&mut self) -> bool {
+        self.count += 1;
+
+        if self.count >= self.every {
+            self.count = 0;
+            (self.kick)();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Note the result of a poll, passing it through.
+    ///
+    /// Successful polls count toward the next kick; failed polls don't.
+    #[inline]
+    pub fn track<T, E>(&mut self, result: Result<T, E>) -> Result<T, E> {
+        if result.is_ok() {
+            self.beat();
+        }
+        result
+    }
+
+    /// Destroy the heartbeat, returning the kick callback.
+    #[inline]
+    pub fn release(self) -> Kick {
+        self.kick
+    }
+}
+
+/// One of the two heartbeat sources of a [`CrossCheck`](CrossCheck).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The first source.
+    A,
+
+    /// The second source.
+    B,
+}
+
+/// The other side didn't beat as often as expected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    /// The number of beats expected from the other side.
+    pub expected: u16,
+
+    /// The number of beats actually seen from the other side.
+    pub actual: u16,
+}
+
+struct Beats {
+    per_window: u16,
+    count: UnsafeCell<u16>,
+    window: UnsafeCell<u16>,
+    seen: UnsafeCell<u16>,
+    diverged: UnsafeCell<bool>,
+}
+
+impl Beats {
+    const fn new(per_window: u16) -> Self {
+        Beats {
+            per_window,
+            count: UnsafeCell::new(0),
+            window: UnsafeCell::new(0),
+            seen: UnsafeCell::new(0),
+            diverged: UnsafeCell::new(false),
+        }
+    }
+}
+
+/// Cross-check two independent heartbeat sources against each other.
+///
+/// Each side is expected to beat a fixed number of times in a common
+/// window of time.  Once a side has beaten its own number of times, it
+/// checks how many times the other side has beaten since it last
+/// checked, and flags a divergence if that's off by more than the
+/// tolerance.  Since the two sides' windows don't line up exactly, the
+/// tolerance should be at least one, but to detect a dead source it
+/// must be less than that source's count.
+///
+/// Each side's state is only ever written from that side, and only
+/// read from the other, so this needs no atomics, and each side may be
+/// called from its own interrupt priority.
+pub struct CrossCheck {
+    a: Beats,
+    b: Beats,
+    tolerance: u16,
+}
+
+// Each side's state is only written by `heartbeat()` for that side,
+// whose callers promise to call it from a single context.
+unsafe impl Sync for CrossCheck {}
+
+impl CrossCheck {
+    /// Create a cross-check where side A beats `a_per_window` times and
+    /// side B beats `b_per_window` times in the same window of time.
+    #[inline]
+    pub const fn new(a_per_window: u16, b_per_window: u16, tolerance: u16) -> Self {
+        CrossCheck {
+            a: Beats::new(a_per_window),
+            b: Beats::new(b_per_window),
+            tolerance,
+        }
+    }
+
+    /// Note a beat from one side.
+    ///
+    /// Once this side's window is complete, returns an error if the
+    /// other side's beats have diverged from what was expected.
+    ///
+    /// # Safety
+    ///
+    /// Each side must only ever be beaten from a single context, so that
+    /// a call for one side never interrupts or is interrupted by another
+    /// call for the same side.  The two sides may interrupt each other
+    /// freely.
+    pub unsafe fn heartbeat(&self, side: Side) -> Result<(), Divergence> {
+        let (mine, theirs) = match side {
+            Side::A => (&self.a, &self.b),
+            Side::B => (&self.b, &self.a),
+        };
+
+        // This side's state is ours alone to write, and the reads of
+        // the other side's count are atomic.
+        let count = mine.count.get();
+        *count = (*count).wrapping_add(1);
+
+        let window = mine.window.get();
+        *window += 1;
+        if *window < mine.per_window {
+            return Ok(());
+        }
+        *window = 0;
+
+        let their_count = *theirs.count.get();
+        let actual = their_count.wrapping_sub(*mine.seen.get());
+        *mine.seen.get() = their_count;
+
+        let expected = theirs.per_window;
+        if actual.abs_diff(expected) > self.tolerance {
+            *mine.diverged.get() = true;
+            Err(Divergence { expected, actual })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Has a divergence ever been flagged?
+    #[inline]
+    pub fn has_diverged(&self) -> bool {
+        // This is safe since the reads are atomic.
+        unsafe { *self.a.diverged.get() || *self.b.diverged.get() }
+    }
+}
+
+impl core::fmt::Debug for CrossCheck {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CrossCheck")
+            .field("tolerance", &self.tolerance)
+            .field("diverged", &self.has_diverged())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::*;
+
+    use core::cell::Cell;
+
+    #[test]
+    fn kicks_every_n() {
+        let kicks = Cell::new(0);
+        let mut heartbeat = Heartbeat::new(3, || kicks.set(kicks.get() + 1));
+
+        assert_eq!(false, heartbeat.beat());
+        assert_eq!(false, heartbeat.beat());
+        assert_eq!(true, heartbeat.beat());
+        assert_eq!(1, kicks.get());
+
+        for _ in 0..6 {
+            heartbeat.beat();
+        }
+        assert_eq!(3, kicks.get());
+    }
+
+    #[test]
+    fn failed_polls_dont_count() {
+        let kicks = Cell::new(0);
+        let mut heartbeat = Heartbeat::new(2, || kicks.set(kicks.get() + 1));
+
+        assert_eq!(Ok(1), heartbeat.track::<_, ()>(Ok(1)));
+        assert_eq!(Err(()), heartbeat.track::<u8, _>(Err(())));
+        assert_eq!(Err(()), heartbeat.track::<u8, _>(Err(())));
+        assert_eq!(0, kicks.get());
+
+        assert_eq!(Ok(2), heartbeat.track::<_, ()>(Ok(2)));
+        assert_eq!(1, kicks.get());
+    }
+
+    #[test]
+    fn zero_kicks_every_poll() {
+        let kicks = Cell::new(0);
+        let mut heartbeat = Heartbeat::new(0, || kicks.set(kicks.get() + 1));
+
+        heartbeat.beat();
+        heartbeat.beat();
+        assert_eq!(2, kicks.get());
+    }
+
+    #[test]
+    fn cross_check_in_step() {
+        let check = CrossCheck::new(4, 2, 1);
+
+        // It is always safe to beat a stack-scoped CrossCheck.
+        unsafe {
+            for _ in 0..10 {
+                assert_eq!(Ok(()), check.heartbeat(Side::A));
+                assert_eq!(Ok(()), check.heartbeat(Side::A));
+                assert_eq!(Ok(()), check.heartbeat(Side::B));
+            }
+        }
+        assert_eq!(false, check.has_diverged());
+    }
+
+    #[test]
+    fn cross_check_dead_timer() {
+        let check = CrossCheck::new(4, 2, 1);
+
+        // It is always safe to beat a stack-scoped CrossCheck.
+        unsafe {
+            for _ in 0..2 {
+                check.heartbeat(Side::A).unwrap();
+                check.heartbeat(Side::A).unwrap();
+                check.heartbeat(Side::B).unwrap();
+            }
+
+            // Side B stops beating.
+            for _ in 0..7 {
+                assert_eq!(Ok(()), check.heartbeat(Side::A));
+            }
+            assert_eq!(false, check.has_diverged());
+            assert_eq!(
+                Err(Divergence {
+                    expected: 2,
+                    actual: 0
+                }),
+                check.heartbeat(Side::A)
+            );
+        }
+        assert_eq!(true, check.has_diverged());
+    }
+}
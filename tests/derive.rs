@@ -0,0 +1,56 @@
+//! Check the configurations generated by `#[derive(Debounce)]`.
+
+#![cfg(feature = "derive")]
+#![allow(clippy::bool_assert_comparison)]
+
+use unflappable::{debouncer_uninit, Debounce, Debouncer, Edge, Level};
+
+#[derive(Debounce)]
+#[debounce(max_count = 2)]
+struct Defaults;
+
+#[derive(Debounce)]
+#[debounce(storage = u16, max_count = 1000, init_high)]
+struct Slow;
+
+#[derive(Debounce)]
+#[debounce(max_count = 3, init_high = false)]
+struct Explicit;
+
+#[derive(Debounce)]
+#[debounce(debounce_ms = 30, poll_hz = 200, init_high)]
+struct Timed;
+
+#[test]
+fn derived_constants() {
+    assert_eq!(2u8, Defaults::MAX_COUNT);
+    assert_eq!(false, Defaults::INIT_HIGH);
+
+    assert_eq!(1000u16, Slow::MAX_COUNT);
+    assert_eq!(true, Slow::INIT_HIGH);
+
+    assert_eq!(3u8, Explicit::MAX_COUNT);
+    assert_eq!(false, Explicit::INIT_HIGH);
+
+    assert_eq!(6u8, Timed::MAX_COUNT);
+    assert_eq!(true, Timed::INIT_HIGH);
+}
+
+#[test]
+fn derived_debouncer() {
+    let debouncer: Debouncer<(), Defaults> = debouncer_uninit!();
+    // It is always safe to init a stack-scoped Debouncer.
+    let debounced = unsafe { debouncer.init(()) }.unwrap();
+
+    // It is always safe to poll a stack-scoped Debouncer.
+    assert_eq!(
+        Edge::NoChange,
+        unsafe { debouncer.poll_with(true) }.unwrap()
+    );
+    // It is always safe to poll a stack-scoped Debouncer.
+    assert_eq!(Edge::Rising, unsafe { debouncer.poll_with(true) }.unwrap());
+    assert_eq!(Level::High, debounced.state());
+
+    // It is always safe to deinit a stack-scoped Debouncer.
+    unsafe { debouncer.deinit(debounced) }.unwrap();
+}
@@ -0,0 +1,127 @@
+//! Check that the crate never allocates.
+//!
+//! The crate itself is `#![no_std]` and doesn't link `alloc`, but that
+//! alone doesn't prove that nothing reachable from its API allocates.
+//! This test installs a global allocator that refuses to allocate while
+//! the public types are exercised, and aborts the test if anything
+//! tries.  It runs without the test harness, since the harness itself
+//! allocates freely.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use embedded_hal::digital::v2::InputPin;
+
+use unflappable::analog::{Band, Ladder};
+use unflappable::bank::DebouncerBank;
+use unflappable::default::ActiveLow;
+use unflappable::differential::Differential;
+use unflappable::encoder::{EncoderWithButton, Quadrature};
+use unflappable::event::EventDebouncer;
+use unflappable::fault::{FaultPolicy, Supervised};
+use unflappable::gesture::{GestureTiming, LongPress};
+use unflappable::{debouncer_uninit, Debouncer, Level};
+
+static FORBIDDEN: AtomicBool = AtomicBool::new(false);
+
+struct Forbid;
+
+unsafe impl GlobalAlloc for Forbid {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if FORBIDDEN.load(Ordering::SeqCst) {
+            // Printing would allocate, so just bail out loudly.
+            std::process::abort();
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Forbid = Forbid;
+
+struct Pin<'a>(&'a Cell<bool>);
+
+impl<'a> InputPin for Pin<'a> {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.0.get())
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(!self.0.get())
+    }
+}
+
+fn exercise() {
+    let level = Cell::new(true);
+    let other = Cell::new(false);
+
+    let debouncer: Debouncer<_, ActiveLow> = debouncer_uninit!();
+    // It is always safe to init a stack-scoped Debouncer.
+    let debounced = unsafe { debouncer.init(Pin(&level)) }.unwrap();
+    for high in [false, false, true, false, false, false, false, true] {
+        level.set(high);
+        // It is always safe to poll a stack-scoped Debouncer.
+        unsafe { debouncer.poll() }.unwrap();
+    }
+    let _ = debounced.state();
+
+    let mut encoder = EncoderWithButton::new(Pin(&level), Pin(&other), debounced);
+    for _ in 0..4 {
+        level.set(!level.get());
+        encoder.poll().unwrap();
+    }
+    let (_, _, debounced) = encoder.release();
+    // It is always safe to deinit a stack-scoped Debouncer.
+    unsafe { debouncer.deinit(debounced) }.unwrap();
+
+    let mut quadrature = Quadrature::new();
+    quadrature.update(true, false);
+
+    let mut ladder = Ladder::new([Band::new(0, 10, 'a'), Band::new(20, 30, 'b')], 2);
+    for reading in [5, 5, 25, 25, 25, 100, 100] {
+        ladder.update(reading);
+    }
+
+    let bank: DebouncerBank<12> = DebouncerBank::new(0xfff);
+    for sample in [0xffe, 0xffe, 0xffe, 0xffe] {
+        // It is always safe to update a stack-scoped DebouncerBank.
+        unsafe { bank.update(sample) };
+    }
+    let _ = bank.pin(0).is_low();
+
+    let mut events: EventDebouncer<u8, 4> = EventDebouncer::new(10);
+    for now in 0..20 {
+        events.accept((now % 3) as u8, now);
+    }
+
+    let mut long_press = LongPress::new(GestureTiming::default());
+    for _ in 0..100 {
+        long_press.update(true);
+    }
+    long_press.hold_progress();
+
+    let differential = Differential::new(Pin(&level), Pin(&other));
+    let _ = differential.is_high();
+
+    let supervised = Supervised::new(
+        Pin(&level),
+        FaultPolicy::new(3, Level::Low).reprobe_every(10),
+    );
+    let _ = supervised.is_high();
+}
+
+fn main() {
+    FORBIDDEN.store(true, Ordering::SeqCst);
+    exercise();
+    FORBIDDEN.store(false, Ordering::SeqCst);
+
+    println!("no allocations");
+}
@@ -0,0 +1,158 @@
+//! Check features that firmware stacks together.
+//!
+//! Each layer is tested on its own in its module.  These tests stack
+//! them the way an application would, to catch layers that step on
+//! each other, such as an event seen by one layer but lost to another.
+
+#![allow(clippy::bool_assert_comparison)]
+
+#[cfg(all(feature = "heapless", feature = "critical-section"))]
+#[test]
+fn bank_queue_long_press() {
+    use heapless::spsc::Queue;
+    use unflappable::bank::{BankEdges, DebouncerBank};
+    use unflappable::gesture::{BankGesture, BankGestures, GestureTiming};
+    use unflappable::queue::Drain;
+
+    static BANK: DebouncerBank<4> = DebouncerBank::new(0xf);
+
+    let mut queue: Queue<BankEdges, 8> = Queue::new();
+    let (mut producer, mut consumer) = queue.split();
+    let timing = GestureTiming {
+        long_press: 10,
+        ..GestureTiming::default()
+    };
+    let mut gestures = BankGestures::new(timing, [0], []);
+    let mut recognized = Vec::new();
+
+    // Hold button 0, and tap button 1 while it's held, all without the
+    // main loop draining the queue.
+    let mut samples = Vec::new();
+    samples.extend([0b1110; 4]);
+    samples.extend([0b1100; 4]);
+    samples.extend([0b1110; 4]);
+    samples.extend([0b1111; 4]);
+
+    for &sample in samples.iter() {
+        // The test is the only code using the bank.
+        let edges = unsafe { BANK.update(sample) };
+        if edges.any() {
+            producer.enqueue(edges).unwrap();
+        }
+        gestures.update(!BANK.snapshot() & 0xf, |gesture| recognized.push(gesture));
+    }
+
+    let mut drained = Vec::new();
+    assert_eq!(4, consumer.drain_events(8, |edges| drained.push(edges)));
+    assert_eq!(
+        [
+            BankEdges {
+                rising: 0,
+                falling: 0b01,
+            },
+            BankEdges {
+                rising: 0,
+                falling: 0b10,
+            },
+            BankEdges {
+                rising: 0b10,
+                falling: 0,
+            },
+            BankEdges {
+                rising: 0b01,
+                falling: 0,
+            },
+        ],
+        drained[..]
+    );
+
+    // The long press is only seen by the gesture layer, and the edges
+    // it saw match those queued.
+    assert_eq!(
+        [
+            BankGesture::Pressed(0),
+            BankGesture::Pressed(1),
+            BankGesture::Released(1),
+            BankGesture::LongPress(0),
+            BankGesture::Released(0),
+        ],
+        recognized[..]
+    );
+}
+
+#[cfg(all(feature = "observer", feature = "stats", feature = "heapless"))]
+#[test]
+fn observer_stats_queue() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use unflappable::queue::EdgeQueue;
+    use unflappable::{debouncer_uninit, default::ActiveLow, Debouncer, Observer};
+
+    static DEBOUNCER: Debouncer<(), ActiveLow> = debouncer_uninit!();
+    static OBSERVED: AtomicUsize = AtomicUsize::new(0);
+
+    fn observe() {
+        OBSERVED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let mut queue: EdgeQueue<16> = EdgeQueue::new();
+    let (mut producer, consumer) = queue.split();
+
+    // The test is the only code using the debouncer.
+    let debounced = unsafe {
+        DEBOUNCER.init_observed(
+            (),
+            Observer {
+                on_rise: observe,
+                on_fall: observe,
+            },
+        )
+    }
+    .unwrap();
+
+    // Three presses, with some bounce.
+    let mut samples = Vec::new();
+    for _ in 0..3 {
+        samples.extend([false, true, false, false, false, false]);
+        samples.extend([true, false, true, true, true, true]);
+    }
+
+    for &sample in samples.iter() {
+        // The test is the only code using the debouncer.
+        let edge = unsafe { DEBOUNCER.poll_with(sample) }.unwrap();
+        if edge != unflappable::Edge::NoChange {
+            producer.enqueue(edge).unwrap();
+        }
+    }
+
+    // Every layer saw every edge, once.
+    let stats = DEBOUNCER.stats();
+    assert_eq!(6, stats.transitions);
+    assert_eq!(6, OBSERVED.load(Ordering::SeqCst));
+    assert_eq!(6, consumer.len());
+    assert_eq!(
+        samples.windows(2).filter(|w| w[0] != w[1]).count() as u32 + 1,
+        stats.flips
+    );
+
+    // The test is the only code using the debouncer.
+    unsafe { DEBOUNCER.deinit(debounced) }.unwrap();
+}
+
+// The events and reports that an application logs are all loggable.
+#[cfg(feature = "defmt")]
+#[test]
+fn events_are_format() {
+    fn is_format<T: defmt::Format>() {}
+
+    is_format::<unflappable::Edge>();
+    is_format::<unflappable::Activity>();
+    is_format::<unflappable::Level>();
+    is_format::<unflappable::Status<u8>>();
+    is_format::<unflappable::bank::BankEdges>();
+    is_format::<unflappable::gesture::BankGesture>();
+    is_format::<unflappable::snapshot::StateSnapshot>();
+    is_format::<unflappable::time::Timestamped>();
+    #[cfg(feature = "stats")]
+    is_format::<unflappable::Stats>();
+}
@@ -0,0 +1,151 @@
+//! Derive macro for [`unflappable`][0] debounce configurations.
+//!
+//! Don't depend on this crate directly, instead enable the `derive`
+//! feature of `unflappable`, which re-exports the macro.
+//!
+//! [0]: https://docs.rs/unflappable
+
+#![deny(missing_docs)]
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, Error, Expr, ExprLit, Ident, Lit, LitBool, Type};
+
+/// Derive the `Debounce` trait from a `#[debounce(...)]` attribute.
+///
+/// See the documentation of `unflappable::Debounce` for the details.
+#[proc_macro_derive(Debounce, attributes(debounce))]
+pub fn derive_debounce(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream, Error> {
+    let mut storage: Option<Type> = None;
+    let mut max_count: Option<(u128, Span)> = None;
+    let mut debounce_ms: Option<(u128, Span)> = None;
+    let mut poll_hz: Option<(u128, Span)> = None;
+    let mut init_high = false;
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("debounce"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("storage") {
+                storage = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("max_count") {
+                max_count = Some(parse_int(&meta)?);
+            } else if meta.path.is_ident("debounce_ms") {
+                debounce_ms = Some(parse_int(&meta)?);
+            } else if meta.path.is_ident("poll_hz") {
+                poll_hz = Some(parse_int(&meta)?);
+            } else if meta.path.is_ident("init_high") {
+                init_high = if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<LitBool>()?.value
+                } else {
+                    true
+                };
+            } else {
+                return Err(meta.error(
+                    "expected `storage`, `max_count`, `debounce_ms`, `poll_hz` or `init_high`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+
+    let (max_count, max_count_span) = match (max_count, debounce_ms, poll_hz) {
+        (Some(max_count), None, None) => max_count,
+        (None, Some((debounce_ms, span)), Some((poll_hz, _))) => {
+            // Round up, so the debounce takes at least as long as asked.
+            let product = debounce_ms
+                .checked_mul(poll_hz)
+                .ok_or_else(|| Error::new(span, "debounce time too long"))?;
+            let count = product / 1_000;
+            let count = if count * 1_000 < product {
+                count + 1
+            } else {
+                count
+            };
+            (count, span)
+        }
+        (Some((_, span)), _, _) => {
+            return Err(Error::new(
+                span,
+                "give either `max_count`, or `debounce_ms` and `poll_hz`, not both",
+            ))
+        }
+        (None, Some((_, span)), None) | (None, None, Some((_, span))) => {
+            return Err(Error::new(
+                span,
+                "`debounce_ms` and `poll_hz` must be given together",
+            ))
+        }
+        (None, None, None) => {
+            return Err(Error::new(
+                Span::call_site(),
+                "missing `#[debounce(max_count = ...)]` attribute",
+            ))
+        }
+    };
+    let storage = storage.unwrap_or_else(|| syn::parse_quote!(u8));
+
+    let bits = match &storage {
+        Type::Path(path) if path.qself.is_none() => {
+            match path.path.get_ident().map(Ident::to_string).as_deref() {
+                Some("u8") => 8,
+                Some("u16") => 16,
+                Some("u32") => 32,
+                Some("u64") => 64,
+                Some("u128") => 128,
+                _ => 0,
+            }
+        }
+        _ => 0,
+    };
+    if bits == 0 {
+        return Err(Error::new_spanned(
+            storage,
+            "storage must be one of `u8`, `u16`, `u32`, `u64` or `u128`",
+        ));
+    }
+
+    if max_count == 0 {
+        return Err(Error::new(max_count_span, "max_count cannot be zero"));
+    }
+    if max_count >> (bits - 2) != 0 {
+        return Err(Error::new(
+            max_count_span,
+            "max_count must be represented in two bits fewer than the storage",
+        ));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let max_count = proc_macro2::Literal::u128_unsuffixed(max_count);
+
+    Ok(quote! {
+        impl #impl_generics ::unflappable::Debounce for #name #ty_generics #where_clause {
+            type Storage = #storage;
+
+            const MAX_COUNT: Self::Storage = #max_count;
+
+            const INIT_HIGH: bool = #init_high;
+        }
+    })
+}
+
+fn parse_int(meta: &syn::meta::ParseNestedMeta) -> Result<(u128, Span), Error> {
+    let expr: Expr = meta.value()?.parse()?;
+    let value = match &expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.base10_parse()?,
+        _ => return Err(Error::new_spanned(expr, "expected an integer literal")),
+    };
+    Ok((value, meta.path.get_ident().unwrap().span()))
+}